@@ -0,0 +1,149 @@
+use crate::prelude::*;
+use bevy::prelude::*;
+
+/// Represents the health points of an actor, which dies once they drop to 0.
+#[derive(Clone, Component, Copy, Debug, Eq, PartialEq)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    /// Creates a `Health` full of a given number of points.
+    pub const fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Removes a given number of points, down to 0.
+    pub const fn take_damage(&mut self, damage: u32) {
+        self.current = self.current.saturating_sub(damage);
+    }
+
+    pub const fn is_dead(self) -> bool {
+        self.current == 0
+    }
+}
+
+/// Represents the stats used when an actor attacks another one (see
+/// `CombatStats::roll_damage`).
+#[derive(Clone, Component, Copy, Debug, Eq, PartialEq)]
+pub struct CombatStats {
+    /// The maximum damage dealt by the actor's attacks.
+    pub attack: u32,
+    /// The damage absorbed by the actor when attacked.
+    pub defense: u32,
+}
+
+impl CombatStats {
+    /// Rolls the damage dealt by an attack against a defender. The attack
+    /// roll is between half the attack (rounded up) and the attack, and the
+    /// defender's defense is subtracted from it.
+    pub fn roll_damage(self, defender: Self, rng: &mut impl Rng) -> u32 {
+        let roll = rng.gen_range(self.attack.div_ceil(2)..=self.attack);
+        roll.saturating_sub(defender.defense)
+    }
+}
+
+/// Represents an attack of the actor standing at `attacker` against the actor
+/// standing at `target`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Attack {
+    pub attacker: MapPosition,
+    pub target: MapPosition,
+}
+
+/// Represents the attacks declared during a turn, resolved by
+/// `resolve_attacks` at the beginning of the next one.
+#[derive(Default, Resource)]
+pub struct PendingAttacks(pub Vec<Attack>);
+
+/// Resolves the pending attacks in the order they were declared. The mobs
+/// killed are despawned and removed from the current map, and the actors
+/// killed before their turn don't attack. The player stays on the map with
+/// no health left, and the `CauseOfDeath` is recorded for the game over
+/// screen.
+pub fn resolve_attacks(
+    mut commands: Commands,
+    mut attacks: ResMut<PendingAttacks>,
+    mut q_map: Query<&mut Map, With<OnDisplay>>,
+    mut q_actors: Query<
+        (Entity, &MapPosition, &Actor, &CombatStats, &mut Health),
+        With<OnDisplay>,
+    >,
+    mut rng: ResMut<RunRng>,
+) {
+    let Ok(mut map) = q_map.get_single_mut() else {
+        return;
+    };
+
+    for attack in attacks.0.drain(..) {
+        let Some((attacker, stats)) = q_actors
+            .iter()
+            .find(|(_, pos, _, _, health)| {
+                **pos == attack.attacker && !health.is_dead()
+            })
+            .map(|(_, _, actor, stats, _)| (*actor, *stats))
+        else {
+            continue;
+        };
+        let Some((entity, pos, actor, defense, mut health)) =
+            q_actors.iter_mut().find(|(_, pos, _, _, health)| {
+                **pos == attack.target && !health.is_dead()
+            })
+        else {
+            continue;
+        };
+
+        health.take_damage(stats.roll_damage(*defense, &mut rng.combat));
+        if health.is_dead() && actor.is_player() {
+            commands.insert_resource(CauseOfDeath(format!(
+                "Killed by a {}",
+                attacker.kind.name()
+            )));
+        } else if health.is_dead() {
+            if let Ok(index) = map.as_tile_index(pos) {
+                map.tiles[index].actor = None;
+            }
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_take_damage() {
+        let mut health = Health::new(5);
+        health.take_damage(3);
+        assert_eq!(2, health.current);
+        assert!(!health.is_dead());
+
+        health.take_damage(3);
+        assert_eq!(0, health.current);
+        assert!(health.is_dead());
+    }
+
+    #[test]
+    fn test_roll_damage() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let attacker = CombatStats {
+            attack: 5,
+            defense: 0,
+        };
+        let defender = CombatStats {
+            attack: 0,
+            defense: 1,
+        };
+
+        for _ in 0..100 {
+            let damage = attacker.roll_damage(defender, &mut rng);
+            assert!((2..=4).contains(&damage), "{damage}");
+        }
+        // the defense absorbs the whole attack, and no attack deals no damage
+        assert_eq!(0, defender.roll_damage(attacker, &mut rng));
+        assert_eq!(0, defender.roll_damage(defender, &mut rng));
+    }
+}
@@ -14,3 +14,25 @@ pub const TILESET_ACTOR_IDX_PLAYER: usize = 0;
 pub const TILESET_ACTOR_IDX_RABBIT: usize = 1;
 
 pub const TILESET_ACTOR_IDX_BLOB: usize = 2;
+
+/// The health points of the player at the beginning of a run.
+pub const PLAYER_HEALTH: u32 = 20;
+pub const PLAYER_ATTACK: u32 = 5;
+pub const PLAYER_DEFENSE: u32 = 1;
+
+pub const BLOB_HEALTH: u32 = 8;
+pub const BLOB_ATTACK: u32 = 4;
+pub const BLOB_DEFENSE: u32 = 0;
+
+pub const RABBIT_HEALTH: u32 = 3;
+pub const RABBIT_ATTACK: u32 = 1;
+pub const RABBIT_DEFENSE: u32 = 0;
+
+/// The energy spent by an actor for acting (see `Energy`).
+pub const ENERGY_ACTION_COST: u32 = 100;
+
+/// The energy gained by the actors every tick: the player acts every tick,
+/// the rabbits twice, and the blobs every two ticks.
+pub const PLAYER_SPEED: u32 = 100;
+pub const BLOB_SPEED: u32 = 50;
+pub const RABBIT_SPEED: u32 = 200;
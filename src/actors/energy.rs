@@ -0,0 +1,143 @@
+use crate::prelude::*;
+
+/// Represents the energy accumulated by an actor. Every tick, the actors gain
+/// as much energy as their speed (see `ActorKind::speed`), and acting costs
+/// `ENERGY_ACTION_COST`, so the fast actors act several times per tick while
+/// the slow ones wait for a few ticks.
+#[derive(
+    Clone, Component, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd,
+)]
+pub struct Energy(pub u32);
+
+impl Energy {
+    /// Returns whether the actor has enough energy to act.
+    pub const fn can_act(self) -> bool {
+        self.0 >= ENERGY_ACTION_COST
+    }
+
+    /// Spends the energy needed for acting.
+    pub const fn spend(&mut self) {
+        self.0 = self.0.saturating_sub(ENERGY_ACTION_COST);
+    }
+}
+
+/// Represents an actor waiting for its turn (see `schedule_turns`).
+#[derive(Clone, Copy, Debug)]
+pub struct ScheduledActor {
+    /// The energy gained every tick.
+    pub speed: u32,
+    pub energy: Energy,
+}
+
+/// Lets the actors act, one at a time, until the player can act again.
+///
+/// The next actor is the one with the most energy among those having enough
+/// to act. The ties are broken by the order of the actors, the first one
+/// acting first, so the order is always the same for the same actors. Once
+/// no actor can act, a tick elapses and every actor gains energy.
+///
+/// # Arguments
+///
+/// - `actors`: The actors, whose energy is spent or gained in place.
+/// - `player`: The index of the player in `actors`.
+/// - `act`: Performs the action of the actor at a given index.
+///
+/// # Returns
+///
+/// The number of ticks elapsed.
+pub fn schedule_turns(
+    actors: &mut [ScheduledActor],
+    player: usize,
+    mut act: impl FnMut(usize),
+) -> usize {
+    assert!(
+        actors.get(player).is_some_and(|actor| actor.speed > 0),
+        "the player never acts"
+    );
+
+    let mut ticks = 0;
+    loop {
+        let next = actors
+            .iter()
+            .enumerate()
+            .filter(|(_, actor)| actor.energy.can_act())
+            .max_by(|(i, a), (j, b)| a.energy.cmp(&b.energy).then(j.cmp(i)))
+            .map(|(index, _)| index);
+
+        let Some(index) = next else {
+            for actor in actors.iter_mut() {
+                actor.energy.0 += actor.speed;
+            }
+            ticks += 1;
+            continue;
+        };
+
+        actors[index].energy.spend();
+        if index == player {
+            return ticks;
+        }
+        act(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled(kind: ActorKind) -> ScheduledActor {
+        ScheduledActor {
+            speed: kind.speed(),
+            energy: Energy::default(),
+        }
+    }
+
+    #[test]
+    fn test_spend_energy() {
+        let mut energy = Energy(ENERGY_ACTION_COST + 1);
+        assert!(energy.can_act());
+        energy.spend();
+        assert_eq!(Energy(1), energy);
+        assert!(!energy.can_act());
+        energy.spend();
+        assert_eq!(Energy(0), energy);
+    }
+
+    #[test]
+    fn test_schedule_turns_by_speed() {
+        let mut actors = [
+            scheduled(ActorKind::Blob),
+            scheduled(ActorKind::Rabbit),
+            scheduled(ActorKind::Player),
+        ];
+
+        // the rabbit acts twice per player's turn, the blob every two turns
+        let mut turns = vec![];
+        for _ in 0..4 {
+            let mut actions = vec![];
+            let ticks =
+                schedule_turns(&mut actors, 2, |index| actions.push(index));
+            assert_eq!(1, ticks);
+            turns.push(actions);
+        }
+        assert_eq!(
+            vec![vec![1, 1], vec![1, 0, 1], vec![1, 1], vec![1, 0, 1]],
+            turns
+        );
+    }
+
+    #[test]
+    fn test_schedule_turns_ties() {
+        // the actors with as much energy act in their order, before the
+        // player placed last
+        let mut actors = [scheduled(ActorKind::Rabbit); 3];
+        actors[2] = scheduled(ActorKind::Player);
+        actors[0].energy = Energy(ENERGY_ACTION_COST);
+        actors[1].energy = Energy(ENERGY_ACTION_COST);
+        actors[2].energy = Energy(ENERGY_ACTION_COST);
+
+        let mut actions = vec![];
+        let ticks = schedule_turns(&mut actors, 2, |index| actions.push(index));
+        assert_eq!(0, ticks);
+        assert_eq!(vec![0, 1], actions);
+    }
+}
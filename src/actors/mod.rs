@@ -1,26 +1,39 @@
+mod combat;
 mod constants;
+mod energy;
 
+pub use combat::*;
 pub use constants::*;
+pub use energy::*;
 
 use crate::prelude::*;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub struct ActorsPlugin;
 
 impl Plugin for ActorsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::InitializingActors),
-            spawn_mobs_on_current_map.run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(
-            OnEnter(GameState::CleanupActors),
-            despawn_mobs_on_current_map.run_if(in_state(AppState::InGame)),
-        )
-        .add_systems(OnEnter(GameState::PlayerTurn), update_actor_sprites)
-        .add_systems(OnEnter(GameState::EnemyTurn), update_actor_sprites)
-        .add_systems(OnEnter(GameState::EnemyTurn), increase_game_turn);
+        app.init_resource::<PendingAttacks>()
+            .add_systems(
+                OnEnter(GameState::InitializingActors),
+                spawn_mobs_on_current_map.run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                OnEnter(GameState::CleanupActors),
+                despawn_mobs_on_current_map.run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                OnEnter(GameState::PlayerTurn),
+                resolve_attacks.before(update_actor_sprites),
+            )
+            .add_systems(
+                OnEnter(GameState::EnemyTurn),
+                resolve_attacks.before(move_mob),
+            )
+            .add_systems(OnEnter(GameState::PlayerTurn), update_actor_sprites)
+            .add_systems(OnEnter(GameState::EnemyTurn), update_actor_sprites);
     }
 }
 
@@ -57,9 +70,91 @@ impl Actor {
     pub fn is_neutral(&self) -> bool {
         self.hostility == ActorHostility::Neutral
     }
+
+    /// Returns whether the actor can damage the destructible tiles in its way
+    /// (see `Map::damage_tile`).
+    pub const fn can_dig(self) -> bool {
+        matches!(self.kind, ActorKind::Player | ActorKind::Blob)
+    }
+
+    /// Returns whether the actor can open doors (see `Map::open_door`). Only
+    /// the player carries keys, so the hostile mobs open the unlocked doors.
+    pub fn can_open_doors(self) -> bool {
+        self.is_player() || self.is_hostile()
+    }
+
+    /// Returns whether the actor can walk on the tiles of a given kind, e.g.
+    /// blobs can't cross water.
+    pub fn can_cross(self, registry: &TileRegistry, kind: TileKind) -> bool {
+        !(self.kind == ActorKind::Blob
+            && kind.has_tag(registry, TILE_TAG_WATER))
+    }
+
+    /// Returns whether the actor is hidden when standing on the tiles of a
+    /// given kind, e.g. rabbits hide in tall grass.
+    pub fn hides_in(self, registry: &TileRegistry, kind: TileKind) -> bool {
+        self.kind == ActorKind::Rabbit && kind.has_tag(registry, TILE_TAG_COVER)
+    }
 }
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+/// Represents the items carried by an actor.
+#[derive(Clone, Component, Debug, Default)]
+pub struct Inventory(pub Vec<ItemKind>);
+
+impl Inventory {
+    /// Removes an item of a given kind from the inventory, e.g. a key used on
+    /// a locked door.
+    ///
+    /// # Returns
+    ///
+    /// Whether the inventory had such an item.
+    pub fn take(&mut self, kind: ItemKind) -> bool {
+        let Some(index) = self.0.iter().position(|item| *item == kind) else {
+            return false;
+        };
+        self.0.remove(index);
+        true
+    }
+}
+
+/// Represents the number of turns spent searching for traps in a row (see
+/// `Map::search_traps`), reset when the actor does anything else.
+#[derive(Clone, Component, Copy, Default)]
+pub struct SearchStreak(pub u32);
+
+/// Represents the number of turns an actor must wait before acting again,
+/// after walking on a tile with a movement cost above 1.
+#[derive(Clone, Component, Copy, Default)]
+pub struct MovementCooldown(pub u32);
+
+impl MovementCooldown {
+    /// Returns the cooldown after walking on the tile at a given position.
+    pub fn after_moving_to(
+        map: &Map,
+        registry: &TileRegistry,
+        pos: &MapPosition,
+    ) -> Self {
+        let cost = map
+            .as_tile_index(pos)
+            .map_or(1, |index| map.tiles[index].kind.movement_cost(registry));
+        Self(cost.saturating_sub(1))
+    }
+
+    /// Spends a turn of the cooldown.
+    ///
+    /// # Returns
+    ///
+    /// Whether the actor was waiting, i.e. can't act this turn.
+    pub const fn wait(&mut self) -> bool {
+        if self.0 == 0 {
+            return false;
+        }
+        self.0 -= 1;
+        true
+    }
+}
+
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ActorKind {
     Blob,
     Rabbit,
@@ -67,6 +162,15 @@ pub enum ActorKind {
 }
 
 impl ActorKind {
+    /// Returns the name of the actors of this kind, e.g. in the cause of death.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Blob => "blob",
+            Self::Rabbit => "rabbit",
+            Self::Player => "player",
+        }
+    }
+
     pub fn get_hostility(&self) -> ActorHostility {
         match self {
             ActorKind::Blob => ActorHostility::Enemy,
@@ -74,6 +178,35 @@ impl ActorKind {
             ActorKind::Player => ActorHostility::Neutral,
         }
     }
+
+    /// Returns the health of a newly spawned actor of this kind.
+    pub const fn health(self) -> Health {
+        Health::new(match self {
+            Self::Blob => BLOB_HEALTH,
+            Self::Rabbit => RABBIT_HEALTH,
+            Self::Player => PLAYER_HEALTH,
+        })
+    }
+
+    /// Returns the combat stats of the actors of this kind.
+    pub const fn combat_stats(self) -> CombatStats {
+        let (attack, defense) = match self {
+            Self::Blob => (BLOB_ATTACK, BLOB_DEFENSE),
+            Self::Rabbit => (RABBIT_ATTACK, RABBIT_DEFENSE),
+            Self::Player => (PLAYER_ATTACK, PLAYER_DEFENSE),
+        };
+        CombatStats { attack, defense }
+    }
+
+    /// Returns the energy gained every tick by the actors of this kind (see
+    /// `Energy`).
+    pub const fn speed(self) -> u32 {
+        match self {
+            Self::Blob => BLOB_SPEED,
+            Self::Rabbit => RABBIT_SPEED,
+            Self::Player => PLAYER_SPEED,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
@@ -89,6 +222,18 @@ pub struct ActorBundle {
     pub actor: Actor,
     /// The map's position where the actor is at.
     pub map_position: MapPosition,
+    /// The turns left before the actor can act again.
+    pub cooldown: MovementCooldown,
+    /// The items carried by the actor.
+    pub inventory: Inventory,
+    /// The turns spent searching in a row.
+    pub search_streak: SearchStreak,
+    /// The health points left.
+    pub health: Health,
+    /// The attack and defense of the actor.
+    pub combat_stats: CombatStats,
+    /// The energy accumulated for acting.
+    pub energy: Energy,
     /// The sprite representing the actor.
     pub sprite: SpriteSheetBundle,
 }
@@ -103,6 +248,12 @@ impl ActorBundle {
         Self {
             actor: actor.clone(),
             map_position,
+            cooldown: MovementCooldown::default(),
+            inventory: Inventory::default(),
+            search_streak: SearchStreak::default(),
+            health: actor.kind.health(),
+            combat_stats: actor.kind.combat_stats(),
+            energy: Energy::default(),
             sprite: SpriteSheetBundle {
                 atlas: TextureAtlas {
                     layout: tileset.0.clone(),
@@ -117,82 +268,161 @@ impl ActorBundle {
     }
 }
 
-/// Despawn mob entities on the current map.
+/// Components of the mobs stored when they are despawned (see `StoredMob`).
+type StoredMobComponents<'a> = (
+    Entity,
+    &'a Actor,
+    &'a MapPosition,
+    &'a Health,
+    &'a MovementCooldown,
+    &'a Energy,
+);
+
+/// Despawn mob entities on the current map. The mobs are stored in the
+/// `WorldGraph` beforehand, so they are restored when the player comes back.
 pub fn despawn_mobs_on_current_map(
     mut commands: Commands,
-    q_actors: Query<(Entity, &Actor), With<OnDisplay>>,
+    q_actors: Query<StoredMobComponents, With<OnDisplay>>,
     mut next_game_state: ResMut<NextState<GameState>>,
+    mut world: ResMut<WorldGraph>,
+    current_map_number: Res<CurrentMapNumber>,
 ) {
-    for (entity, actor) in &q_actors {
+    let mut mobs = vec![];
+    for (entity, actor, position, health, cooldown, energy) in &q_actors {
         if actor.is_player() {
             continue;
         }
+        mobs.push(StoredMob {
+            actor: *actor,
+            position: *position,
+            health: *health,
+            cooldown: *cooldown,
+            energy: *energy,
+        });
         commands.entity(entity).despawn();
     }
+    world.store_mobs(current_map_number.0, mobs);
     next_game_state.set(GameState::CleanupMap);
 }
 
-pub fn generate_spawn_counts(_map: &Map) -> HashMap<ActorKind, usize> {
-    let mut result = HashMap::new();
+pub fn generate_spawn_counts(_map: &Map) -> BTreeMap<ActorKind, usize> {
+    let mut result = BTreeMap::new();
     result.insert(ActorKind::Blob, 3);
     result.insert(ActorKind::Rabbit, 3);
     return result;
 }
 
-/// Spawn mob entities (enemies, NPC...) on the current map.
+/// Resources deciding where the mobs and the player are spawned on the
+/// current map (see `spawn_mobs_on_current_map`).
+#[derive(SystemParam)]
+pub struct MapArrival<'w> {
+    registry: Res<'w, TileRegistry>,
+    world: Res<'w, WorldGraph>,
+    current_map_number: Res<'w, CurrentMapNumber>,
+    last_exit: Option<Res<'w, LastMapExit>>,
+    saved_player: Option<Res<'w, SavedPlayer>>,
+}
+
+/// Spawn mob entities (enemies, NPC...) on the current map. The mobs left on
+/// a map already visited are restored from the `WorldGraph`, and there are no
+/// mobs on the overworld.
+///
+/// The player arrives next to the exit leading back to the previous map, or
+/// on the map's entrance (see `Map::arrival_position`). The player of a saved
+/// run is restored where they were instead (see `SavedPlayer`), and the save
+/// file is removed once the run is restored.
 pub fn spawn_mobs_on_current_map(
     mut commands: Commands,
     mut q_map: Query<&mut Map, With<OnDisplay>>,
     mut q_actors: Query<(&mut MapPosition, &Actor), With<OnDisplay>>,
     tileset: Res<TilesetActor>,
     mut next_game_state: ResMut<NextState<GameState>>,
+    mut rng: ResMut<RunRng>,
+    arrival: MapArrival,
 ) {
+    let MapArrival {
+        registry,
+        world,
+        current_map_number,
+        last_exit,
+        saved_player,
+    } = arrival;
     let mut map = q_map.single_mut();
+    let rng = &mut rng.map;
 
-    let pos_occupied: Vec<MapPosition> =
-        q_actors.iter().map(|(m_p, _)| *m_p).collect();
+    let pos_arrival = saved_player.as_ref().map_or_else(
+        || {
+            map.arrival_position(
+                &registry,
+                last_exit.as_ref().map(|last| last.map),
+            )
+        },
+        |saved| Some(saved.position),
+    );
 
-    let spawn_counts = generate_spawn_counts(&map);
-    let actor_quantity = spawn_counts.values().fold(0, |acc, &x| acc + x);
-    let pos_actors = map
-        .generate_random_positions(actor_quantity, &pos_occupied)
-        .unwrap();
+    // the arrival is kept free for the player
+    let pos_occupied: Vec<MapPosition> = q_actors
+        .iter()
+        .map(|(m_p, _)| *m_p)
+        .chain(pos_arrival)
+        .collect();
 
-    let mut spawned_quantity = 0;
-    for (actor_kind, quantity) in spawn_counts.iter() {
-        spawn_creature(
-            *actor_kind,
+    let pos_actors = if current_map_number.0 == OVERWORLD_MAP_ID {
+        vec![]
+    } else if world.is_visited(current_map_number.0) {
+        let mobs = &world.nodes[current_map_number.0].mobs;
+        for mob in mobs {
+            let entities = spawn_creature(
+                mob.actor.kind,
+                &mut map,
+                &[mob.position],
+                &mut commands,
+                &tileset,
+            )
+            .unwrap();
+            commands.entity(entities[0]).insert((
+                mob.health,
+                mob.cooldown,
+                mob.energy,
+            ));
+        }
+        mobs.iter().map(|mob| mob.position).collect()
+    } else {
+        spawn_new_mobs(
             &mut map,
-            &pos_actors[spawned_quantity..spawned_quantity + quantity],
+            &registry,
+            &pos_occupied,
             &mut commands,
             &tileset,
+            rng,
         )
-        .unwrap();
-        spawned_quantity += quantity;
-    }
+    };
 
     // initialize the player only if there's no player created
     let pos_player = q_actors.iter_mut().filter(|(_, a)| a.is_player()).last();
 
-    // if the player already exists, set a new spawn on the map
-    if let Some(mut pos_player) = pos_player {
-        let pos_new_spawn = map
-            .generate_random_positions(1, &pos_actors)
+    let pos_player_spawn = pos_arrival
+        .filter(|pos| !pos_actors.contains(pos))
+        .unwrap_or_else(|| {
+            map.generate_random_positions(
+                &registry,
+                ActorKind::Player,
+                1,
+                &pos_actors,
+                rng,
+            )
             .expect("failed to initialize player spawn")
             .pop()
-            .unwrap();
-
-        pos_player.0.x = pos_new_spawn.x;
-        pos_player.0.y = pos_new_spawn.y;
-    } else {
-        let pos_player_spawn = map
-            .generate_random_positions(1, &pos_actors)
-            .unwrap()
-            .last()
             .unwrap()
-            .clone();
+        });
 
-        spawn_creature(
+    // if the player already exists, set a new spawn on the map
+    if let Some((mut pos_player, player)) = pos_player {
+        let index = map.as_tile_index(&pos_player_spawn).unwrap();
+        map.tiles[index].actor = Some(*player);
+        *pos_player = pos_player_spawn;
+    } else {
+        let player = spawn_creature(
             ActorKind::Player,
             &mut map,
             &[pos_player_spawn],
@@ -200,18 +430,92 @@ pub fn spawn_mobs_on_current_map(
             &tileset,
         )
         .unwrap();
+        if let Some(saved) = saved_player {
+            commands.entity(player[0]).insert((
+                saved.health,
+                saved.inventory.clone(),
+                saved.cooldown,
+                saved.search_streak,
+                saved.energy,
+            ));
+            commands.remove_resource::<SavedPlayer>();
+            SavedRun::remove();
+        }
     }
     next_game_state.set(GameState::PlayerTurn);
 }
 
+/// Spawns the mobs of a map visited for the first time, on the map's mob
+/// spawns (e.g. from vaults) first, then on random positions.
+///
+/// # Returns
+///
+/// The positions of the spawned mobs.
+fn spawn_new_mobs(
+    map: &mut Map,
+    registry: &TileRegistry,
+    pos_occupied: &[MapPosition],
+    commands: &mut Commands,
+    tileset: &TilesetActor,
+    rng: &mut StdRng,
+) -> Vec<MapPosition> {
+    let spawn_counts = generate_spawn_counts(map);
+
+    let mut mob_spawns: Vec<MapPosition> = map
+        .mob_spawns
+        .iter()
+        .filter(|pos| !pos_occupied.contains(pos))
+        .copied()
+        .collect();
+    mob_spawns.shuffle(rng);
+
+    let mut pos_actors: Vec<MapPosition> = Vec::new();
+    for (&actor_kind, &quantity) in &spawn_counts {
+        let actor = Actor::new(actor_kind);
+        let mut positions: Vec<MapPosition> = Vec::new();
+        mob_spawns.retain(|pos| {
+            let crossable = map.as_tile_index(pos).is_ok_and(|index| {
+                actor.can_cross(registry, map.tiles[index].kind)
+            });
+            if crossable && positions.len() < quantity {
+                positions.push(*pos);
+                false
+            } else {
+                true
+            }
+        });
+
+        let pos_taken = [pos_occupied, &pos_actors, &positions].concat();
+        match map.generate_random_positions(
+            registry,
+            actor_kind,
+            quantity - positions.len(),
+            &pos_taken,
+            rng,
+        ) {
+            Ok(pos_random) => positions.extend(pos_random),
+            Err(e) => warn!("failed to spawn {}s: {e}", actor_kind.name()),
+        }
+
+        spawn_creature(actor_kind, map, &positions, commands, tileset).unwrap();
+        pos_actors.extend(positions);
+    }
+    pos_actors
+}
+
 /// Spawn creatures at specific map positions.
+///
+/// # Returns
+///
+/// The spawned entities, in the order of the positions.
 pub fn spawn_creature(
     actor_kind: ActorKind,
     map: &mut Map,
     positions: &[MapPosition],
     commands: &mut Commands,
     tileset: &TilesetActor,
-) -> Result<(), String> {
+) -> Result<Vec<Entity>, String> {
+    let mut entities = Vec::with_capacity(positions.len());
     for position in positions {
         let tile_pos = map.as_tile_index(position).unwrap();
         if map.tiles[tile_pos].actor.is_some() {
@@ -219,22 +523,101 @@ pub fn spawn_creature(
         }
         let actor = Actor::new(actor_kind);
         map.tiles[tile_pos].actor = Some(actor);
-        commands
-            .spawn((OnDisplay, ActorBundle::new(actor, *position, tileset)));
+        entities.push(
+            commands
+                .spawn((OnDisplay, ActorBundle::new(actor, *position, tileset)))
+                .id(),
+        );
     }
-    Ok(())
+    Ok(entities)
 }
 
 /// Update the sprite position of all actors of the current map according to
-/// their map position.
+/// their map position. The actors hiding on their tile (see `Actor::hides_in`)
+/// or out of the player's `FieldOfView` aren't displayed.
 pub fn update_actor_sprites(
+    q_map: Query<&Map, With<OnDisplay>>,
+    fov: Res<FieldOfView>,
+    registry: Res<TileRegistry>,
     mut q_actors: Query<
-        (&mut Transform, &MapPosition),
-        (With<OnDisplay>, With<Actor>),
+        (&mut Transform, &mut Visibility, &MapPosition, &Actor),
+        With<OnDisplay>,
     >,
 ) {
-    for (mut transform, pos) in &mut q_actors {
+    let map = q_map.get_single().ok();
+    for (mut transform, mut visibility, pos, actor) in &mut q_actors {
         let (x, y) = pos.as_sprite_coordinates();
         transform.translation = Vec3::new(x, y, Z_INDEX_ACTOR);
+
+        let hidden = map
+            .and_then(|map| map.as_tile_index(pos).ok().map(|i| &map.tiles[i]))
+            .is_some_and(|tile| actor.hides_in(&registry, tile.kind))
+            || (!actor.is_player() && !fov.is_visible(pos));
+        *visibility = if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn test_mobs_keep_their_state_on_other_maps() {
+        let map = Map {
+            width: 3,
+            height: 1,
+            tiles: vec![Tile::default(); 3],
+            ..Default::default()
+        };
+        let mut graph = WorldGraph {
+            nodes: vec![WorldNode::default(); 2],
+        };
+        graph.store_map(1, &map);
+        let tileset = TilesetActor(Handle::default(), Handle::default());
+
+        let mut world = World::new();
+        world.insert_resource(graph);
+        world.insert_resource(CurrentMapNumber(1));
+        world.insert_resource(RunRng::from(RunSeed(0)));
+        world.insert_resource(TileRegistry::shipped());
+        world.init_resource::<NextState<GameState>>();
+        let blob = Actor::new(ActorKind::Blob);
+        let wounded = Health {
+            current: 3,
+            max: BLOB_HEALTH,
+        };
+        world
+            .spawn((
+                OnDisplay,
+                ActorBundle::new(blob, MapPosition::new(1, 0), &tileset),
+            ))
+            .insert((wounded, MovementCooldown(2), Energy(50)));
+        world.insert_resource(tileset);
+
+        world.run_system_once(despawn_mobs_on_current_map);
+        assert_eq!(0, world.query::<&Actor>().iter(&world).count());
+
+        world.spawn((OnDisplay, map));
+        world.run_system_once(spawn_mobs_on_current_map);
+        let (_, position, health, cooldown, energy) = world
+            .query::<(
+                &Actor,
+                &MapPosition,
+                &Health,
+                &MovementCooldown,
+                &Energy,
+            )>()
+            .iter(&world)
+            .find(|(actor, _, _, _, _)| !actor.is_player())
+            .unwrap();
+        assert_eq!(MapPosition::new(1, 0), *position);
+        assert_eq!(wounded, *health);
+        assert_eq!(2, cooldown.0);
+        assert_eq!(Energy(50), *energy);
     }
 }
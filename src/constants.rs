@@ -6,6 +6,16 @@ pub const WINDOW_WIDTH: f32 = 600.0;
 /// The window's height in pixels.
 pub const WINDOW_HEIGHT: f32 = 800.0;
 
+/// The file where the run is saved, continued from the main menu.
+pub const SAVE_FILE: &str = "save.txt";
+
+/// The folder where the assets are loaded from, relative to the folder found
+/// by the `AssetServer` (see `asset_path`).
+pub const ASSET_FOLDER: &str = "assets";
+
+/// The font used by the ui.
+pub const UI_FONT: &str = "fonts/GABOED.ttf";
+
 /// The window's title.
 pub const WINDOW_TITLE: &str = "roguelike";
 
@@ -20,4 +30,11 @@ pub const Z_INDEX_TILE: f32 = 0.0;
 pub const UI_TEXT_TURN_COLOR: Color = Color::BLACK;
 pub const UI_TEXT_TURN_SIZE: f32 = 20.0;
 
+pub const UI_TEXT_GAME_OVER_COLOR: Color = Color::WHITE;
+pub const UI_TEXT_GAME_OVER_TITLE_SIZE: f32 = 48.0;
+pub const UI_TEXT_GAME_OVER_SIZE: f32 = 24.0;
+/// The color of the screen displayed over the map once the player died.
+pub const UI_GAME_OVER_BACKGROUND_COLOR: Color =
+    Color::rgba(0.0, 0.0, 0.0, 0.8);
+
 pub const PERLIN_NOISE_SCALE: f64 = 0.1;
@@ -12,3 +12,7 @@ pub const TILE_COORDINATE_LABEL_FONT_SIZE: f32 = SPRITE_TILE_WIDTH * 0.25;
 
 pub const Z_INDEX_GRID_LINES: f32 = 0.5;
 pub const Z_INDEX_TILE_COORDINATES: f32 = 0.5;
+
+/// The color of the overlay highlighting the hidden traps.
+pub const HIDDEN_TRAP_OVERLAY_COLOR: Color = Color::rgba(1.0, 0.0, 1.0, 0.4);
+pub const Z_INDEX_HIDDEN_TRAP_OVERLAY: f32 = 0.5;
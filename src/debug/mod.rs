@@ -1,10 +1,12 @@
 mod constants;
 mod grid;
 mod tile;
+mod trap;
 
 use constants::*;
 use grid::*;
 use tile::*;
+use trap::{hide_hidden_traps, show_hidden_traps};
 
 use crate::prelude::*;
 
@@ -15,15 +17,22 @@ impl Plugin for DebugPlugin {
         app.init_state::<ExecutionMode>()
             .add_systems(
                 Update,
-                update_execution_mode.run_if(in_state(GameState::PlayerTurn)),
+                update_execution_mode.run_if(
+                    in_state(GameState::PlayerTurn)
+                        .and_then(in_state(PauseState::Running)),
+                ),
             )
             .add_systems(
                 OnEnter(ExecutionMode::Debug),
-                (show_grid, show_tile_coordinate_labels),
+                (show_grid, show_tile_coordinate_labels, show_hidden_traps),
+            )
+            .add_systems(
+                OnEnter(GameState::PlayerTurn),
+                show_hidden_traps.run_if(in_state(ExecutionMode::Debug)),
             )
             .add_systems(
                 OnExit(ExecutionMode::Debug),
-                (hide_grid, hide_tile_coordinate_labels),
+                (hide_grid, hide_tile_coordinate_labels, hide_hidden_traps),
             );
     }
 }
@@ -19,7 +19,7 @@ pub fn spawn_tile_coordinate_labels(
                     text: Text::from_section(
                         format!("({j},{i})"),
                         TextStyle {
-                            font: asset_server.load("fonts/GABOED.ttf"),
+                            font: asset_server.load(UI_FONT),
                             font_size: TILE_COORDINATE_LABEL_FONT_SIZE,
                             color: TILE_COORDINATE_LABEL_FONT_COLOR,
                         },
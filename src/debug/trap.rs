@@ -0,0 +1,68 @@
+use crate::debug::constants::{
+    HIDDEN_TRAP_OVERLAY_COLOR, Z_INDEX_HIDDEN_TRAP_OVERLAY,
+};
+use crate::prelude::*;
+
+/// Marker component for the overlays highlighting the hidden traps.
+#[derive(Component)]
+pub struct HiddenTrapOverlay;
+
+fn spawn_hidden_trap_overlays(commands: &mut Commands, map: &Map) {
+    for trap in map.traps.iter().filter(|trap| trap.hidden) {
+        let (overlay_x, overlay_y) = trap.position.as_sprite_coordinates();
+        commands.spawn((
+            HiddenTrapOverlay,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: HIDDEN_TRAP_OVERLAY_COLOR,
+                    custom_size: Some(Vec2::new(
+                        SPRITE_TILE_WIDTH,
+                        SPRITE_TILE_HEIGHT,
+                    )),
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    overlay_x,
+                    overlay_y,
+                    Z_INDEX_HIDDEN_TRAP_OVERLAY,
+                ),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn despawn_hidden_trap_overlays(
+    commands: &mut Commands,
+    overlay_entities: &[Entity],
+) {
+    for entity in overlay_entities {
+        commands.entity(*entity).despawn();
+    }
+}
+
+/// Highlights the hidden traps of the current map. The overlays are spawned
+/// again on every player turn, as traps are revealed or the map changes.
+pub fn show_hidden_traps(
+    mut commands: Commands,
+    q_map: Query<&Map, With<OnDisplay>>,
+    q_overlay_entities: Query<Entity, With<HiddenTrapOverlay>>,
+) {
+    despawn_hidden_trap_overlays(
+        &mut commands,
+        &q_overlay_entities.iter().collect::<Vec<_>>(),
+    );
+    if let Ok(map) = q_map.get_single() {
+        spawn_hidden_trap_overlays(&mut commands, map);
+    }
+}
+
+pub fn hide_hidden_traps(
+    mut commands: Commands,
+    q_overlay_entities: Query<Entity, With<HiddenTrapOverlay>>,
+) {
+    despawn_hidden_trap_overlays(
+        &mut commands,
+        &q_overlay_entities.iter().collect::<Vec<_>>(),
+    );
+}
@@ -0,0 +1,144 @@
+use crate::prelude::*;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::PlayerTurn),
+            check_player_death
+                .after(resolve_attacks)
+                .after(trigger_traps)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnEnter(AppState::GameOver), setup_game_over_screen)
+        .add_systems(OnExit(AppState::GameOver), reset_run);
+    }
+}
+
+/// Represents what killed the player, displayed on the game over screen.
+#[derive(Resource)]
+pub struct CauseOfDeath(pub String);
+
+/// Marker component to represent the game over screen.
+#[derive(Component)]
+pub struct UiGameOver;
+
+/// The entities spawned for a run, despawned before a new run starts (see
+/// `reset_run`).
+type RunEntityFilter = Or<(
+    With<OnDisplay>,
+    With<MainCamera>,
+    With<UiHud>,
+    With<UiGameOver>,
+)>;
+
+/// Switches to the game over screen once the player has no health left.
+pub fn check_player_death(
+    q_actors: Query<(&Actor, &Health), With<OnDisplay>>,
+    mut app_next_state: ResMut<NextState<AppState>>,
+    mut game_next_state: ResMut<NextState<GameState>>,
+) {
+    if q_actors
+        .iter()
+        .any(|(actor, health)| actor.is_player() && health.is_dead())
+    {
+        app_next_state.set(AppState::GameOver);
+        game_next_state.set(GameState::Uninitialized);
+    }
+}
+
+/// Creates the game over screen over the map, showing the cause of death, the
+/// map reached and the number of turns survived.
+pub fn setup_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    cause_of_death: Option<Res<CauseOfDeath>>,
+    current_map_number: Res<CurrentMapNumber>,
+    current_turn_number: Res<CurrentTurnNumber>,
+    world: Res<WorldGraph>,
+) {
+    let font = asset_server.load(UI_FONT);
+    let text = |value: String, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: UI_TEXT_GAME_OVER_COLOR,
+            },
+        )
+    };
+    let lines = [
+        text("Game over".into(), UI_TEXT_GAME_OVER_TITLE_SIZE),
+        text(
+            cause_of_death
+                .map_or_else(|| "Died".into(), |cause| cause.0.clone()),
+            UI_TEXT_GAME_OVER_SIZE,
+        ),
+        text(
+            format!(
+                "Reached depth {} (map {})",
+                world.depth(current_map_number.0),
+                current_map_number.0
+            ),
+            UI_TEXT_GAME_OVER_SIZE,
+        ),
+        text(
+            format!("Survived {} turns", current_turn_number.0),
+            UI_TEXT_GAME_OVER_SIZE,
+        ),
+        text(
+            "Press Enter to start a new run".into(),
+            UI_TEXT_GAME_OVER_SIZE,
+        ),
+    ];
+
+    commands
+        .spawn((
+            UiGameOver,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(UI_TEXT_GAME_OVER_SIZE),
+                    ..default()
+                },
+                background_color: UI_GAME_OVER_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn(line);
+            }
+        });
+}
+
+/// Tears down the run which just ended before a new one starts. The entities
+/// spawned when entering `AppState::InGame` (the displayed map and actors,
+/// the camera and the ui) are despawned, so they are created again, and the
+/// run resources are reset with a new `RunSeed`.
+pub fn reset_run(
+    mut commands: Commands,
+    q_entities: Query<Entity, RunEntityFilter>,
+) {
+    for entity in &q_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let run_seed = RunSeed::random();
+    commands.insert_resource(run_seed);
+    commands.insert_resource(RunRng::from(run_seed));
+    commands.insert_resource(CurrentTurnNumber::default());
+    commands.insert_resource(CurrentMapNumber::default());
+    commands.insert_resource(WorldGraph::default());
+    commands.insert_resource(FieldOfView::default());
+    commands.insert_resource(PendingAttacks::default());
+    commands.remove_resource::<Overworld>();
+    commands.remove_resource::<LastMapExit>();
+    commands.remove_resource::<CauseOfDeath>();
+}
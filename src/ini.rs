@@ -0,0 +1,142 @@
+/// Represents a section of a text file made of sections, such as the tile
+/// definitions (see `TileRegistry`) or the saved runs (see `SavedRun`).
+///
+/// Each section starts with its name between brackets, followed by one
+/// property per line:
+///
+/// ```text
+/// # a comment
+/// [name]
+/// key = value
+/// ```
+///
+/// The empty lines and the lines starting with `#` are ignored.
+#[derive(Debug)]
+pub struct IniSection<'a> {
+    /// The line where the section starts.
+    pub line_number: usize,
+    pub name: &'a str,
+    /// The properties of the section, with their line.
+    pub properties: Vec<(&'a str, &'a str, usize)>,
+}
+
+impl<'a> IniSection<'a> {
+    /// Returns whether the section has a given property.
+    pub fn has(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the value of a property, if the section has it.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.properties
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, value, _)| *value)
+    }
+
+    /// Parses the value of a required property with a given function. The
+    /// errors point at the property's line.
+    pub fn parse<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&'a str) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let (_, value, line_number) = self
+            .properties
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .ok_or_else(|| {
+                format!(
+                    "line {}: section '{}' has no property '{key}'",
+                    self.line_number, self.name
+                )
+            })?;
+        parse(value).map_err(|e| format!("line {line_number}: {e}"))
+    }
+}
+
+/// Splits a text into its sections (see `IniSection`).
+///
+/// # Returns
+///
+/// The sections in the text's order, or an error pointing at the faulty
+/// line.
+pub fn parse_sections(s: &str) -> Result<Vec<IniSection<'_>>, String> {
+    let mut sections: Vec<IniSection> = vec![];
+
+    for (line_index, line) in s.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) =
+            line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+        {
+            sections.push(IniSection {
+                line_number,
+                name: name.trim(),
+                properties: vec![],
+            });
+            continue;
+        }
+
+        let Some(section) = sections.last_mut() else {
+            return Err(format!(
+                "line {line_number}: property outside of a section"
+            ));
+        };
+        let (key, value) = line.split_once('=').ok_or_else(|| {
+            format!("line {line_number}: expected 'property = value'")
+        })?;
+        let (key, value) = (key.trim(), value.trim());
+        if section.has(key) {
+            return Err(format!(
+                "line {line_number}: duplicate property '{key}'"
+            ));
+        }
+        section.properties.push((key, value, line_number));
+    }
+
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sections() {
+        let text = "# comment\n[first]\na = 1\nb =\n\n[ second ]\nc=3\n";
+        let sections = parse_sections(text).unwrap();
+
+        assert_eq!(2, sections.len());
+        assert_eq!(("first", 2), (sections[0].name, sections[0].line_number));
+        assert_eq!(Some("1"), sections[0].get("a"));
+        assert_eq!(Some(""), sections[0].get("b"));
+        assert!(!sections[0].has("c"));
+        assert_eq!("second", sections[1].name);
+        assert_eq!(Ok(1), sections[1].parse("c", |value| Ok(value.len())));
+        assert_eq!(
+            Err("line 7: invalid".into()),
+            sections[1].parse("c", |_| Err::<u8, _>("invalid".into()))
+        );
+        assert_eq!(
+            Err("line 6: section 'second' has no property 'd'".into()),
+            sections[1].parse("d", |value| Ok(value.to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_parse_sections_errors() {
+        let error = |text| parse_sections(text).unwrap_err();
+
+        assert_eq!("line 1: property outside of a section", error("a = 1"));
+        assert_eq!("line 2: expected 'property = value'", error("[a]\nb"));
+        assert_eq!(
+            "line 3: duplicate property 'b'",
+            error("[a]\nb = 1\nb = 2")
+        );
+    }
+}
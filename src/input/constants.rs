@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
 pub const KEY_PLAYER_SKIP_TURN: KeyCode = KeyCode::Space;
+pub const KEY_PLAYER_ENTER_LOCATION: KeyCode = KeyCode::Enter;
+pub const KEY_PLAYER_SEARCH: KeyCode = KeyCode::KeyF;
 pub const KEYS_PLAYER_MOVE_LEFT: [KeyCode; 2] =
     [KeyCode::KeyA, KeyCode::ArrowLeft];
 pub const KEYS_PLAYER_MOVE_RIGHT: [KeyCode; 2] =
@@ -9,4 +11,6 @@ pub const KEYS_PLAYER_MOVE_UP: [KeyCode; 2] = [KeyCode::KeyW, KeyCode::ArrowUp];
 pub const KEYS_PLAYER_MOVE_DOWN: [KeyCode; 2] =
     [KeyCode::KeyS, KeyCode::ArrowDown];
 
+pub const KEY_PAUSE: KeyCode = KeyCode::Escape;
 pub const KEY_APP_EXIT: KeyCode = KeyCode::Escape;
+pub const KEY_NEW_RUN: KeyCode = KeyCode::Enter;
@@ -14,12 +14,22 @@ impl Plugin for InputPlugin {
                 check_camera_zoom_via_mouse,
                 check_player_move_via_keys,
                 check_player_skip_turn_via_keys,
+                check_player_search_via_keys,
+                check_player_enter_location_via_keys,
             )
-                .run_if(in_state(GameState::PlayerTurn)),
+                .run_if(
+                    in_state(GameState::PlayerTurn)
+                        .and_then(in_state(PauseState::Running)),
+                ),
         )
         .add_systems(
             Update,
-            check_app_exit_via_keys.run_if(in_state(AppState::InGame)),
+            check_pause_via_keys.run_if(in_state(GameState::PlayerTurn)),
+        )
+        .add_systems(
+            Update,
+            (check_new_run_via_keys, check_app_exit_via_keys)
+                .run_if(in_state(AppState::GameOver)),
         );
     }
 }
@@ -48,62 +58,234 @@ pub fn check_camera_zoom_via_mouse(
 /// Checks if the player skip turn when `KEY_PLAYER_SKIP_TURN` is pressed.
 pub fn check_player_skip_turn_via_keys(
     mut next_state: ResMut<NextState<GameState>>,
+    mut q_actors: Query<(&Actor, &mut SearchStreak), With<OnDisplay>>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
     if input.just_pressed(KEY_PLAYER_SKIP_TURN) {
+        reset_player_search_streak(&mut q_actors);
         next_state.set(GameState::EnemyTurn);
     }
 }
 
-/// Checks if the player receives a directional input (i.e. an arrow key or a
-/// WSQD key pressed), and moves the `Player` position accordingly.
-pub fn check_player_move_via_keys(
+/// Resets the turns spent searching in a row by the player, once they do
+/// anything else than searching (see `check_player_search_via_keys`).
+fn reset_player_search_streak(
+    q_actors: &mut Query<(&Actor, &mut SearchStreak), With<OnDisplay>>,
+) {
+    for (actor, mut streak) in q_actors.iter_mut() {
+        if actor.is_player() {
+            streak.0 = 0;
+        }
+    }
+}
+
+/// Checks if the player searches for traps when `KEY_PLAYER_SEARCH` is
+/// pressed. Searching takes a turn, and the chance to reveal the hidden traps
+/// nearby increases with the turns spent searching in a row.
+pub fn check_player_search_via_keys(
     mut next_state: ResMut<NextState<GameState>>,
-    mut q_actors: Query<(&mut MapPosition, &Actor), With<OnDisplay>>,
+    mut q_actors: Query<
+        (&MapPosition, &Actor, &mut SearchStreak),
+        With<OnDisplay>,
+    >,
     mut q_map: Query<&mut Map, With<OnDisplay>>,
+    mut rng: ResMut<RunRng>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    let mut map = q_map.single_mut();
+    if !input.just_pressed(KEY_PLAYER_SEARCH) {
+        return;
+    }
+    let Some((pos_player, _, mut streak)) =
+        q_actors.iter_mut().find(|(_, actor, _)| actor.is_player())
+    else {
+        return;
+    };
+
+    streak.0 += 1;
+    q_map.single_mut().search_traps(
+        pos_player,
+        search_chance(streak.0),
+        &mut rng.ai,
+    );
+    next_state.set(GameState::EnemyTurn);
+}
 
-    let (mut pos_player, _) = q_actors
+/// Checks if the player enters the location they stand on when
+/// `KEY_PLAYER_ENTER_LOCATION` is pressed on the overworld. In that case, the
+/// exit to the location is saved in `LastMapExit` and the game state is
+/// switched to `GameState::CleanupActors`.
+pub fn check_player_enter_location_via_keys(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut q_actors: Query<
+        (&MapPosition, &Actor, &mut SearchStreak),
+        With<OnDisplay>,
+    >,
+    mut overworld: ResMut<Overworld>,
+    mut world: ResMut<WorldGraph>,
+    current_map_number: Res<CurrentMapNumber>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    if current_map_number.0 != OVERWORLD_MAP_ID
+        || !input.just_pressed(KEY_PLAYER_ENTER_LOCATION)
+    {
+        return;
+    }
+
+    let (pos_player, _, mut streak) = q_actors
         .iter_mut()
-        .filter(|(_, a)| a.is_player())
+        .filter(|(_, a, _)| a.is_player())
         .last()
         .expect("no player pos found");
 
+    if let Some(exit) = overworld.enter(*pos_player, &mut world) {
+        commands.insert_resource(LastMapExit {
+            map: OVERWORLD_MAP_ID,
+            exit,
+        });
+        streak.0 = 0;
+        next_state.set(GameState::CleanupActors);
+    }
+}
+
+/// Checks if the player receives a directional input (i.e. an arrow key or a
+/// WSQD key pressed), and moves the `Player` position accordingly, picking up
+/// the item lying there. If the player can't move, the door in the way is
+/// opened, or the destructible tile in the way is damaged instead.
+///
+/// While the player is slowed down by the terrain (see `MovementCooldown`),
+/// their turn is skipped.
+pub fn check_player_move_via_keys(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut q_actors: Query<
+        (
+            &mut MapPosition,
+            &Actor,
+            &mut MovementCooldown,
+            &mut Inventory,
+            &mut SearchStreak,
+        ),
+        With<OnDisplay>,
+    >,
+    mut q_map: Query<&mut Map, With<OnDisplay>>,
+    registry: Res<TileRegistry>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut attacks: ResMut<PendingAttacks>,
+) {
+    let mut map = q_map.single_mut();
+
+    let (mut pos_player, player, mut cooldown, mut inventory, mut streak) =
+        q_actors
+            .iter_mut()
+            .filter(|(_, a, _, _, _)| a.is_player())
+            .last()
+            .expect("no player pos found");
+
+    if cooldown.wait() {
+        streak.0 = 0;
+        next_state.set(GameState::EnemyTurn);
+        return;
+    }
+
     let pos_player_old = pos_player.clone();
 
     if input.any_just_pressed(KEYS_PLAYER_MOVE_RIGHT)
-        && can_move_right(&pos_player.clone(), &map)
+        && can_move_right(&pos_player.clone(), &map, &registry)
     {
         move_right(&mut map, &mut pos_player).unwrap();
     }
 
     if input.any_just_pressed(KEYS_PLAYER_MOVE_LEFT)
-        && can_move_left(&pos_player.clone(), &map)
+        && can_move_left(&pos_player.clone(), &map, &registry)
     {
         move_left(&mut map, &mut pos_player).unwrap();
     }
 
     if input.any_just_pressed(KEYS_PLAYER_MOVE_UP)
-        && can_move_up(&pos_player.clone(), &map)
+        && can_move_up(&pos_player.clone(), &map, &registry)
     {
         move_up(&mut map, &mut pos_player).unwrap();
     }
 
     if input.any_just_pressed(KEYS_PLAYER_MOVE_DOWN)
-        && can_move_down(&pos_player.clone(), &map)
+        && can_move_down(&pos_player.clone(), &map, &registry)
     {
         move_down(&mut map, &mut pos_player).unwrap();
     }
 
     if pos_player_old != pos_player.clone() {
+        *cooldown =
+            MovementCooldown::after_moving_to(&map, &registry, &pos_player);
+        streak.0 = 0;
+        if let Some(item) = map.take_item(&pos_player) {
+            inventory.0.push(item);
+        }
+        next_state.set(GameState::EnemyTurn);
+        return;
+    }
+
+    // bumping into an actor attacks it, into a door opens it, and into a
+    // destructible tile damages it
+    let pos_bump = [
+        (KEYS_PLAYER_MOVE_RIGHT, pos_player_old.right()),
+        (KEYS_PLAYER_MOVE_LEFT, pos_player_old.left()),
+        (KEYS_PLAYER_MOVE_UP, pos_player_old.up()),
+        (KEYS_PLAYER_MOVE_DOWN, pos_player_old.down()),
+    ]
+    .into_iter()
+    .filter(|(keys, _)| input.any_just_pressed(*keys))
+    .find_map(|(_, pos)| pos.ok());
+
+    let Some(pos_bump) = pos_bump else {
+        return;
+    };
+    let occupied = map
+        .as_tile_index(&pos_bump)
+        .is_ok_and(|index| map.tiles[index].actor.is_some());
+    if occupied {
+        attacks.0.push(Attack {
+            attacker: pos_player_old,
+            target: pos_bump,
+        });
+        streak.0 = 0;
         next_state.set(GameState::EnemyTurn);
+    } else if (player.can_open_doors()
+        && map.open_door(&registry, &pos_bump, &mut inventory))
+        || (player.can_dig() && map.damage_tile(&registry, &pos_bump))
+    {
+        streak.0 = 0;
+        next_state.set(GameState::EnemyTurn);
+    }
+}
+
+/// Opens the pause menu when `KEY_PAUSE` is pressed on the player turn, or
+/// resumes the game if it is already paused.
+pub fn check_pause_via_keys(
+    input: Res<ButtonInput<KeyCode>>,
+    pause_state: Res<State<PauseState>>,
+    mut pause_next_state: ResMut<NextState<PauseState>>,
+) {
+    if input.just_pressed(KEY_PAUSE) {
+        pause_next_state.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+}
+
+/// Starts a new run from the game over screen when `KEY_NEW_RUN` is pressed
+/// (see `reset_run`).
+pub fn check_new_run_via_keys(
+    input: Res<ButtonInput<KeyCode>>,
+    mut app_next_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KEY_NEW_RUN) {
+        app_next_state.set(AppState::InGame);
     }
 }
 
-/// Checks if an application exit event (i.e. Escape key pressed), and moves
-/// the app state to `AppState::Finished`.
+/// Checks if an application exit event (i.e. Escape key pressed on the game
+/// over screen), and moves the app state to `AppState::Finished`.
 pub fn check_app_exit_via_keys(
     input: Res<ButtonInput<KeyCode>>,
     mut app_next_state: ResMut<NextState<AppState>>,
@@ -2,9 +2,13 @@ mod actors;
 mod camera;
 mod constants;
 mod debug;
+mod game_over;
+mod ini;
 mod input;
 mod map;
+mod menu;
 mod resources;
+mod save;
 mod states;
 mod ui;
 
@@ -13,9 +17,13 @@ mod prelude {
     pub use crate::camera::*;
     pub use crate::constants::*;
     pub use crate::debug::*;
+    pub use crate::game_over::*;
+    pub use crate::ini::*;
     pub use crate::input::*;
     pub use crate::map::*;
+    pub use crate::menu::*;
     pub use crate::resources::*;
+    pub use crate::save::*;
     pub use crate::states::*;
     pub use crate::ui::*;
     pub use bevy::asset::LoadedFolder;
@@ -44,12 +52,15 @@ fn main() {
             CameraPlugin,
             InputPlugin,
             MapPlugin,
+            MenuPlugin,
             ResourcesPlugin,
             DebugPlugin,
+            GameOverPlugin,
             UiPlugin,
         ))
         .init_state::<AppState>()
         .init_state::<GameState>()
+        .init_state::<PauseState>()
         .add_systems(OnEnter(AppState::LoadingAssets), load_assets)
         .add_systems(
             Update,
@@ -68,7 +79,7 @@ fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
 }
 
 /// Checks if all assets are properly loaded. The application state is switched
-/// only after everything is loaded.
+/// to the main menu only after everything is loaded.
 fn check_assets(
     mut app_next_state: ResMut<NextState<AppState>>,
     mut events: EventReader<AssetEvent<LoadedFolder>>,
@@ -76,7 +87,7 @@ fn check_assets(
     for event in events.read() {
         if let AssetEvent::LoadedWithDependencies { id: _ } = event {
             println!("asset loaded!");
-            app_next_state.set(AppState::InGame);
+            app_next_state.set(AppState::MainMenu);
         }
     }
 }
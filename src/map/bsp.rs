@@ -0,0 +1,317 @@
+use rand::rngs::StdRng;
+
+use super::tile::{Tile, TileKind};
+use crate::prelude::*;
+
+/// Represents a rectangular area of a map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MapArea {
+    /// The column of the top left corner.
+    pub x: usize,
+    /// The row of the top left corner.
+    pub y: usize,
+    /// The number of columns covered by the area.
+    pub width: usize,
+    /// The number of rows covered by the area.
+    pub height: usize,
+}
+
+impl MapArea {
+    /// Returns the coordinates of the tile at the center of the area.
+    pub const fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Returns whether a tile is inside the area.
+    pub const fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x
+            && x < self.x + self.width
+            && y >= self.y
+            && y < self.y + self.height
+    }
+
+    /// Returns whether two areas share at least one tile.
+    pub const fn intersects(&self, other: &Self) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Generates dungeon maps made of rectangular rooms joined by corridors.
+///
+/// The map is recursively split in two (binary space partitioning) until the
+/// partitions are too small, then a room is created in each partition. The
+/// rooms of sibling partitions are joined by corridors, so all rooms are
+/// connected by construction. Doors are placed where corridors enter rooms.
+pub struct BinarySpacePartitioning {
+    /// The minimum width and height of a partition.
+    pub min_leaf_size: usize,
+    /// The minimum width and height of a room, if the partition is large
+    /// enough.
+    pub min_room_size: usize,
+}
+
+impl Default for BinarySpacePartitioning {
+    fn default() -> Self {
+        Self {
+            min_leaf_size: BSP_MIN_LEAF_SIZE,
+            min_room_size: BSP_MIN_ROOM_SIZE,
+        }
+    }
+}
+
+impl MapGenerator for BinarySpacePartitioning {
+    fn generate(
+        &self,
+        registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map {
+        let mut map = Map {
+            width,
+            height,
+            tiles: vec![Tile::from_kind(TileKind::WALL); width * height],
+            ..Default::default()
+        };
+
+        // the map borders are always walls
+        if width < 3 || height < 3 {
+            return map;
+        }
+        let area = MapArea {
+            x: 1,
+            y: 1,
+            width: width - 2,
+            height: height - 2,
+        };
+
+        let mut rooms = vec![];
+        self.partition(registry, area, &mut map, &mut rooms, rng);
+        add_doors(&mut map, &rooms);
+        map
+    }
+}
+
+impl BinarySpacePartitioning {
+    /// Splits an area recursively and carves rooms in the resulting
+    /// partitions.
+    ///
+    /// # Returns
+    ///
+    /// One of the rooms carved in the area, used for connecting the area to
+    /// its sibling.
+    fn partition(
+        &self,
+        registry: &TileRegistry,
+        area: MapArea,
+        map: &mut Map,
+        rooms: &mut Vec<MapArea>,
+        rng: &mut StdRng,
+    ) -> MapArea {
+        let can_split_columns = area.width >= 2 * self.min_leaf_size;
+        let can_split_rows = area.height >= 2 * self.min_leaf_size;
+
+        let split_columns = match (can_split_columns, can_split_rows) {
+            (false, false) => {
+                let room = self.create_room(area, rng);
+                carve_room(map, &room);
+                rooms.push(room);
+                return room;
+            }
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => rng.gen_bool(0.5),
+        };
+
+        let (area_a, area_b) = if split_columns {
+            let split = rng.gen_range(
+                self.min_leaf_size..=area.width - self.min_leaf_size,
+            );
+            (
+                MapArea {
+                    width: split,
+                    ..area
+                },
+                MapArea {
+                    x: area.x + split,
+                    width: area.width - split,
+                    ..area
+                },
+            )
+        } else {
+            let split = rng.gen_range(
+                self.min_leaf_size..=area.height - self.min_leaf_size,
+            );
+            (
+                MapArea {
+                    height: split,
+                    ..area
+                },
+                MapArea {
+                    y: area.y + split,
+                    height: area.height - split,
+                    ..area
+                },
+            )
+        };
+
+        let room_a = self.partition(registry, area_a, map, rooms, rng);
+        let room_b = self.partition(registry, area_b, map, rooms, rng);
+
+        let (x_a, y_a) = room_a.center();
+        let (x_b, y_b) = room_b.center();
+        map.carve_corridor(
+            registry,
+            x_a + y_a * map.width,
+            x_b + y_b * map.width,
+            TileKind::FLOOR,
+        );
+
+        if rng.gen_bool(0.5) {
+            room_a
+        } else {
+            room_b
+        }
+    }
+
+    /// Returns a room with a random size and position inside a partition. The
+    /// last row and column of the partition are kept as walls, so rooms of
+    /// neighboring partitions never touch.
+    fn create_room(&self, leaf: MapArea, rng: &mut StdRng) -> MapArea {
+        let width_max = (leaf.width - 1).max(1);
+        let height_max = (leaf.height - 1).max(1);
+        let width =
+            rng.gen_range(self.min_room_size.min(width_max)..=width_max);
+        let height =
+            rng.gen_range(self.min_room_size.min(height_max)..=height_max);
+
+        MapArea {
+            x: rng.gen_range(leaf.x..=leaf.x + width_max - width),
+            y: rng.gen_range(leaf.y..=leaf.y + height_max - height),
+            width,
+            height,
+        }
+    }
+}
+
+/// Carves the floor of a room.
+fn carve_room(map: &mut Map, room: &MapArea) {
+    for y in room.y..room.y + room.height {
+        for x in room.x..room.x + room.width {
+            map.tiles[x + y * map.width].kind = TileKind::FLOOR;
+        }
+    }
+}
+
+/// Turns into doors the corridor tiles entering the rooms, i.e. the floor
+/// tiles just outside a room's side which are surrounded by walls along that
+/// side.
+fn add_doors(map: &mut Map, rooms: &[MapArea]) {
+    let is_wall = |map: &Map, x: usize, y: usize| {
+        map.tiles[x + y * map.width].kind == TileKind::WALL
+    };
+
+    let mut doors = vec![];
+    for room in rooms {
+        let (x_min, y_min) = (room.x - 1, room.y - 1);
+        let (x_max, y_max) = (room.x + room.width, room.y + room.height);
+
+        for x in room.x..x_max {
+            for y in [y_min, y_max] {
+                if is_wall(map, x - 1, y) && is_wall(map, x + 1, y) {
+                    doors.push((x, y));
+                }
+            }
+        }
+        for y in room.y..y_max {
+            for x in [x_min, x_max] {
+                if is_wall(map, x, y - 1) && is_wall(map, x, y + 1) {
+                    doors.push((x, y));
+                }
+            }
+        }
+    }
+
+    for (x, y) in doors {
+        let index = x + y * map.width;
+        let is_in_room = rooms.iter().any(|room| room.contains(x, y));
+        if map.tiles[index].kind == TileKind::FLOOR && !is_in_room {
+            map.tiles[index].kind = TileKind::DOOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bsp_rooms_are_connected() {
+        let registry = TileRegistry::shipped();
+        let generator = BinarySpacePartitioning::default();
+
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let map = generator.generate(&registry, 40, 30, &mut rng);
+
+            assert_eq!(40 * 30, map.tiles.len());
+            assert_eq!(1, map.walkable_regions(&registry).len());
+            assert!(map.tiles.iter().any(|t| t.kind == TileKind::DOOR));
+        }
+    }
+
+    #[test]
+    fn test_bsp_locked_doors_are_solvable() {
+        let registry = TileRegistry::shipped();
+        let entry =
+            MapGeneratorEntry::new("bsp", BinarySpacePartitioning::default())
+                .with_locked_doors(2);
+        let mut maps_locked = 0;
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let map = entry.generate(&registry, 40, 30, None, &[], &mut rng);
+            let locked = map
+                .tiles
+                .iter()
+                .filter(|t| t.kind == TileKind::DOOR_LOCKED)
+                .count();
+
+            assert_eq!(locked, map.items.len());
+            assert!(map.are_locks_solvable(&registry), "seed {seed}");
+            maps_locked += usize::from(locked > 0);
+        }
+        assert!(maps_locked > 50);
+    }
+
+    #[test]
+    fn test_bsp_borders_are_walls() {
+        let registry = TileRegistry::shipped();
+        let generator = BinarySpacePartitioning::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let (width, height) = (31, 17);
+        let map = generator.generate(&registry, width, height, &mut rng);
+
+        for (index, tile) in map.tiles.iter().enumerate() {
+            let (x, y) = (index % width, index / width);
+            if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                assert!(tile.kind == TileKind::WALL);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bsp_small_maps() {
+        let registry = TileRegistry::shipped();
+        let generator = BinarySpacePartitioning::default();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for (width, height) in [(1, 1), (3, 3), (5, 4), (8, 20)] {
+            let map = generator.generate(&registry, width, height, &mut rng);
+            assert_eq!(width * height, map.tiles.len());
+        }
+    }
+}
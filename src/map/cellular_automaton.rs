@@ -1,5 +1,12 @@
+use std::fmt;
+use std::str::FromStr;
+
 use rand::Rng;
 
+/// The maximum number of generations computed when smoothing an automaton, in
+/// case the smoothing rule never stabilizes.
+const SMOOTH_MAX_GENERATIONS: usize = 100;
+
 /// Represents the different state for a cellular automaton cell.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CellularState {
@@ -7,6 +14,165 @@ pub enum CellularState {
     Dead,
 }
 
+/// Represents the transition rule of a cellular automaton, written in the
+/// birth/survival notation (e.g. `B3/S23` for Conway's Game of Life).
+///
+/// A dead cell becomes alive if its number of alive neighbors is in `birth`,
+/// an alive cell stays alive if its number of alive neighbors is in
+/// `survival`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CellularRule {
+    /// Whether a dead cell with `n` alive neighbors is born, for `n` in 0..=8.
+    pub birth: [bool; 9],
+    /// Whether an alive cell with `n` alive neighbors survives, for `n` in
+    /// 0..=8.
+    pub survival: [bool; 9],
+}
+
+impl CellularRule {
+    /// Conway's Game of Life rule (`B3/S23`).
+    pub const CONWAY: Self = Self::new(&[3], &[2, 3]);
+
+    /// Creates a rule from the numbers of alive neighbors for which cells are
+    /// born or survive. Numbers above 8 are ignored.
+    pub const fn new(birth: &[usize], survival: &[usize]) -> Self {
+        Self {
+            birth: Self::to_flags(birth),
+            survival: Self::to_flags(survival),
+        }
+    }
+
+    const fn to_flags(counts: &[usize]) -> [bool; 9] {
+        let mut flags = [false; 9];
+        let mut i = 0;
+        while i < counts.len() {
+            if counts[i] < flags.len() {
+                flags[counts[i]] = true;
+            }
+            i += 1;
+        }
+        flags
+    }
+
+    /// Returns the next state of a cell given its current state and its
+    /// number of alive neighbors.
+    pub fn apply(
+        &self,
+        state: &CellularState,
+        alive_neighbors: usize,
+    ) -> CellularState {
+        let flags = match state {
+            CellularState::Alive => &self.survival,
+            CellularState::Dead => &self.birth,
+        };
+        if flags.get(alive_neighbors).copied().unwrap_or(false) {
+            CellularState::Alive
+        } else {
+            CellularState::Dead
+        }
+    }
+}
+
+impl FromStr for CellularRule {
+    type Err = String;
+
+    /// Parses a rule written in the birth/survival notation, e.g.
+    /// `B678/S345678`. The notation is case insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_counts = |part: &str, prefix: char| {
+            let mut chars = part.trim().chars();
+            if !chars
+                .next()
+                .is_some_and(|c| c.eq_ignore_ascii_case(&prefix))
+            {
+                return Err(format!(
+                    "rule part '{part}' must start with {prefix}"
+                ));
+            }
+            let mut flags = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n @ 0..=8) => flags[n as usize] = true,
+                    _ => return Err(format!("invalid neighbor count '{c}'")),
+                }
+            }
+            Ok(flags)
+        };
+
+        let (birth, survival) = s
+            .split_once('/')
+            .ok_or_else(|| format!("rule '{s}' must be like B3/S23"))?;
+
+        Ok(Self {
+            birth: parse_counts(birth, 'B')?,
+            survival: parse_counts(survival, 'S')?,
+        })
+    }
+}
+
+impl fmt::Display for CellularRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |flags: &[bool; 9]| -> String {
+            (0..flags.len())
+                .filter(|&n| flags[n])
+                .map(|n| n.to_string())
+                .collect()
+        };
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+/// Represents which cells are considered as neighbors of a cell.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Neighborhood {
+    /// The 8 surrounding cells, including the diagonals.
+    #[default]
+    Moore,
+    /// The 4 cells on the left, right, top and bottom.
+    VonNeumann,
+}
+
+impl FromStr for Neighborhood {
+    type Err = String;
+
+    /// Parses a neighborhood from its name, `moore` or `von_neumann`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moore" => Ok(Self::Moore),
+            "von_neumann" => Ok(Self::VonNeumann),
+            _ => Err(format!("unknown neighborhood '{s}'")),
+        }
+    }
+}
+
+/// Represents how the neighbors outside of the automaton are handled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EdgeHandling {
+    /// Cells outside of the automaton are not counted.
+    #[default]
+    Ignore,
+    /// Cells outside of the automaton are counted as alive, i.e. the
+    /// automaton is surrounded by walls.
+    Alive,
+    /// The automaton wraps around, the cells on a border being neighbors of
+    /// the cells on the opposite border.
+    Wrap,
+}
+
+impl FromStr for EdgeHandling {
+    type Err = String;
+
+    /// Parses an edge handling from its name, `ignore`, `alive` or `wrap`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(Self::Ignore),
+            "alive" => Ok(Self::Alive),
+            "wrap" => Ok(Self::Wrap),
+            _ => Err(format!("unknown edge handling '{s}'")),
+        }
+    }
+}
+
 /// Represents a cellular automaton state at a given time.
 pub struct CellularAutomaton {
     /// The width of the cellular automaton.
@@ -16,15 +182,29 @@ pub struct CellularAutomaton {
     /// The cells' states of the cellular automaton, the cells' coordinates are
     /// given by their index in the vector.
     pub cells: Vec<CellularState>,
+    /// The rule applied at each transition.
+    pub rule: CellularRule,
+    /// The cells considered as neighbors.
+    pub neighborhood: Neighborhood,
+    /// How the neighbors outside of the automaton are handled.
+    pub edges: EdgeHandling,
 }
 
 impl CellularAutomaton {
     /// Initializes a `CellularAutomaton` of dimensions `width`x`height`, with
     /// the cells' state assigned with a given probability.
-    pub fn new(width: usize, height: usize, alive_probability: f64) -> Self {
+    ///
+    /// The automaton follows Conway's Game of Life rule with a Moore
+    /// neighborhood, the cells outside of the automaton being ignored.
+    pub fn new(
+        width: usize,
+        height: usize,
+        alive_probability: f64,
+        rng: &mut impl Rng,
+    ) -> Self {
         let cells = (0..width * height)
             .map(|_| {
-                if rand::thread_rng().gen_bool(alive_probability) {
+                if rng.gen_bool(alive_probability) {
                     CellularState::Alive
                 } else {
                     CellularState::Dead
@@ -36,78 +216,108 @@ impl CellularAutomaton {
             width,
             height,
             cells,
+            rule: CellularRule::CONWAY,
+            neighborhood: Neighborhood::default(),
+            edges: EdgeHandling::default(),
         }
     }
 
-    /// Advances the cellular automaton to the next generation based on
-    /// predefined transition rules.
-    ///
-    /// This function applies rules to each cell, determining its state in the
-    /// next generation based on the count of alive neighbors. The transition
-    /// follows standard rules for Conway's Game of Life.
-    ///
-    /// # Remarks
+    /// Sets the rule applied at each transition.
+    #[must_use]
+    pub const fn with_rule(mut self, rule: CellularRule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Sets the cells considered as neighbors.
+    #[must_use]
+    pub const fn with_neighborhood(
+        mut self,
+        neighborhood: Neighborhood,
+    ) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Sets how the neighbors outside of the automaton are handled.
+    #[must_use]
+    pub const fn with_edges(mut self, edges: EdgeHandling) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// Advances the cellular automaton to the next generation based on its
+    /// rule.
     ///
-    /// - Living cells with fewer than 2 or more than 3 alive neighbors die.
-    /// - Dead cells with exactly 3 alive neighbors become alive.
-    /// - Cells with 2 or 3 alive neighbors remain in their current state.
+    /// This function applies the rule to each cell, determining its state in
+    /// the next generation based on the count of alive neighbors.
     pub fn transition(&mut self) {
-        let mut next_generation = self.cells.clone();
-        for (i, c) in self.cells.iter().enumerate() {
-            let alive_neighbors =
-                count_neighbors_in_state(self, i, CellularState::Alive);
-
-            next_generation[i] = match c {
-                CellularState::Alive => {
-                    if alive_neighbors < 2 || alive_neighbors > 3 {
-                        CellularState::Dead
-                    } else {
-                        CellularState::Alive
-                    }
-                }
-                CellularState::Dead => {
-                    if alive_neighbors == 3 {
-                        CellularState::Alive
-                    } else {
-                        CellularState::Dead
-                    }
-                }
-            }
-        }
-        self.cells = next_generation;
+        self.cells = self.next_generation(&self.rule);
     }
 
     /// Applies a smoothing operation to the cellular automaton, updating cell
-    // states based on neighboring conditions.
+    /// states based on neighboring conditions.
     ///
-    /// It iteratively processes the current generation of cells, updating each
-    /// cell's state based on the count of alive neighbors. The process
-    /// continues until no further changes occur.
-    pub fn smooth(&mut self) {
-        let mut current_generation = self.cells.clone();
-        let mut has_changed = true;
-
-        while has_changed {
-            has_changed = false;
-            let mut next_generation = current_generation.clone();
-
-            for (i, c) in current_generation.iter().enumerate() {
+    /// It repeatedly applies the smoothing `rule` to the current generation of
+    /// cells, until no further changes occur (or a maximum number of
+    /// generations is reached, if the rule never stabilizes).
+    pub fn smooth(&mut self, rule: &CellularRule) {
+        for _ in 0..SMOOTH_MAX_GENERATIONS {
+            let next_generation = self.next_generation(rule);
+            if next_generation == self.cells {
+                return;
+            }
+            self.cells = next_generation;
+        }
+    }
+
+    /// Returns the generation following the current one for a given rule.
+    fn next_generation(&self, rule: &CellularRule) -> Vec<CellularState> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
                 let alive_neighbors =
                     count_neighbors_in_state(self, i, CellularState::Alive);
+                rule.apply(c, alive_neighbors)
+            })
+            .collect()
+    }
+}
 
-                if c == &CellularState::Dead && alive_neighbors > 3 {
-                    next_generation[i] = CellularState::Alive;
-                    has_changed = true;
-                }
-            }
-            current_generation = next_generation.clone();
-        }
-        self.cells = current_generation;
+/// Returns the coordinate next to `value` in a given direction (-1, 0 or 1),
+/// wrapping around if needed.
+const fn offset(
+    value: usize,
+    delta: i8,
+    size: usize,
+    wrap: bool,
+) -> Option<usize> {
+    match delta {
+        -1 if value > 0 => Some(value - 1),
+        -1 if wrap => Some(size - 1),
+        1 if value + 1 < size => Some(value + 1),
+        1 if wrap => Some(0),
+        0 => Some(value),
+        _ => None,
+    }
+}
+
+/// Returns the number of neighbors a cell has in a given neighborhood,
+/// borders excluded.
+const fn neighborhood_size(neighborhood: Neighborhood) -> usize {
+    match neighborhood {
+        Neighborhood::Moore => 8,
+        Neighborhood::VonNeumann => 4,
     }
 }
 
-/// Returns the flat indices of all Von Neumann neighbors for a given cell in a
-/// cellular automaton, considering borders and corners.
+/// Returns the flat indices of all neighbors for a given cell in a cellular
+/// automaton, depending on its `Neighborhood` (Moore or Von Neumann). With
+/// `EdgeHandling::Wrap`, the cells on the opposite borders are included,
+/// otherwise the cells outside of the automaton are left out. A cell is never
+/// listed twice, even if it is reached on both sides of a wrapping automaton
+/// less than 3 cells wide or high.
 ///
 /// # Arguments
 ///
@@ -121,47 +331,49 @@ pub fn enumerate_neighbors(
     automaton: &CellularAutomaton,
     i: usize,
 ) -> Vec<usize> {
-    let mut neighbors = Vec::new();
+    // the Von Neumann neighbors come first, followed by the diagonals
+    const MOORE_OFFSETS: [(i8, i8); 8] = [
+        (-1, 0),
+        (1, 0),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (-1, -1),
+        (1, 1),
+        (-1, 1),
+    ];
+
+    let offsets = if automaton.neighborhood == Neighborhood::VonNeumann {
+        &MOORE_OFFSETS[..4]
+    } else {
+        &MOORE_OFFSETS[..]
+    };
+
+    let wrap = automaton.edges == EdgeHandling::Wrap;
     let x = i % automaton.width;
     let y = i / automaton.width;
-    // left neighbor
-    if x > 0 {
-        neighbors.push(i - 1);
-    }
-    // right neighbor
-    if x < automaton.width - 1 {
-        neighbors.push(i + 1);
-    }
-    // top neighbor
-    if y > 0 {
-        neighbors.push((y - 1) * automaton.width + x);
-    }
-    // bottom neighbor
-    if y < automaton.height - 1 {
-        neighbors.push((y + 1) * automaton.width + x);
-    }
-    // top right neighbor
-    if x < automaton.width - 1 && y > 0 {
-        neighbors.push((y - 1) * automaton.width + x + 1);
-    }
-    // top left neighbor
-    if x > 0 && y > 0 {
-        neighbors.push((y - 1) * automaton.width + x - 1);
-    }
-    // bottom right neighbor
-    if x < automaton.width - 1 && y < automaton.height - 1 {
-        neighbors.push((y + 1) * automaton.width + x + 1);
-    }
-    // bottom left neighbor
-    if x > 0 && y < automaton.height - 1 {
-        neighbors.push((y + 1) * automaton.width + x - 1);
-    }
 
+    let mut neighbors = Vec::with_capacity(offsets.len());
+    for &(dx, dy) in offsets {
+        let (Some(x), Some(y)) = (
+            offset(x, dx, automaton.width, wrap),
+            offset(y, dy, automaton.height, wrap),
+        ) else {
+            continue;
+        };
+        let n_i = y * automaton.width + x;
+        if n_i != i && !neighbors.contains(&n_i) {
+            neighbors.push(n_i);
+        }
+    }
     neighbors
 }
 
 /// Counts the number of neighboring cells in a specified state around a given cell.
 ///
+/// With `EdgeHandling::Alive`, the neighbors outside of the automaton are
+/// counted as alive.
+///
 /// # Arguments
 ///
 /// * `automaton` - The cellular automaton.
@@ -176,21 +388,33 @@ pub fn count_neighbors_in_state(
     cell_i: usize,
     cell_state: CellularState,
 ) -> usize {
-    enumerate_neighbors(automaton, cell_i)
+    let neighbors = enumerate_neighbors(automaton, cell_i);
+    let count = neighbors
         .iter()
         .filter(|&&n_i| automaton.cells[n_i] == cell_state)
-        .count()
+        .count();
+
+    if automaton.edges == EdgeHandling::Alive
+        && cell_state == CellularState::Alive
+    {
+        count + neighborhood_size(automaton.neighborhood) - neighbors.len()
+    } else {
+        count
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn test_enumerate_neighbors() {
-        let ca1x1 = CellularAutomaton::new(1, 1, 0f64);
-        let ca1x2 = CellularAutomaton::new(1, 2, 0f64);
-        let ca3x3 = CellularAutomaton::new(3, 3, 0f64);
+        let mut rng = StdRng::seed_from_u64(0);
+        let ca1x1 = CellularAutomaton::new(1, 1, 0f64, &mut rng);
+        let ca1x2 = CellularAutomaton::new(1, 2, 0f64, &mut rng);
+        let ca3x3 = CellularAutomaton::new(3, 3, 0f64, &mut rng);
 
         assert_eq!(enumerate_neighbors(&ca3x3, 0).len(), 3);
         assert_eq!(enumerate_neighbors(&ca3x3, 1).len(), 5);
@@ -210,7 +434,8 @@ mod tests {
 
     #[test]
     fn test_transition() {
-        let mut ca1x1 = CellularAutomaton::new(1, 1, 1.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ca1x1 = CellularAutomaton::new(1, 1, 1.0, &mut rng);
         assert_eq!(CellularState::Alive, ca1x1.cells[0]);
         for _ in 0..10 {
             ca1x1.transition();
@@ -218,7 +443,7 @@ mod tests {
         }
 
         // block pattern remains the same
-        let mut ca2x2 = CellularAutomaton::new(2, 2, 1.0);
+        let mut ca2x2 = CellularAutomaton::new(2, 2, 1.0, &mut rng);
         for _ in 0..10 {
             ca2x2.transition();
             for i in 0..ca2x2.cells.len() {
@@ -251,11 +476,8 @@ mod tests {
             CellularState::Dead,
         ];
 
-        let mut ca3x3 = CellularAutomaton {
-            width: 3,
-            height: 3,
-            cells: expected_blinker_even_position.clone(),
-        };
+        let mut ca3x3 = CellularAutomaton::new(3, 3, 0.0, &mut rng);
+        ca3x3.cells = expected_blinker_even_position.clone();
 
         for i in 1..10 {
             ca3x3.transition();
@@ -266,4 +488,119 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_enumerate_neighbors_von_neumann() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let ca3x3 = CellularAutomaton::new(3, 3, 0f64, &mut rng)
+            .with_neighborhood(Neighborhood::VonNeumann);
+
+        assert_eq!(enumerate_neighbors(&ca3x3, 0).len(), 2);
+        assert_eq!(enumerate_neighbors(&ca3x3, 1).len(), 3);
+        assert_eq!(enumerate_neighbors(&ca3x3, 4), vec![3, 5, 1, 7]);
+    }
+
+    #[test]
+    fn test_enumerate_neighbors_wrap() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let ca4x4 = CellularAutomaton::new(4, 4, 0f64, &mut rng)
+            .with_edges(EdgeHandling::Wrap);
+
+        assert_eq!(
+            enumerate_neighbors(&ca4x4, 0),
+            vec![3, 1, 12, 4, 13, 15, 5, 7]
+        );
+        for i in 0..ca4x4.cells.len() {
+            assert_eq!(enumerate_neighbors(&ca4x4, i).len(), 8);
+        }
+
+        // the cells reached on both sides are counted once
+        let ca2x2 = CellularAutomaton::new(2, 2, 1.0, &mut rng)
+            .with_edges(EdgeHandling::Wrap);
+        assert_eq!(enumerate_neighbors(&ca2x2, 0), vec![1, 2, 3]);
+        assert_eq!(
+            3,
+            count_neighbors_in_state(&ca2x2, 0, CellularState::Alive)
+        );
+        let ca1x3 = CellularAutomaton::new(1, 3, 0f64, &mut rng)
+            .with_edges(EdgeHandling::Wrap);
+        assert_eq!(enumerate_neighbors(&ca1x3, 0), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_parse_neighborhood_and_edges() {
+        assert_eq!(Ok(Neighborhood::VonNeumann), "von_neumann".parse());
+        assert_eq!(Ok(EdgeHandling::Wrap), "wrap".parse());
+        assert!("hexagonal".parse::<Neighborhood>().is_err());
+        assert!("mirror".parse::<EdgeHandling>().is_err());
+    }
+
+    #[test]
+    fn test_count_neighbors_with_alive_edges() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let ca3x3 = CellularAutomaton::new(3, 3, 0f64, &mut rng)
+            .with_edges(EdgeHandling::Alive);
+
+        let count =
+            |i| count_neighbors_in_state(&ca3x3, i, CellularState::Alive);
+        assert_eq!(count(0), 5);
+        assert_eq!(count(1), 3);
+        assert_eq!(count(4), 0);
+
+        let ca3x3 = ca3x3.with_neighborhood(Neighborhood::VonNeumann);
+        let count =
+            |i| count_neighbors_in_state(&ca3x3, i, CellularState::Alive);
+        assert_eq!(count(0), 2);
+        assert_eq!(count(4), 0);
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: CellularRule = "B678/S345678".parse().unwrap();
+        assert_eq!(CellularRule::new(&[6, 7, 8], &[3, 4, 5, 6, 7, 8]), rule);
+        assert_eq!("B678/S345678", rule.to_string());
+
+        assert_eq!(Ok(CellularRule::CONWAY), "b3/s23".parse());
+        assert_eq!(Ok(CellularRule::new(&[], &[])), "B/S".parse());
+
+        assert!("B9/S23".parse::<CellularRule>().is_err());
+        assert!("S23/B3".parse::<CellularRule>().is_err());
+        assert!("B3S23".parse::<CellularRule>().is_err());
+    }
+
+    #[test]
+    fn test_transition_with_cave_rule() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let rule = "B678/S345678".parse().unwrap();
+
+        // an isolated cell dies
+        let mut ca3x3 =
+            CellularAutomaton::new(3, 3, 0.0, &mut rng).with_rule(rule);
+        ca3x3.cells[4] = CellularState::Alive;
+        ca3x3.transition();
+        assert!(ca3x3.cells.iter().all(|c| *c == CellularState::Dead));
+
+        // walls at the borders make the corners grow
+        let mut ca3x3 = CellularAutomaton::new(3, 3, 0.0, &mut rng)
+            .with_rule(rule)
+            .with_edges(EdgeHandling::Alive);
+        ca3x3.cells[1] = CellularState::Alive;
+        ca3x3.cells[3] = CellularState::Alive;
+        ca3x3.transition();
+        assert_eq!(CellularState::Alive, ca3x3.cells[0]);
+        assert_eq!(CellularState::Dead, ca3x3.cells[4]);
+    }
+
+    #[test]
+    fn test_smooth() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut ca3x3 = CellularAutomaton::new(3, 3, 0.0, &mut rng);
+        ca3x3.cells[0] = CellularState::Alive;
+        ca3x3.cells[1] = CellularState::Alive;
+        ca3x3.cells[3] = CellularState::Alive;
+
+        // growth propagates until the whole automaton is filled
+        ca3x3.smooth(&"B23/S012345678".parse().unwrap());
+        assert!(ca3x3.cells.iter().all(|c| *c == CellularState::Alive));
+    }
 }
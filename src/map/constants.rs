@@ -1,12 +1,132 @@
+use bevy::prelude::Color;
+
+use super::noise::FractalParameters;
+
 pub const MAP_WIDTH: usize = 20;
 pub const MAP_HEIGHT: usize = 20;
 
 pub const TILESET_TERRAIN_ROWS: usize = 1;
-pub const TILESET_TERRAIN_COLUMNS: usize = 5;
-pub const TILESET_TERRAIN_IDX_GRASS: usize = 0;
-pub const TILESET_TERRAIN_IDX_GRASS_WITH_FLOWER: usize = 1;
-pub const TILESET_TERRAIN_IDX_GRASS_WITH_STONE: usize = 2;
-pub const TILESET_TERRAIN_IDX_GRASS_WITH_STONE_DAMAGED: usize = 3;
-pub const TILESET_TERRAIN_IDX_SIGNPOST: usize = 4;
+/// The number of columns of the terrain tileset, the structure tileset being
+/// appended to its right (see `append_tileset`).
+pub const TILESET_TERRAIN_COLUMNS: usize = 5 + TILESET_STRUCTURE_COLUMNS;
+/// The number of columns of the structure tileset, holding the sprites of
+/// walls, floors, doors and traps.
+pub const TILESET_STRUCTURE_COLUMNS: usize = 5;
+/// The index in the terrain tileset of the sprite representing a key.
+pub const TILESET_TERRAIN_IDX_KEY: usize = 1;
+/// The color multiplied with the sprite of a key.
+pub const ITEM_KEY_COLOR: Color = Color::GOLD;
+/// The Z value for displaying items on the screen, between the tiles and the
+/// actors.
+pub const Z_INDEX_ITEM: f32 = 0.25;
+/// The index in the terrain tileset of the sprite representing a trap, tinted
+/// depending on the trap's kind.
+pub const TILESET_TERRAIN_IDX_TRAP: usize = 9;
+pub const TRAP_SPIKE_COLOR: Color = Color::rgb(0.8, 0.2, 0.2);
+pub const TRAP_SNARE_COLOR: Color = Color::rgb(0.6, 0.4, 0.2);
+pub const TRAP_TELEPORT_COLOR: Color = Color::rgb(0.6, 0.3, 0.9);
+pub const TRAP_ALARM_COLOR: Color = Color::rgb(1.0, 0.6, 0.0);
+/// The file where the tile kinds are defined (see `TileRegistry`), in the
+/// asset folder (see `asset_path`).
+pub const TILE_DEFINITIONS_FILE: &str = "tiles.txt";
+/// The tag of the tiles which actors unable to swim can't cross.
+pub const TILE_TAG_WATER: &str = "water";
+/// The tag of the tiles where small actors can hide.
+pub const TILE_TAG_COVER: &str = "cover";
+/// The tag of the doors which need a key to be opened.
+pub const TILE_TAG_LOCKED: &str = "locked";
 
 pub const PROBABILITY_STONE_DAMAGED: f64 = 0.1;
+
+/// The id of the overworld in the `WorldGraph`, where the player starts.
+pub const OVERWORLD_MAP_ID: usize = 0;
+pub const OVERWORLD_WIDTH: usize = 40;
+pub const OVERWORLD_HEIGHT: usize = 40;
+/// The proportion of the overworld with the lowest noise turned into caves.
+pub const OVERWORLD_PERCENTILE_CAVE: f64 = 0.3;
+/// The probability for a location which isn't a cave to be ruins.
+pub const OVERWORLD_PROBABILITY_RUINS: f64 = 0.05;
+
+/// The minimum number of exits added to a map, besides its entrance. A map
+/// without exits is left through its entrance (see `WorldGraph::link_new_map`).
+pub const MAP_EXITS_MIN: usize = 0;
+pub const MAP_EXITS_MAX: usize = 3;
+/// The probability for an exit to be stairs inside the map rather than on an
+/// edge.
+pub const PROBABILITY_EXIT_STAIRS: f64 = 0.2;
+
+pub const PERLIN_FRACTAL: FractalParameters = FractalParameters {
+    octaves: 4,
+    lacunarity: 2.0,
+    persistence: 0.5,
+};
+/// The proportion of the tiles with the lowest noise turned into stones.
+pub const PERLIN_PERCENTILE_STONE: f64 = 0.3;
+/// The proportion of the tiles with a lower noise than the flowers.
+pub const PERLIN_PERCENTILE_FLOWER: f64 = 0.9;
+/// The offset of the noise sampled for the moisture, so it doesn't follow the
+/// elevation.
+pub const PERLIN_MOISTURE_OFFSET: f64 = 100.0;
+/// The proportions of the grass tiles with a lower moisture than the tall
+/// grass, mud, shallow water and deep water respectively.
+pub const PERLIN_PERCENTILE_TALL_GRASS: f64 = 0.6;
+pub const PERLIN_PERCENTILE_MUD: f64 = 0.8;
+pub const PERLIN_PERCENTILE_SHALLOW_WATER: f64 = 0.87;
+pub const PERLIN_PERCENTILE_DEEP_WATER: f64 = 0.95;
+
+/// The file where the settings of the cellular automaton generator are
+/// defined (see `CellularAutomatonGenerator`), in the asset folder (see
+/// `asset_path`). The following constants are used if the file is invalid.
+pub const CELLULAR_AUTOMATON_FILE: &str = "cellular_automaton.txt";
+pub const CELLULAR_AUTOMATON_ALIVE_PROBABILITY: f64 = 0.5;
+pub const CELLULAR_AUTOMATON_TRANSITIONS: usize = 50;
+pub const CELLULAR_AUTOMATON_RULE: &str = "B3/S23";
+pub const CELLULAR_AUTOMATON_SMOOTHING_RULE: &str = "B45678/S012345678";
+
+pub const BSP_MIN_LEAF_SIZE: usize = 6;
+pub const BSP_MIN_ROOM_SIZE: usize = 3;
+
+pub const DRUNKARD_FLOOR_COVERAGE: f64 = 0.45;
+pub const DRUNKARD_WALKERS: usize = 4;
+pub const DRUNKARD_WALKER_LIFETIME: usize = 100;
+
+/// The maximum number of choices undone before starting a wave function
+/// collapse over.
+pub const WFC_MAX_BACKTRACKS: usize = 1000;
+/// The number of times a wave function collapse is started over before giving
+/// up.
+pub const WFC_MAX_ATTEMPTS: usize = 10;
+/// The file of the sample map (see `parse_tile_grid`) of the meadows
+/// generated with wave function collapse, in the asset folder (see
+/// `asset_path`).
+pub const WFC_SAMPLE_MEADOW_FILE: &str = "samples/meadow.txt";
+
+/// The folder where the vault files are loaded from (see `asset_path`).
+pub const VAULT_FOLDER: &str = "vaults";
+pub const VAULT_FILE_EXTENSION: &str = "vault";
+/// The maximum number of vaults stamped into the maps supporting them.
+pub const VAULTS_PER_MAP: usize = 1;
+/// The maximum number of doors locked in the maps supporting them, a key being
+/// placed for each of them.
+pub const LOCKED_DOORS_PER_MAP: usize = 1;
+/// The maximum number of traps set in the maps supporting them.
+pub const TRAPS_PER_MAP: usize = 3;
+/// The damage dealt to an actor stepping on a spike trap.
+pub const TRAP_SPIKE_DAMAGE: u32 = 3;
+/// The number of turns lost by an actor stepping on a spike trap.
+pub const TRAP_SPIKE_TURNS: u32 = 1;
+/// The number of turns an actor is held by a snare.
+pub const TRAP_SNARE_TURNS: u32 = 3;
+/// The maximum distance (in the cardinal directions) between the player and
+/// the traps they can detect.
+pub const TRAP_SEARCH_RADIUS: usize = 2;
+/// The chance to detect a trap per turn spent searching in a row.
+pub const TRAP_SEARCH_CHANCE_PER_TURN: f64 = 0.25;
+
+/// The maximum distance at which the player sees the tiles (see
+/// `Map::field_of_view`).
+pub const FOV_RADIUS: usize = 8;
+
+/// The brightness of the tiles remembered by the player but not visible
+/// anymore, multiplying their sprite color.
+pub const FOG_REMEMBERED_BRIGHTNESS: f32 = 0.4;
@@ -0,0 +1,97 @@
+use super::tile::{Tile, TileKind};
+use crate::prelude::*;
+
+impl Map {
+    /// Damages a destructible tile, replacing it with its damaged kind (see
+    /// `TileKind::damaged`), e.g. a stone becomes a damaged stone, then
+    /// grass.
+    ///
+    /// # Returns
+    ///
+    /// Whether the tile was damaged. Tiles out of the map, or which aren't
+    /// destructible, are left untouched.
+    pub fn damage_tile(
+        &mut self,
+        registry: &TileRegistry,
+        pos: &MapPosition,
+    ) -> bool {
+        let Ok(index) = self.as_tile_index(pos) else {
+            return false;
+        };
+        let Some(damaged) = self.tiles[index].kind.damaged(registry) else {
+            return false;
+        };
+        self.tiles[index].kind = damaged;
+        true
+    }
+}
+
+/// Updates the tile entities (and their sprites) whose kind differs from the
+/// current map's tiles, e.g. after a tile was damaged. The sprites are hidden,
+/// dimmed or fully displayed depending on the player's knowledge of the tiles
+/// (see `Map::tile_visibility`).
+pub fn update_tile_sprites(
+    q_map: Query<&Map, With<OnDisplay>>,
+    mut q_tiles: Query<
+        (
+            &mut Tile,
+            &mut TextureAtlas,
+            &mut Sprite,
+            &mut Visibility,
+            &MapPosition,
+        ),
+        With<OnDisplay>,
+    >,
+    fov: Res<FieldOfView>,
+    registry: Res<TileRegistry>,
+) {
+    let Ok(map) = q_map.get_single() else {
+        return;
+    };
+
+    for (mut tile, mut atlas, mut sprite, mut visibility, pos) in &mut q_tiles {
+        let Ok(index) = map.as_tile_index(pos) else {
+            continue;
+        };
+        let kind = map.tiles[index].kind;
+        if tile.kind != kind {
+            tile.kind = kind;
+            atlas.index = TileKind::to_sprite_idx(kind, &registry);
+        }
+        let tile_visibility = map.tile_visibility(pos, &fov);
+        sprite.color = tile_visibility.sprite_color(&registry, kind);
+        *visibility = tile_visibility.as_visibility();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_stone_until_grass() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map {
+            width: 2,
+            height: 1,
+            tiles: vec![
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
+                Tile::from_kind(TileKind::WALL),
+            ],
+            ..Default::default()
+        };
+        let stone = MapPosition::new(0, 0);
+
+        assert!(map.damage_tile(&registry, &stone));
+        assert_eq!(TileKind::GRASS_WITH_STONE_DAMAGED, map.tiles[0].kind);
+        assert!(!map.tiles[0].is_walkable(&registry));
+
+        assert!(map.damage_tile(&registry, &stone));
+        assert_eq!(TileKind::GRASS, map.tiles[0].kind);
+        assert!(map.tiles[0].is_walkable(&registry));
+
+        assert!(!map.damage_tile(&registry, &stone));
+        assert!(!map.damage_tile(&registry, &MapPosition::new(1, 0)));
+        assert!(!map.damage_tile(&registry, &MapPosition::new(2, 0)));
+    }
+}
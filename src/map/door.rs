@@ -0,0 +1,273 @@
+use super::tile::TileKind;
+use crate::prelude::*;
+
+impl Map {
+    /// Opens the door at a given position, replacing it with its opened kind
+    /// (see `TileKind::opened`). A locked door is opened only if a key can be
+    /// taken from the inventory.
+    ///
+    /// # Returns
+    ///
+    /// Whether the door was opened.
+    pub fn open_door(
+        &mut self,
+        registry: &TileRegistry,
+        pos: &MapPosition,
+        inventory: &mut Inventory,
+    ) -> bool {
+        let Ok(index) = self.as_tile_index(pos) else {
+            return false;
+        };
+        let kind = self.tiles[index].kind;
+        let Some(opened) = kind.opened(registry) else {
+            return false;
+        };
+        if kind.is_locked(registry) && !inventory.take(ItemKind::Key) {
+            return false;
+        }
+        self.tiles[index].kind = opened;
+        true
+    }
+
+    /// Returns which tiles can be reached from a given tile, going through
+    /// the passable tiles (see `TileKind::is_passable`) accepted by
+    /// `can_pass`.
+    pub fn reachable_tiles(
+        &self,
+        registry: &TileRegistry,
+        start: usize,
+        can_pass: impl Fn(usize) -> bool,
+    ) -> Vec<bool> {
+        let mut reached = vec![false; self.tiles.len()];
+        reached[start] = true;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            for neighbor in self.cardinal_neighbors(index) {
+                if !reached[neighbor]
+                    && self.tiles[neighbor].kind.is_passable(registry)
+                    && can_pass(neighbor)
+                {
+                    reached[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Locks up to `count` doors, and places a key for each of them on a tile
+    /// which can be reached from the entrance without opening that lock. The
+    /// keys are placed on the map's item spawns (e.g. set by vaults) if any
+    /// of them can be used.
+    ///
+    /// A door is locked only if the area behind it contains neither the
+    /// entrance, an exit nor a key, so the player never arrives in a locked
+    /// area and can't lock themselves out by using the keys in the wrong
+    /// order (see `Map::are_locks_solvable`). Maps without an entrance are
+    /// left untouched.
+    pub fn lock_doors(
+        &mut self,
+        registry: &TileRegistry,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        let Some(entrance) =
+            self.entrance.and_then(|pos| self.as_tile_index(&pos).ok())
+        else {
+            return;
+        };
+
+        for _ in 0..count {
+            let reached = self.reachable_tiles(registry, entrance, |i| {
+                !self.tiles[i].kind.is_locked(registry)
+            });
+            let candidates: Vec<_> = (0..self.tiles.len())
+                .filter(|&door| {
+                    reached[door] && self.tiles[door].kind == TileKind::DOOR
+                })
+                .filter_map(|door| {
+                    let reached_locked =
+                        self.reachable_tiles(registry, entrance, |i| {
+                            i != door && !self.tiles[i].kind.is_locked(registry)
+                        });
+                    let has_area_behind = (0..self.tiles.len())
+                        .any(|i| i != door && reached[i] && !reached_locked[i]);
+                    let keeps_items = self.items.iter().all(|item| {
+                        self.as_tile_index(&item.position)
+                            .is_ok_and(|i| reached_locked[i])
+                    });
+                    let keeps_reserved = (0..self.tiles.len())
+                        .all(|i| !self.is_reserved(i) || reached_locked[i]);
+                    let key_tiles: Vec<_> = (0..self.tiles.len())
+                        .filter(|&i| reached_locked[i])
+                        .filter(|&i| self.tiles[i].kind.is_walkable(registry))
+                        .filter(|&i| !self.is_reserved(i))
+                        .filter(|&i| {
+                            let pos = MapPosition::new(
+                                i % self.width,
+                                i / self.width,
+                            );
+                            self.item_at(&pos).is_none()
+                        })
+                        .collect();
+                    (has_area_behind
+                        && keeps_items
+                        && keeps_reserved
+                        && !key_tiles.is_empty())
+                    .then_some((door, key_tiles))
+                })
+                .collect();
+
+            let Some((door, key_tiles)) = candidates.choose(rng) else {
+                return;
+            };
+            let spawn_tiles: Vec<_> = key_tiles
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    self.item_spawns
+                        .iter()
+                        .any(|pos| self.as_tile_index(pos) == Ok(i))
+                })
+                .collect();
+            let key = if spawn_tiles.is_empty() {
+                *key_tiles.choose(rng).unwrap()
+            } else {
+                *spawn_tiles.choose(rng).unwrap()
+            };
+            self.tiles[*door].kind = TileKind::DOOR_LOCKED;
+            self.items.push(MapItem {
+                kind: ItemKind::Key,
+                position: MapPosition::new(key % self.width, key / self.width),
+            });
+        }
+    }
+
+    /// Returns whether all locked doors can be opened by a player starting
+    /// from the entrance, whatever the order they are opened in: every key
+    /// can be reached without opening a locked door, there are as many keys
+    /// as locked doors, and every locked door can be reached once the others
+    /// are opened.
+    pub fn are_locks_solvable(&self, registry: &TileRegistry) -> bool {
+        let locked: Vec<_> = (0..self.tiles.len())
+            .filter(|&i| self.tiles[i].kind.is_locked(registry))
+            .collect();
+        let Some(entrance) =
+            self.entrance.and_then(|pos| self.as_tile_index(&pos).ok())
+        else {
+            return locked.is_empty();
+        };
+
+        let reached =
+            self.reachable_tiles(registry, entrance, |i| !locked.contains(&i));
+        let reached_unlocked =
+            self.reachable_tiles(registry, entrance, |_| true);
+        let keys: Vec<_> = self
+            .items
+            .iter()
+            .filter(|item| item.kind == ItemKind::Key)
+            .map(|item| self.as_tile_index(&item.position))
+            .collect();
+
+        keys.len() >= locked.len()
+            && keys
+                .iter()
+                .all(|key| key.as_ref().is_ok_and(|&i| reached[i]))
+            && locked.iter().all(|&door| reached_unlocked[door])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_open_door() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["_+_"]);
+        map.entrance = Some(MapPosition::new(0, 0));
+        map.tiles[0].kind = TileKind::DOOR_LOCKED;
+        let mut inventory = Inventory::default();
+
+        assert!(!map.open_door(
+            &registry,
+            &MapPosition::new(0, 0),
+            &mut inventory
+        ));
+        assert!(!map.open_door(
+            &registry,
+            &MapPosition::new(2, 0),
+            &mut inventory
+        ));
+        assert!(map.open_door(
+            &registry,
+            &MapPosition::new(1, 0),
+            &mut inventory
+        ));
+        assert_eq!(TileKind::DOOR_OPEN, map.tiles[1].kind);
+        assert!(map.tiles[1].is_walkable(&registry));
+
+        inventory.0.push(ItemKind::Key);
+        assert!(map.open_door(
+            &registry,
+            &MapPosition::new(0, 0),
+            &mut inventory
+        ));
+        assert_eq!(TileKind::DOOR_OPEN, map.tiles[0].kind);
+        assert!(inventory.0.is_empty());
+    }
+
+    #[test]
+    fn test_lock_doors() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["__+__", "_####", "_+___"]);
+        map.entrance = Some(MapPosition::new(0, 0));
+        map.exits.push(MapExit {
+            position: MapPosition::new(4, 2),
+            edge: None,
+            destination: None,
+        });
+
+        for seed in 0..20 {
+            let mut map = map.clone();
+            let mut rng = StdRng::seed_from_u64(seed);
+            map.lock_doors(&registry, 2, &mut rng);
+
+            // the door leading to the exit is never locked
+            assert_eq!(TileKind::DOOR_LOCKED, map.tiles[2].kind);
+            assert_eq!(TileKind::DOOR, map.tiles[11].kind);
+            assert_eq!(1, map.items.len());
+            let key = map.items[0].position;
+            assert!(key.y > 0 || key.x < 2, "key behind its lock");
+            assert!(map.are_locks_solvable(&registry));
+        }
+
+        // a key behind its own lock can't be used
+        map.tiles[2].kind = TileKind::DOOR_LOCKED;
+        map.items.push(MapItem {
+            kind: ItemKind::Key,
+            position: MapPosition::new(4, 0),
+        });
+        assert!(!map.are_locks_solvable(&registry));
+        map.items[0].position = MapPosition::new(3, 2);
+        assert!(map.are_locks_solvable(&registry));
+    }
+
+    #[test]
+    fn test_lock_doors_on_item_spawns() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["___+_", "_####"]);
+        map.entrance = Some(MapPosition::new(0, 0));
+        map.item_spawns = vec![MapPosition::new(4, 0), MapPosition::new(1, 0)];
+
+        for seed in 0..10 {
+            let mut map = map.clone();
+            map.lock_doors(&registry, 1, &mut StdRng::seed_from_u64(seed));
+
+            // the item spawn behind the locked door can't hold its key
+            assert_eq!(TileKind::DOOR_LOCKED, map.tiles[3].kind);
+            assert_eq!(MapPosition::new(1, 0), map.items[0].position);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use rand::rngs::StdRng;
+
+use super::noise::floor_to_index;
+use super::tile::{Tile, TileKind};
+use crate::prelude::*;
+
+/// Generates winding caves by letting walkers dig randomly through stone
+/// (a.k.a. drunkard's walk).
+///
+/// Walkers are released in waves, each walker starting from an already dug
+/// tile, until the expected proportion of floor is reached. As every walker
+/// starts on the dug area, the caves are connected by construction. The map
+/// borders are never dug.
+pub struct DrunkardsWalk {
+    /// The proportion of the map's tiles to dig, between 0 and 1.
+    pub floor_coverage: f64,
+    /// The number of walkers released in each wave.
+    pub walkers: usize,
+    /// The maximum number of steps a walker takes before stopping.
+    pub walker_lifetime: usize,
+}
+
+impl Default for DrunkardsWalk {
+    fn default() -> Self {
+        Self {
+            floor_coverage: DRUNKARD_FLOOR_COVERAGE,
+            walkers: DRUNKARD_WALKERS,
+            walker_lifetime: DRUNKARD_WALKER_LIFETIME,
+        }
+    }
+}
+
+impl MapGenerator for DrunkardsWalk {
+    fn generate(
+        &self,
+        _registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map {
+        let mut is_dug = vec![false; width * height];
+
+        // keep a stone border when the map is large enough
+        let margin = usize::from(width >= 3 && height >= 3);
+        let (x_min, x_max) = (margin, width.saturating_sub(margin + 1));
+        let (y_min, y_max) = (margin, height.saturating_sub(margin + 1));
+
+        let diggable = (x_max + 1 - x_min) * (y_max + 1 - y_min);
+        let target = self.target_floor_count(diggable);
+
+        let start = width / 2 + height / 2 * width;
+        let mut dug = vec![start];
+        is_dug[start] = true;
+
+        'waves: while dug.len() < target {
+            let mut walkers: Vec<_> = (0..self.walkers.max(1))
+                .map(|_| *dug.choose(rng).unwrap())
+                .collect();
+
+            for _ in 0..self.walker_lifetime {
+                for walker in &mut walkers {
+                    let (x, y) = (*walker % width, *walker / width);
+                    let (x, y) = match rng.gen_range(0..4) {
+                        0 if x > x_min => (x - 1, y),
+                        1 if x < x_max => (x + 1, y),
+                        2 if y > y_min => (x, y - 1),
+                        3 if y < y_max => (x, y + 1),
+                        _ => (x, y),
+                    };
+                    *walker = x + y * width;
+
+                    if !is_dug[*walker] {
+                        is_dug[*walker] = true;
+                        dug.push(*walker);
+                        if dug.len() >= target {
+                            break 'waves;
+                        }
+                    }
+                }
+            }
+        }
+
+        let tiles = is_dug
+            .iter()
+            .map(|&is_dug| {
+                let kind = if is_dug {
+                    TileKind::GRASS
+                } else if rng.gen_bool(PROBABILITY_STONE_DAMAGED) {
+                    TileKind::GRASS_WITH_STONE_DAMAGED
+                } else {
+                    TileKind::GRASS_WITH_STONE
+                };
+                Tile::from_kind(kind)
+            })
+            .collect();
+
+        Map {
+            width,
+            height,
+            tiles,
+            ..Default::default()
+        }
+    }
+}
+
+impl DrunkardsWalk {
+    /// Returns the number of tiles to dig among the diggable ones, at least
+    /// one tile being dug.
+    fn target_floor_count(&self, diggable: usize) -> usize {
+        let coverage = self.floor_coverage.clamp(0.0, 1.0);
+        let diggable_count =
+            f64::from(u32::try_from(diggable).unwrap_or(u32::MAX));
+        let target = floor_to_index((coverage * diggable_count).ceil());
+        target.clamp(1, diggable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_floor(map: &Map) -> usize {
+        let registry = TileRegistry::shipped();
+        map.tiles
+            .iter()
+            .filter(|t| t.kind.is_walkable(&registry))
+            .count()
+    }
+
+    #[test]
+    fn test_drunkards_walk_coverage() {
+        let registry = TileRegistry::shipped();
+        for (coverage, expected) in
+            [(0.0, 1), (0.25, 81), (0.5, 162), (1.0, 324)]
+        {
+            let generator = DrunkardsWalk {
+                floor_coverage: coverage,
+                ..Default::default()
+            };
+
+            for seed in 0..20 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let map = generator.generate(&registry, 20, 20, &mut rng);
+                assert_eq!(expected, count_floor(&map));
+            }
+        }
+    }
+
+    #[test]
+    fn test_drunkards_walk_connectivity() {
+        let registry = TileRegistry::shipped();
+        let generator = DrunkardsWalk {
+            walkers: 8,
+            walker_lifetime: 10,
+            ..Default::default()
+        };
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let map = generator.generate(&registry, 30, 15, &mut rng);
+            assert_eq!(1, map.walkable_regions(&registry).len());
+        }
+    }
+
+    #[test]
+    fn test_drunkards_walk_keeps_borders() {
+        let registry = TileRegistry::shipped();
+        let generator = DrunkardsWalk {
+            floor_coverage: 1.0,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let (width, height) = (12, 7);
+        let map = generator.generate(&registry, width, height, &mut rng);
+
+        for (index, tile) in map.tiles.iter().enumerate() {
+            let (x, y) = (index % width, index / width);
+            let is_border =
+                x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            assert_eq!(!is_border, tile.kind.is_walkable(&registry));
+        }
+    }
+}
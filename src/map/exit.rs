@@ -0,0 +1,341 @@
+use super::tile::TileKind;
+use crate::prelude::*;
+
+/// Represents a side of a map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MapEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl MapEdge {
+    pub const ALL: [Self; 4] =
+        [Self::Left, Self::Right, Self::Top, Self::Bottom];
+
+    /// Returns the edge on the other side of the map.
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+        }
+    }
+}
+
+/// Represents a way out of a map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MapExit {
+    /// The position of the exit tile.
+    pub position: MapPosition,
+    /// The edge where the exit is, or `None` for stairs inside the map.
+    pub edge: Option<MapEdge>,
+    /// The id of the map the exit leads to (see `WorldGraph`), or `None` while
+    /// the exit isn't linked to another map.
+    pub destination: Option<usize>,
+}
+
+impl MapExit {
+    /// Returns the edge where the player arrives on the destination map,
+    /// i.e. the opposite of the edge the player left from. Stairs have no
+    /// arrival edge.
+    pub fn arrival_edge(&self) -> Option<MapEdge> {
+        self.edge.map(MapEdge::opposite)
+    }
+}
+
+impl Map {
+    /// Returns the exit at a given position, if any.
+    pub fn exit_at(&self, pos: &MapPosition) -> Option<&MapExit> {
+        self.exits.iter().find(|exit| exit.position == *pos)
+    }
+
+    /// Sets the entrance of the map, where the player arrives. The entrance is
+    /// placed on a given edge, or on any walkable tile if there's no edge.
+    ///
+    /// If the edge has no walkable tile, a corridor is carved from the
+    /// closest walkable tile up to the edge, so the entrance stays connected
+    /// to the rest of the map. The corridor leaves the `protected` areas
+    /// (e.g. vaults) unchanged.
+    pub fn add_entrance(
+        &mut self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        edge: Option<MapEdge>,
+        rng: &mut impl Rng,
+    ) {
+        let index = match edge {
+            Some(edge) => self.carve_to_edge(registry, protected, edge, rng),
+            None => self
+                .free_walkable_tiles(registry, false)
+                .choose(rng)
+                .copied(),
+        };
+
+        self.entrance = index.map(|index| {
+            MapPosition::new(index % self.width, index / self.width)
+        });
+    }
+
+    /// Adds an exit, not linked to another map yet, on a given edge or inside
+    /// the map (stairs) if there's no edge. Edge exits are connected like the
+    /// entrance (see `Map::add_entrance`), and stairs are never placed in the
+    /// `protected` areas.
+    ///
+    /// # Returns
+    ///
+    /// The added exit, or `None` if there's no room left for it.
+    pub fn add_exit(
+        &mut self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        edge: Option<MapEdge>,
+        rng: &mut impl Rng,
+    ) -> Option<MapExit> {
+        let index = if let Some(edge) = edge {
+            self.carve_to_edge(registry, protected, edge, rng)
+        } else {
+            // stairs go on the edges only when the interior is full
+            let unprotected = |tiles: Vec<usize>| -> Vec<usize> {
+                tiles
+                    .into_iter()
+                    .filter(|&index| !self.is_in_areas(protected, index))
+                    .collect()
+            };
+            let interior =
+                unprotected(self.free_walkable_tiles(registry, true));
+            let tiles = if interior.is_empty() {
+                unprotected(self.free_walkable_tiles(registry, false))
+            } else {
+                interior
+            };
+            tiles.choose(rng).copied()
+        }?;
+
+        self.tiles[index].kind = TileKind::LEVEL_EXIT;
+        let exit = MapExit {
+            position: MapPosition::new(index % self.width, index / self.width),
+            edge,
+            destination: None,
+        };
+        self.exits.push(exit);
+        Some(exit)
+    }
+
+    /// Adds between `MAP_EXITS_MIN` and `MAP_EXITS_MAX` unlinked exits. The
+    /// exits are either stairs or on an edge, except the entrance's edge, and
+    /// leave the `protected` areas unchanged (see `Map::add_exit`).
+    pub fn add_exits(
+        &mut self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        entrance_edge: Option<MapEdge>,
+        rng: &mut impl Rng,
+    ) {
+        let edges: Vec<_> = MapEdge::ALL
+            .into_iter()
+            .filter(|edge| Some(*edge) != entrance_edge)
+            .collect();
+
+        for _ in 0..rng.gen_range(MAP_EXITS_MIN..=MAP_EXITS_MAX) {
+            let edge = if rng.gen_bool(PROBABILITY_EXIT_STAIRS) {
+                None
+            } else {
+                edges.choose(rng).copied()
+            };
+            self.add_exit(registry, protected, edge, rng);
+        }
+    }
+
+    /// Returns the walkable tiles which are neither the entrance nor an exit,
+    /// optionally excluding the tiles on the map edges.
+    fn free_walkable_tiles(
+        &self,
+        registry: &TileRegistry,
+        interior_only: bool,
+    ) -> Vec<usize> {
+        (0..self.tiles.len())
+            .filter(|&index| self.tiles[index].kind.is_walkable(registry))
+            .filter(|&index| !self.is_reserved(index))
+            .filter(|&index| {
+                let (x, y) = (index % self.width, index / self.width);
+                !interior_only
+                    || (x > 0
+                        && y > 0
+                        && x < self.width - 1
+                        && y < self.height - 1)
+            })
+            .collect()
+    }
+
+    /// Returns whether a tile is the entrance or an exit.
+    pub fn is_reserved(&self, index: usize) -> bool {
+        let pos = MapPosition::new(index % self.width, index / self.width);
+        self.entrance == Some(pos) || self.exit_at(&pos).is_some()
+    }
+
+    /// Returns the distance between a tile and an edge, and the index of the
+    /// tile on the edge facing it.
+    const fn project_on_edge(
+        &self,
+        index: usize,
+        edge: MapEdge,
+    ) -> (usize, usize) {
+        let (x, y) = (index % self.width, index / self.width);
+        match edge {
+            MapEdge::Left => (x, y * self.width),
+            MapEdge::Right => {
+                (self.width - 1 - x, self.width - 1 + y * self.width)
+            }
+            MapEdge::Top => (y, x),
+            MapEdge::Bottom => {
+                (self.height - 1 - y, x + (self.height - 1) * self.width)
+            }
+        }
+    }
+
+    /// Selects randomly one of the walkable tiles closest to an edge, and
+    /// carves a straight corridor from it to the edge. The corridor only
+    /// crosses the `protected` areas through their passable tiles.
+    ///
+    /// # Returns
+    ///
+    /// The index of the corridor's tile on the edge, or `None` if there's no
+    /// walkable tile whose edge tile is free.
+    fn carve_to_edge(
+        &mut self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        edge: MapEdge,
+        rng: &mut impl Rng,
+    ) -> Option<usize> {
+        let candidates: Vec<_> = (0..self.tiles.len())
+            .filter(|&index| self.tiles[index].kind.is_walkable(registry))
+            .map(|index| (index, self.project_on_edge(index, edge)))
+            .filter(|&(_, (_, projection))| !self.is_reserved(projection))
+            .filter(|&(index, (_, projection))| {
+                self.can_carve_corridor(registry, protected, index, projection)
+            })
+            .collect();
+
+        let distance_min = candidates
+            .iter()
+            .map(|(_, (distance, _))| *distance)
+            .min()?;
+        let closest: Vec<_> = candidates
+            .into_iter()
+            .filter(|(_, (distance, _))| *distance == distance_min)
+            .collect();
+
+        let &(index, (_, projection)) = closest.choose(rng)?;
+        let kind = self.tiles[index].kind.corridor_kind();
+        self.carve_corridor(registry, index, projection, kind);
+        Some(projection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tile::Tile;
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn create_stone_map(width: usize, height: usize) -> Map {
+        let mut map = Map {
+            width,
+            height,
+            tiles: vec![
+                Tile::from_kind(TileKind::GRASS_WITH_STONE);
+                width * height
+            ],
+            ..Default::default()
+        };
+        let center = width / 2 + height / 2 * width;
+        map.tiles[center].kind = TileKind::GRASS;
+        map
+    }
+
+    const fn is_on_edge(map: &Map, pos: &MapPosition, edge: MapEdge) -> bool {
+        match edge {
+            MapEdge::Left => pos.x == 0,
+            MapEdge::Right => pos.x == map.width - 1,
+            MapEdge::Top => pos.y == 0,
+            MapEdge::Bottom => pos.y == map.height - 1,
+        }
+    }
+
+    #[test]
+    fn test_entrance_and_exits_on_edges() {
+        let registry = TileRegistry::shipped();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for edge in MapEdge::ALL {
+            let mut map = create_stone_map(15, 9);
+            map.add_entrance(&registry, &[], Some(edge), &mut rng);
+            let entrance = map.entrance.unwrap();
+            assert!(is_on_edge(&map, &entrance, edge));
+
+            let exit =
+                map.add_exit(&registry, &[], Some(edge.opposite()), &mut rng);
+            let exit = exit.unwrap();
+            assert!(is_on_edge(&map, &exit.position, edge.opposite()));
+            assert_eq!(None, exit.destination);
+            assert_eq!(Some(edge), exit.arrival_edge());
+
+            let index = map.as_tile_index(&exit.position).unwrap();
+            assert_eq!(TileKind::LEVEL_EXIT, map.tiles[index].kind);
+            assert_eq!(1, map.walkable_regions(&registry).len());
+        }
+    }
+
+    #[test]
+    fn test_stairs_are_inside_the_map() {
+        let registry = TileRegistry::shipped();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut map = create_stone_map(15, 9);
+        map.add_entrance(&registry, &[], None, &mut rng);
+        assert_eq!(Some(MapPosition::new(7, 4)), map.entrance);
+
+        // the only walkable tile is the entrance
+        assert_eq!(None, map.add_exit(&registry, &[], None, &mut rng));
+
+        let mut map = Map {
+            width: 5,
+            height: 4,
+            tiles: vec![Tile::default(); 20],
+            ..Default::default()
+        };
+        for _ in 0..6 {
+            let exit = map.add_exit(&registry, &[], None, &mut rng).unwrap();
+            assert_eq!(None, exit.edge);
+            assert!(MapEdge::ALL.iter().all(|edge| !is_on_edge(
+                &map,
+                &exit.position,
+                *edge
+            )));
+        }
+        // once the interior is full, stairs go on the edges
+        assert!(map.add_exit(&registry, &[], None, &mut rng).is_some());
+        assert_eq!(7, map.exits.len());
+    }
+
+    #[test]
+    fn test_exits_avoid_entrance_edge() {
+        let registry = TileRegistry::shipped();
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut map = create_stone_map(20, 12);
+            map.add_entrance(&registry, &[], Some(MapEdge::Left), &mut rng);
+            map.add_exits(&registry, &[], Some(MapEdge::Left), &mut rng);
+
+            assert!((MAP_EXITS_MIN..=MAP_EXITS_MAX).contains(&map.exits.len()));
+            for exit in &map.exits {
+                assert_ne!(Some(MapEdge::Left), exit.edge);
+                assert_ne!(map.entrance, Some(exit.position));
+            }
+            assert_eq!(1, map.walkable_regions(&registry).len());
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use crate::prelude::*;
+
+/// Represents the tiles of the current map seen by the player, computed with
+/// symmetric shadowcasting (see `Map::field_of_view`).
+///
+/// The field of view is symmetric: the player sees a tile if and only if an
+/// actor standing on that tile would see the player. Hence, the mobs use it
+/// for knowing whether they see the player.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct FieldOfView {
+    /// The map's width.
+    pub width: usize,
+    /// Whether each tile of the map is visible, stored like the map's tiles.
+    pub visible: Vec<bool>,
+}
+
+/// Represents how much the player knows about a tile (see
+/// `Map::tile_visibility`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TileVisibility {
+    /// The tile has never been seen, and isn't displayed.
+    Unseen,
+    /// The tile has been seen before, and is displayed dimmed.
+    Remembered,
+    /// The tile is in the player's field of view.
+    Visible,
+}
+
+impl TileVisibility {
+    /// Returns the color multiplied with the sprite of a tile of a given kind.
+    pub fn sprite_color(
+        self,
+        registry: &TileRegistry,
+        kind: TileKind,
+    ) -> Color {
+        let color = kind.sprite_color(registry);
+        match self {
+            Self::Remembered => Color::rgba(
+                color.r() * FOG_REMEMBERED_BRIGHTNESS,
+                color.g() * FOG_REMEMBERED_BRIGHTNESS,
+                color.b() * FOG_REMEMBERED_BRIGHTNESS,
+                color.a(),
+            ),
+            Self::Unseen | Self::Visible => color,
+        }
+    }
+
+    /// Returns the `Visibility` of the sprites displayed on a tile.
+    pub const fn as_visibility(self) -> Visibility {
+        match self {
+            Self::Unseen => Visibility::Hidden,
+            Self::Remembered | Self::Visible => Visibility::Inherited,
+        }
+    }
+}
+
+impl FieldOfView {
+    /// Creates a `FieldOfView` where all the tiles of a map are visible.
+    pub fn full(map: &Map) -> Self {
+        Self {
+            width: map.width,
+            visible: vec![true; map.tiles.len()],
+        }
+    }
+
+    /// Returns whether the tile at a given position is visible.
+    pub fn is_visible(&self, pos: &MapPosition) -> bool {
+        pos.x < self.width
+            && self
+                .visible
+                .get(pos.x + pos.y * self.width)
+                .copied()
+                .unwrap_or(false)
+    }
+}
+
+/// Represents a slope as an exact fraction, so the visibility of tiles on the
+/// edge of a shadow doesn't depend on rounding errors.
+#[derive(Clone, Copy)]
+struct Slope {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Slope {
+    const fn new(numerator: i64, denominator: i64) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Returns the slope of a tile's left edge, seen from the origin.
+    const fn of_tile(depth: i64, column: i64) -> Self {
+        Self::new(2 * column - 1, 2 * depth)
+    }
+
+    /// Returns `round(depth * self)`, the ties being rounded up.
+    const fn round_ties_up(self, depth: i64) -> i64 {
+        (2 * depth * self.numerator + self.denominator)
+            .div_euclid(2 * self.denominator)
+    }
+
+    /// Returns `round(depth * self)`, the ties being rounded down.
+    const fn round_ties_down(self, depth: i64) -> i64 {
+        -(self.denominator - 2 * depth * self.numerator)
+            .div_euclid(2 * self.denominator)
+    }
+}
+
+/// Converts the depth and the column of a tile in a quadrant into an offset
+/// from the origin.
+type Quadrant = fn(i64, i64) -> (i64, i64);
+
+/// Represents a row of tiles scanned in a quadrant, between two slopes.
+#[derive(Clone, Copy)]
+struct Row {
+    depth: i64,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// Returns the columns of the row's tiles.
+    const fn columns(&self) -> std::ops::RangeInclusive<i64> {
+        self.start.round_ties_up(self.depth)
+            ..=self.end.round_ties_down(self.depth)
+    }
+
+    /// Returns whether a tile of the row is within the row's slopes, i.e. seen
+    /// symmetrically from the origin.
+    const fn is_symmetric(&self, column: i64) -> bool {
+        column * self.start.denominator >= self.depth * self.start.numerator
+            && column * self.end.denominator <= self.depth * self.end.numerator
+    }
+
+    const fn next(&self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+impl Map {
+    /// Adds the tiles visible in a given field of view to the tiles
+    /// remembered by the player.
+    pub fn remember(&mut self, fov: &FieldOfView) {
+        self.remembered.resize(self.tiles.len(), false);
+        for (remembered, visible) in
+            self.remembered.iter_mut().zip(&fov.visible)
+        {
+            *remembered |= visible;
+        }
+    }
+
+    /// Returns how much the player knows about the tile at a given position,
+    /// given their current field of view.
+    pub fn tile_visibility(
+        &self,
+        pos: &MapPosition,
+        fov: &FieldOfView,
+    ) -> TileVisibility {
+        let remembered = self
+            .as_tile_index(pos)
+            .is_ok_and(|i| self.remembered.get(i).copied().unwrap_or(false));
+        if fov.is_visible(pos) {
+            TileVisibility::Visible
+        } else if remembered {
+            TileVisibility::Remembered
+        } else {
+            TileVisibility::Unseen
+        }
+    }
+
+    /// Computes the tiles seen from a given position with symmetric
+    /// shadowcasting, up to `radius` tiles away. The tiles which aren't
+    /// transparent (see `TileKind::is_transparent`) block the sight, but are
+    /// visible themselves.
+    ///
+    /// The map is scanned in four quadrants (north, east, south and west),
+    /// row by row from the origin. Each row keeps track of the slopes between
+    /// which the tiles aren't shadowed by the previous rows.
+    pub fn field_of_view(
+        &self,
+        registry: &TileRegistry,
+        origin: &MapPosition,
+        radius: usize,
+    ) -> FieldOfView {
+        let mut fov = FieldOfView {
+            width: self.width,
+            visible: vec![false; self.tiles.len()],
+        };
+        let Ok(index) = self.as_tile_index(origin) else {
+            return fov;
+        };
+        fov.visible[index] = true;
+
+        let radius = i64::try_from(radius).unwrap_or(i64::MAX);
+        let quadrants: [Quadrant; 4] = [
+            |depth, column| (column, -depth),
+            |depth, column| (depth, column),
+            |depth, column| (column, depth),
+            |depth, column| (-depth, column),
+        ];
+
+        for transform in quadrants {
+            let index_of = |depth: i64, column: i64| {
+                let (dx, dy) = transform(depth, column);
+                let tx =
+                    origin.x.checked_add_signed(isize::try_from(dx).ok()?)?;
+                let ty =
+                    origin.y.checked_add_signed(isize::try_from(dy).ok()?)?;
+                (tx < self.width && ty < self.height)
+                    .then_some(tx + ty * self.width)
+            };
+            // the tiles out of the map block the sight
+            let is_wall = |index: Option<usize>| {
+                index.is_none_or(|i| {
+                    !self.tiles[i].kind.is_transparent(registry)
+                })
+            };
+
+            let mut rows = vec![Row {
+                depth: 1,
+                start: Slope::new(-1, 1),
+                end: Slope::new(1, 1),
+            }];
+            while let Some(mut row) = rows.pop() {
+                if row.depth > radius {
+                    continue;
+                }
+                let mut previous: Option<Option<usize>> = None;
+                for column in row.columns() {
+                    let tile = index_of(row.depth, column);
+                    let within_radius = row.depth * row.depth + column * column
+                        <= radius * radius;
+                    if let Some(index) = tile {
+                        if within_radius
+                            && (is_wall(tile) || row.is_symmetric(column))
+                        {
+                            fov.visible[index] = true;
+                        }
+                    }
+                    if let Some(previous) = previous {
+                        if is_wall(previous) && !is_wall(tile) {
+                            row.start = Slope::of_tile(row.depth, column);
+                        }
+                        if !is_wall(previous) && is_wall(tile) {
+                            let mut next = row.next();
+                            next.end = Slope::of_tile(row.depth, column);
+                            rows.push(next);
+                        }
+                    }
+                    previous = Some(tile);
+                }
+                if previous.is_some_and(|tile| !is_wall(tile)) {
+                    rows.push(row.next());
+                }
+            }
+        }
+        fov
+    }
+}
+
+/// Computes the `FieldOfView` from the player's position on the current map,
+/// and remembers the tiles seen. The whole overworld is always visible.
+pub fn update_field_of_view(
+    mut fov: ResMut<FieldOfView>,
+    mut q_map: Query<&mut Map, With<OnDisplay>>,
+    q_actors: Query<(&MapPosition, &Actor), With<OnDisplay>>,
+    current_map_number: Res<CurrentMapNumber>,
+    registry: Res<TileRegistry>,
+) {
+    let Ok(mut map) = q_map.get_single_mut() else {
+        return;
+    };
+    if current_map_number.0 == OVERWORLD_MAP_ID {
+        *fov = FieldOfView::full(&map);
+        map.remember(&fov);
+        return;
+    }
+    let Some((pos_player, _)) =
+        q_actors.iter().find(|(_, actor)| actor.is_player())
+    else {
+        return;
+    };
+    *fov = map.field_of_view(&registry, pos_player, FOV_RADIUS);
+    map.remember(&fov);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the position of the player on a map parsed for tests (see
+    /// `Map::parse_rows`), i.e. the field of view origin.
+    fn player_position(map: &Map) -> MapPosition {
+        let index = map
+            .tiles
+            .iter()
+            .position(|tile| tile.actor.is_some_and(|a| a.is_player()))
+            .unwrap();
+        MapPosition::new(index % map.width, index / map.width)
+    }
+
+    /// Renders the visible tiles as `v` and the others as `-`.
+    fn render(fov: &FieldOfView, map: &Map) -> Vec<String> {
+        (0..map.height)
+            .map(|y| {
+                (0..map.width)
+                    .map(|x| {
+                        if fov.is_visible(&MapPosition::new(x, y)) {
+                            'v'
+                        } else {
+                            '-'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_open_room_is_visible() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["#####", "#___#", "#_@_#", "#####"]);
+        let origin = player_position(&map);
+        let fov = map.field_of_view(&registry, &origin, 10);
+        assert!(fov.visible.iter().all(|visible| *visible));
+    }
+
+    #[test]
+    fn test_walls_and_stones_block_sight() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&[
+            "_______", //
+            "__#____", "__@_o__", "_______",
+        ]);
+        let origin = player_position(&map);
+        let fov = map.field_of_view(&registry, &origin, 10);
+        assert_eq!(
+            vec!["vv-vvvv", "vvvvvvv", "vvvvv--", "vvvvvvv"],
+            render(&fov, &map)
+        );
+    }
+
+    #[test]
+    fn test_radius_limits_sight() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["@_____"]);
+        let origin = player_position(&map);
+        let fov = map.field_of_view(&registry, &origin, 3);
+        assert_eq!(vec!["vvvv--"], render(&fov, &map));
+    }
+
+    #[test]
+    fn test_remembered_tiles() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["__#__"]);
+        let (left, right) = (MapPosition::new(0, 0), MapPosition::new(4, 0));
+        let fov_right = map.field_of_view(&registry, &right, 10);
+        assert_eq!(
+            TileVisibility::Unseen,
+            map.tile_visibility(&left, &fov_right)
+        );
+
+        map.remember(&map.field_of_view(&registry, &left, 10));
+        map.remember(&fov_right);
+        let visibilities: Vec<_> = (0..map.width)
+            .map(|x| map.tile_visibility(&MapPosition::new(x, 0), &fov_right))
+            .collect();
+        assert_eq!(
+            vec![
+                TileVisibility::Remembered,
+                TileVisibility::Remembered,
+                TileVisibility::Visible,
+                TileVisibility::Visible,
+                TileVisibility::Visible,
+            ],
+            visibilities
+        );
+        assert_eq!(
+            TileVisibility::Unseen,
+            Map::default().tile_visibility(&left, &fov_right)
+        );
+    }
+
+    #[test]
+    fn test_field_of_view_is_symmetric() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&[
+            "__________",
+            "_#___o____",
+            "____#__#__",
+            "_o________",
+            "___##_____",
+            "_______o__",
+        ]);
+        let positions: Vec<_> = (0..map.tiles.len())
+            .filter(|&i| map.tiles[i].kind.is_transparent(&registry))
+            .map(|i| MapPosition::new(i % map.width, i / map.width))
+            .collect();
+        let fovs: Vec<_> = positions
+            .iter()
+            .map(|pos| map.field_of_view(&registry, pos, 20))
+            .collect();
+
+        for (a, fov_a) in positions.iter().zip(&fovs) {
+            for (b, fov_b) in positions.iter().zip(&fovs) {
+                assert_eq!(
+                    fov_a.is_visible(b),
+                    fov_b.is_visible(a),
+                    "{a:?} and {b:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+
+use super::noise::PerlinNoise;
+use crate::prelude::*;
+
+/// Represents an algorithm generating the tiles of a `Map`.
+pub trait MapGenerator: Send + Sync {
+    /// Generates a `Map` of dimensions `width`x`height`, the tile kinds being
+    /// described by `registry`. All random decisions must be taken with `rng`,
+    /// so the same generator state gives the same map.
+    fn generate(
+        &self,
+        registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map;
+}
+
+/// Generates maps with Perlin noise, producing open outdoor layouts.
+pub struct PerlinNoiseGenerator;
+
+impl MapGenerator for PerlinNoiseGenerator {
+    fn generate(
+        &self,
+        _registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map {
+        let noise = PerlinNoise::new(rng);
+        Map::from_perlin_noise(&noise, width, height, rng)
+    }
+}
+
+/// Generates maps with a cellular automaton, producing organic cave-like
+/// layouts.
+pub struct CellularAutomatonGenerator {
+    /// The probability for a cell to be alive at initialization.
+    pub alive_probability: f64,
+    /// The number of transitions applied before smoothing the automaton.
+    pub transitions: usize,
+    /// The rule applied at each transition.
+    pub rule: CellularRule,
+    /// The rule applied repeatedly once the transitions are done, until the
+    /// automaton is stable.
+    pub smoothing_rule: CellularRule,
+    /// The cells considered as neighbors.
+    pub neighborhood: Neighborhood,
+    /// How the neighbors outside of the map are handled.
+    pub edges: EdgeHandling,
+}
+
+impl Default for CellularAutomatonGenerator {
+    fn default() -> Self {
+        Self {
+            alive_probability: CELLULAR_AUTOMATON_ALIVE_PROBABILITY,
+            transitions: CELLULAR_AUTOMATON_TRANSITIONS,
+            rule: CELLULAR_AUTOMATON_RULE
+                .parse()
+                .expect("invalid cellular automaton rule"),
+            smoothing_rule: CELLULAR_AUTOMATON_SMOOTHING_RULE
+                .parse()
+                .expect("invalid cellular automaton smoothing rule"),
+            neighborhood: Neighborhood::Moore,
+            edges: EdgeHandling::Ignore,
+        }
+    }
+}
+
+impl FromStr for CellularAutomatonGenerator {
+    type Err = String;
+
+    /// Parses the generator's settings, written in a `[cellular_automaton]`
+    /// section with one property per field, e.g. `rule = B3/S23` or
+    /// `edges = wrap`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid number '{value}'"))
+        }
+
+        let sections = parse_sections(s)?;
+        let section = sections
+            .iter()
+            .find(|section| section.name == "cellular_automaton")
+            .ok_or("missing section 'cellular_automaton'")?;
+
+        Ok(Self {
+            alive_probability: section.parse("alive_probability", |value| {
+                parse(value).and_then(|p: f64| {
+                    if (0.0..=1.0).contains(&p) {
+                        Ok(p)
+                    } else {
+                        Err(format!("invalid probability '{value}'"))
+                    }
+                })
+            })?,
+            transitions: section.parse("transitions", parse)?,
+            rule: section.parse("rule", str::parse)?,
+            smoothing_rule: section.parse("smoothing_rule", str::parse)?,
+            neighborhood: section.parse("neighborhood", str::parse)?,
+            edges: section.parse("edges", str::parse)?,
+        })
+    }
+}
+
+impl CellularAutomatonGenerator {
+    /// Loads the generator's settings from a file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse())
+            .map_err(|e| {
+                format!("invalid cellular automaton {}: {e}", path.display())
+            })
+    }
+}
+
+impl MapGenerator for CellularAutomatonGenerator {
+    fn generate(
+        &self,
+        _registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map {
+        let mut ca =
+            CellularAutomaton::new(width, height, self.alive_probability, rng)
+                .with_rule(self.rule)
+                .with_neighborhood(self.neighborhood)
+                .with_edges(self.edges);
+        for _ in 0..self.transitions {
+            ca.transition();
+        }
+        ca.smooth(&self.smoothing_rule);
+        Map::from_cellular_automaton(&ca, rng)
+    }
+}
+
+/// Represents a generator registered in the `MapGeneratorRegistry`, along
+/// with the rules deciding when it is selected.
+pub struct MapGeneratorEntry {
+    /// The name identifying the generator.
+    pub name: &'static str,
+    /// The generator itself.
+    pub generator: Box<dyn MapGenerator>,
+    /// The relative chance for the generator to be selected among the other
+    /// eligible generators.
+    pub weight: u32,
+    /// The map depths (see `WorldNode::depth`) where the generator is
+    /// eligible.
+    pub depths: RangeInclusive<usize>,
+    /// The locations where the generator is eligible.
+    pub locations: &'static [LocationKind],
+    /// The maximum number of vaults stamped into the generated maps.
+    pub vaults: usize,
+    /// The maximum number of doors locked in the generated maps, with their
+    /// keys (see `Map::lock_doors`).
+    pub locked_doors: usize,
+    /// The maximum number of traps set in the generated maps.
+    pub traps: usize,
+}
+
+impl MapGeneratorEntry {
+    /// Generates a `Map` of dimensions `width`x`height` with the entry's
+    /// generator, then post-processes it: vaults are stamped into the map, the
+    /// walkable regions are connected together, then the entrance and the
+    /// exits are added, so the exits can always be reached from any walkable
+    /// tile. Lastly, doors are locked and their keys placed, then traps are
+    /// set.
+    ///
+    /// # Arguments
+    ///
+    /// - `entrance_edge`: The edge where the entrance is placed, or `None`
+    ///   for an entrance anywhere on the map.
+    /// - `vaults`: The vaults which can be stamped into the map.
+    pub fn generate(
+        &self,
+        registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        entrance_edge: Option<MapEdge>,
+        vaults: &[Vault],
+        rng: &mut StdRng,
+    ) -> Map {
+        let mut map = self.generator.generate(registry, width, height, rng);
+        let vault_areas = map.stamp_vaults(vaults, self.vaults, rng);
+        map.connect_regions(registry, &vault_areas);
+        map.add_entrance(registry, &vault_areas, entrance_edge, rng);
+        map.add_exits(registry, &vault_areas, entrance_edge, rng);
+        map.lock_doors(registry, self.locked_doors, rng);
+        debug_assert!(
+            map.are_locks_solvable(registry),
+            "unsolvable locked doors"
+        );
+        map.set_traps(registry, self.traps, rng);
+        map
+    }
+
+    /// Creates an entry with a weight of 1, eligible at every depth of every
+    /// location, without vaults, locked doors nor traps.
+    pub fn new(
+        name: &'static str,
+        generator: impl MapGenerator + 'static,
+    ) -> Self {
+        Self {
+            name,
+            generator: Box::new(generator),
+            weight: 1,
+            depths: 0..=usize::MAX,
+            locations: &LocationKind::ALL,
+            vaults: 0,
+            locked_doors: 0,
+            traps: 0,
+        }
+    }
+
+    /// Sets the relative chance for the generator to be selected.
+    #[must_use]
+    pub const fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets the map depths where the generator is eligible.
+    #[must_use]
+    pub const fn with_depths(mut self, depths: RangeInclusive<usize>) -> Self {
+        self.depths = depths;
+        self
+    }
+
+    /// Sets the locations where the generator is eligible.
+    #[must_use]
+    pub const fn with_locations(
+        mut self,
+        locations: &'static [LocationKind],
+    ) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Sets the maximum number of vaults stamped into the generated maps.
+    #[must_use]
+    pub const fn with_vaults(mut self, vaults: usize) -> Self {
+        self.vaults = vaults;
+        self
+    }
+
+    /// Sets the maximum number of doors locked in the generated maps.
+    #[must_use]
+    pub const fn with_locked_doors(mut self, locked_doors: usize) -> Self {
+        self.locked_doors = locked_doors;
+        self
+    }
+
+    /// Sets the maximum number of traps set in the generated maps.
+    #[must_use]
+    pub const fn with_traps(mut self, traps: usize) -> Self {
+        self.traps = traps;
+        self
+    }
+}
+
+/// Lists the map generators available for creating new maps.
+///
+/// By default, forests are outdoor layouts, caves are organic layouts and
+/// ruins are dungeons. The levels under forests are caves, which may also
+/// appear under the other locations.
+#[derive(Resource)]
+pub struct MapGeneratorRegistry {
+    pub entries: Vec<MapGeneratorEntry>,
+}
+
+impl MapGeneratorRegistry {
+    /// Creates the registry of the generators used by the game, the caves
+    /// being generated by `cellular_automaton`. The meadows are left out
+    /// without a `meadow` generator, the forests being generated with Perlin
+    /// noise.
+    pub fn new(
+        cellular_automaton: CellularAutomatonGenerator,
+        meadow: Option<WaveFunctionCollapse>,
+    ) -> Self {
+        let mut registry = Self { entries: vec![] };
+        registry
+            .register(
+                MapGeneratorEntry::new("perlin", PerlinNoiseGenerator)
+                    .with_depths(0..=0)
+                    .with_locations(&[LocationKind::Forest])
+                    .with_vaults(VAULTS_PER_MAP),
+            )
+            .register(
+                MapGeneratorEntry::new(
+                    "cellular_automaton",
+                    cellular_automaton,
+                )
+                .with_weight(2)
+                .with_locations(&[LocationKind::Cave])
+                .with_vaults(VAULTS_PER_MAP)
+                .with_traps(TRAPS_PER_MAP),
+            )
+            .register(
+                MapGeneratorEntry::new(
+                    "drunkards_walk",
+                    DrunkardsWalk::default(),
+                )
+                .with_depths(1..=usize::MAX),
+            )
+            .register(
+                MapGeneratorEntry::new(
+                    "bsp",
+                    BinarySpacePartitioning::default(),
+                )
+                .with_weight(2)
+                .with_locations(&[LocationKind::Ruins])
+                .with_vaults(VAULTS_PER_MAP)
+                .with_locked_doors(LOCKED_DOORS_PER_MAP)
+                .with_traps(TRAPS_PER_MAP),
+            );
+
+        if let Some(meadow) = meadow {
+            registry.register(
+                MapGeneratorEntry::new("wfc_meadow", meadow)
+                    .with_depths(0..=0)
+                    .with_locations(&[LocationKind::Forest])
+                    .with_vaults(VAULTS_PER_MAP),
+            );
+        }
+        registry
+    }
+
+    /// Adds a generator to the registry.
+    pub fn register(&mut self, entry: MapGeneratorEntry) -> &mut Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Selects randomly a generator eligible for a given location and map
+    /// depth, the generators' weights being taken into account. Returns
+    /// `None` if no generator is eligible.
+    pub fn select(
+        &self,
+        location: LocationKind,
+        depth: usize,
+        rng: &mut impl Rng,
+    ) -> Option<&MapGeneratorEntry> {
+        let eligible: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.depths.contains(&depth))
+            .filter(|entry| entry.locations.contains(&location))
+            .collect();
+
+        eligible
+            .choose_weighted(rng, |entry| entry.weight)
+            .ok()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+impl MapGeneratorRegistry {
+    /// Returns the registry of the generators set up by the shipped files.
+    pub fn shipped() -> Self {
+        Self::new(
+            CellularAutomatonGenerator::load(&asset_path(
+                CELLULAR_AUTOMATON_FILE,
+            ))
+            .unwrap(),
+            Some(
+                WaveFunctionCollapse::load(&asset_path(WFC_SAMPLE_MEADOW_FILE))
+                    .unwrap(),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tile::TileKind;
+    use super::*;
+
+    fn create_registry() -> MapGeneratorRegistry {
+        let mut registry = MapGeneratorRegistry { entries: vec![] };
+        registry
+            .register(
+                MapGeneratorEntry::new("shallow", PerlinNoiseGenerator)
+                    .with_depths(0..=2),
+            )
+            .register(
+                MapGeneratorEntry::new("deep", PerlinNoiseGenerator)
+                    .with_depths(3..=usize::MAX),
+            )
+            .register(
+                MapGeneratorEntry::new("ruins", PerlinNoiseGenerator)
+                    .with_locations(&[LocationKind::Ruins]),
+            )
+            .register(
+                MapGeneratorEntry::new("disabled", PerlinNoiseGenerator)
+                    .with_weight(0),
+            );
+        registry
+    }
+
+    #[test]
+    fn test_select_respects_depths_and_weights() {
+        let registry = create_registry();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut select = |depth| {
+            registry
+                .select(LocationKind::Forest, depth, &mut rng)
+                .unwrap()
+                .name
+        };
+        for _ in 0..100 {
+            assert_eq!("shallow", select(0));
+            assert_eq!("shallow", select(2));
+            assert_eq!("deep", select(3));
+        }
+    }
+
+    #[test]
+    fn test_select_respects_locations() {
+        let registry = create_registry();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let names: Vec<_> = (0..100)
+            .map(|_| {
+                registry
+                    .select(LocationKind::Ruins, 0, &mut rng)
+                    .unwrap()
+                    .name
+            })
+            .collect();
+        assert!(names.contains(&"ruins"));
+        assert!(names.contains(&"shallow"));
+        for _ in 0..100 {
+            let entry = registry.select(LocationKind::Cave, 0, &mut rng);
+            assert_ne!("ruins", entry.unwrap().name);
+        }
+    }
+
+    #[test]
+    fn test_select_without_eligible_generator() {
+        let registry = MapGeneratorRegistry { entries: vec![] };
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(registry.select(LocationKind::Forest, 0, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_default_generators_cover_locations() {
+        let registry = MapGeneratorRegistry::shipped();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for location in LocationKind::ALL {
+            for depth in 0..10 {
+                assert!(registry.select(location, depth, &mut rng).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_cellular_automaton_generator() {
+        let generator = CellularAutomatonGenerator::load(&asset_path(
+            CELLULAR_AUTOMATON_FILE,
+        ))
+        .unwrap();
+        assert_eq!(CELLULAR_AUTOMATON_TRANSITIONS, generator.transitions);
+        assert_eq!(Ok(generator.rule), CELLULAR_AUTOMATON_RULE.parse());
+
+        let text = "[cellular_automaton]\nalive_probability = 0.4\n\
+            transitions = 3\nrule = B678/S345678\nsmoothing_rule = B5/S\n\
+            neighborhood = von_neumann\nedges = wrap\n";
+        let generator: CellularAutomatonGenerator = text.parse().unwrap();
+        assert_eq!(3, generator.transitions);
+        assert_eq!(Neighborhood::VonNeumann, generator.neighborhood);
+        assert_eq!(EdgeHandling::Wrap, generator.edges);
+
+        let error = |from, to| {
+            text.replace(from, to)
+                .parse::<CellularAutomatonGenerator>()
+                .err()
+        };
+        assert_eq!(
+            Some("line 2: invalid probability '1.5'".into()),
+            error("0.4", "1.5")
+        );
+        assert_eq!(
+            Some("line 7: unknown edge handling 'mirror'".into()),
+            error("wrap", "mirror")
+        );
+    }
+
+    #[test]
+    fn test_cellular_automaton_with_cave_rule() {
+        let registry = TileRegistry::shipped();
+        let generator = CellularAutomatonGenerator {
+            alive_probability: 0.45,
+            transitions: 5,
+            rule: "B678/S345678".parse().unwrap(),
+            edges: EdgeHandling::Alive,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let map =
+            generator.generate(&registry, MAP_WIDTH, MAP_HEIGHT, &mut rng);
+
+        // walls at the borders keep the map closed
+        for x in 0..MAP_WIDTH {
+            assert!(!map.tiles[x].kind.is_walkable(&registry));
+        }
+    }
+
+    #[test]
+    fn test_default_generators_produce_maps() {
+        let generators = MapGeneratorRegistry::shipped();
+        let registry = TileRegistry::shipped();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for entry in &generators.entries {
+            let map = entry.generate(
+                &registry,
+                MAP_WIDTH,
+                MAP_HEIGHT,
+                None,
+                &[],
+                &mut rng,
+            );
+            assert_eq!(MAP_WIDTH * MAP_HEIGHT, map.tiles.len());
+            assert!(map.entrance.is_some());
+            assert!((MAP_EXITS_MIN..=MAP_EXITS_MAX).contains(&map.exits.len()));
+        }
+    }
+
+    #[test]
+    fn test_generators_support_rectangular_maps() {
+        let generators = MapGeneratorRegistry::shipped();
+        let registry = TileRegistry::shipped();
+
+        for (width, height) in [(MAP_WIDTH, MAP_HEIGHT), (50, 12), (12, 50)] {
+            for (seed, entry) in generators.entries.iter().enumerate() {
+                let mut rng = StdRng::seed_from_u64(seed as u64);
+                let map = entry.generate(
+                    &registry,
+                    width,
+                    height,
+                    Some(MapEdge::Left),
+                    &[],
+                    &mut rng,
+                );
+                let name = entry.name;
+
+                assert_eq!((width, height), (map.width, map.height), "{name}");
+                assert_eq!(width * height, map.tiles.len(), "{name}");
+
+                // the entrance and exits are placed on edges, so they must be
+                // found on the first or last rows and columns whatever the
+                // map's proportions are
+                assert_eq!(Some(0), map.entrance.map(|pos| pos.x), "{name}");
+                for exit in &map.exits {
+                    let index = map.as_tile_index(&exit.position).unwrap();
+                    let (x, y) = (exit.position.x, exit.position.y);
+                    match exit.edge {
+                        Some(MapEdge::Right) => assert_eq!(width - 1, x),
+                        Some(MapEdge::Top) => assert_eq!(0, y),
+                        Some(MapEdge::Bottom) => assert_eq!(height - 1, y),
+                        Some(MapEdge::Left) => panic!("exit on entrance edge"),
+                        None => {}
+                    }
+                    assert_eq!(TileKind::LEVEL_EXIT, map.tiles[index].kind);
+                }
+
+                let position = MapPosition::new(width - 1, height - 1);
+                assert_eq!(
+                    Ok(width * height - 1),
+                    map.as_tile_index(&position)
+                );
+                assert_eq!(1, map.walkable_regions(&registry).len(), "{name}");
+                assert!(map.are_locks_solvable(&registry), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_cellular_automaton_map_layout() {
+        let registry = TileRegistry::shipped();
+        let (width, height) = (23, 9);
+        let mut rng = StdRng::seed_from_u64(0);
+        let ca = CellularAutomaton::new(width, height, 0.5, &mut rng);
+        let map = Map::from_cellular_automaton(&ca, &mut rng);
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = map.as_tile_index(&MapPosition::new(x, y)).unwrap();
+                assert_eq!(
+                    ca.cells[y * width + x] == CellularState::Dead,
+                    map.tiles[index].kind.is_walkable(&registry)
+                );
+            }
+        }
+    }
+}
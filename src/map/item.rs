@@ -0,0 +1,140 @@
+use crate::prelude::*;
+
+/// Represents a kind of item, lying on a map or carried by an actor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ItemKind {
+    /// Opens a locked door (see `Map::open_door`).
+    Key,
+}
+
+impl ItemKind {
+    /// Returns the index of the item's sprite in the terrain tileset.
+    pub const fn to_sprite_idx(self) -> usize {
+        match self {
+            Self::Key => TILESET_TERRAIN_IDX_KEY,
+        }
+    }
+
+    /// Returns the color multiplied with the item's sprite.
+    pub const fn sprite_color(self) -> Color {
+        match self {
+            Self::Key => ITEM_KEY_COLOR,
+        }
+    }
+}
+
+/// Represents an item lying on a map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MapItem {
+    /// The kind of the item.
+    pub kind: ItemKind,
+    /// The position of the item on the map.
+    pub position: MapPosition,
+}
+
+/// Marker component for the entities representing the items of the current
+/// map.
+#[derive(Component)]
+pub struct Item;
+
+impl Map {
+    /// Returns the item lying at a given position, if any.
+    pub fn item_at(&self, pos: &MapPosition) -> Option<&MapItem> {
+        self.items.iter().find(|item| item.position == *pos)
+    }
+
+    /// Removes the item lying at a given position from the map, e.g. when
+    /// picked up.
+    ///
+    /// # Returns
+    ///
+    /// The kind of the item removed, or `None` if there was no item.
+    pub fn take_item(&mut self, pos: &MapPosition) -> Option<ItemKind> {
+        let index = self.items.iter().position(|i| i.position == *pos)?;
+        Some(self.items.remove(index).kind)
+    }
+}
+
+/// Bundle for creating an entity representing an item lying on the map.
+#[derive(Bundle)]
+pub struct ItemBundle {
+    /// Marker component for the item.
+    pub item: Item,
+    /// Sprite bundle for rendering the item.
+    pub sprite: SpriteSheetBundle,
+    /// The position on the map where the item is.
+    pub map_position: MapPosition,
+}
+
+impl ItemBundle {
+    /// Creates a new entity using the `ItemBundle` struct components.
+    pub fn new(item: &MapItem, tileset: &TilesetTerrain) -> Self {
+        let (sprite_x, sprite_y) = item.position.as_sprite_coordinates();
+        Self {
+            item: Item,
+            sprite: SpriteSheetBundle {
+                transform: Transform::from_xyz(
+                    sprite_x,
+                    sprite_y,
+                    Z_INDEX_ITEM,
+                ),
+                sprite: Sprite {
+                    color: item.kind.sprite_color(),
+                    ..Default::default()
+                },
+                texture: tileset.1.clone(),
+                atlas: TextureAtlas {
+                    layout: tileset.0.clone(),
+                    index: item.kind.to_sprite_idx(),
+                },
+                ..Default::default()
+            },
+            map_position: item.position,
+        }
+    }
+}
+
+/// Despawns the item entities whose item is no longer on the current map,
+/// e.g. after the player picked it up. The items lying on unseen tiles aren't
+/// displayed.
+pub fn update_item_sprites(
+    mut commands: Commands,
+    q_map: Query<&Map, With<OnDisplay>>,
+    mut q_items: Query<(Entity, &mut Visibility, &MapPosition), With<Item>>,
+    fov: Res<FieldOfView>,
+) {
+    let Ok(map) = q_map.get_single() else {
+        return;
+    };
+
+    for (entity, mut visibility, pos) in &mut q_items {
+        if map.item_at(pos).is_none() {
+            commands.entity(entity).despawn();
+        } else {
+            *visibility = map.tile_visibility(pos, &fov).as_visibility();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_item() {
+        let position = MapPosition::new(1, 0);
+        let mut map = Map {
+            items: vec![MapItem {
+                kind: ItemKind::Key,
+                position,
+            }],
+            ..Default::default()
+        };
+
+        assert!(map.item_at(&MapPosition::new(0, 0)).is_none());
+        assert_eq!(None, map.take_item(&MapPosition::new(0, 0)));
+        assert_eq!(Some(ItemKind::Key), map.item_at(&position).map(|i| i.kind));
+        assert_eq!(Some(ItemKind::Key), map.take_item(&position));
+        assert!(map.items.is_empty());
+    }
+}
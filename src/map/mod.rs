@@ -1,55 +1,180 @@
+mod bsp;
 mod cellular_automaton;
 mod constants;
+mod dig;
+mod door;
+mod drunkard;
+mod exit;
+mod fov;
+mod generator;
+mod item;
 mod movement;
 mod noise;
+mod overworld;
+mod pathfinding;
+mod region;
 mod tile;
-
-use cellular_automaton::*;
+mod tile_registry;
+mod trap;
+mod vault;
+mod wfc;
+mod world;
+
+pub use bsp::*;
+pub use cellular_automaton::*;
 pub use constants::*;
+pub use dig::*;
+pub use drunkard::*;
+pub use exit::*;
+pub use fov::*;
+pub use generator::*;
+pub use item::*;
 pub use movement::*;
 use noise::*;
-use tile::*;
+pub use overworld::*;
+pub use tile::*;
+pub use tile_registry::*;
+pub use trap::*;
+pub use vault::*;
+pub use wfc::*;
+pub use world::*;
 
 use crate::prelude::*;
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::InitializingMap), initialize_map)
+        app.insert_resource(WorldGraph::default())
+            .init_resource::<FieldOfView>()
+            .add_systems(
+                OnEnter(AppState::LoadingAssets),
+                (load_tile_definitions, load_map_generators, load_vaults),
+            )
+            .add_systems(OnEnter(AppState::InGame), initialize_overworld)
+            .add_systems(OnEnter(GameState::InitializingMap), initialize_map)
             .add_systems(
                 OnEnter(GameState::PlayerTurn),
                 check_if_player_exit_map.run_if(in_state(AppState::InGame)),
             )
+            .add_systems(
+                OnEnter(GameState::PlayerTurn),
+                (
+                    trigger_traps,
+                    update_field_of_view,
+                    (
+                        update_tile_sprites,
+                        update_item_sprites,
+                        update_trap_sprites,
+                    ),
+                )
+                    .chain()
+                    .before(update_actor_sprites),
+            )
             .add_systems(OnEnter(GameState::CleanupMap), cleanup_map)
+            .add_systems(
+                OnEnter(GameState::EnemyTurn),
+                (trigger_traps, update_field_of_view)
+                    .chain()
+                    .before(move_mob),
+            )
             .add_systems(OnEnter(GameState::EnemyTurn), move_mob);
     }
 }
 
-/// Removes all entities (`Map`, `Tile`, etc) related to the current map.
+/// Loads the tile definitions from `TILE_DEFINITIONS_FILE` with the other
+/// assets, and inserts them as the `TileRegistry`. The game can't run without
+/// them, so it exits if they are invalid.
+pub fn load_tile_definitions(
+    mut commands: Commands,
+    mut app_next_state: ResMut<NextState<AppState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    match TileRegistry::load(&asset_path(TILE_DEFINITIONS_FILE)) {
+        Ok(registry) => commands.insert_resource(registry),
+        Err(e) => {
+            error!("{e}");
+            app_next_state.set(AppState::Finished);
+            exit_events.send(AppExit);
+        }
+    }
+}
+
+/// Loads the settings of the map generators with the other assets, and
+/// inserts the `MapGeneratorRegistry`. The default settings are used instead
+/// of the invalid ones, and the meadows are left out if their sample is
+/// invalid.
+pub fn load_map_generators(mut commands: Commands) {
+    let cellular_automaton =
+        CellularAutomatonGenerator::load(&asset_path(CELLULAR_AUTOMATON_FILE))
+            .unwrap_or_else(|e| {
+                warn!("{e}");
+                CellularAutomatonGenerator::default()
+            });
+    let meadow =
+        WaveFunctionCollapse::load(&asset_path(WFC_SAMPLE_MEADOW_FILE))
+            .map_err(|e| warn!("{e}"))
+            .ok();
+    commands
+        .insert_resource(MapGeneratorRegistry::new(cellular_automaton, meadow));
+}
+
+/// Loads the vaults of `VAULT_FOLDER` with the other assets, and inserts the
+/// `VaultLibrary`.
+pub fn load_vaults(mut commands: Commands) {
+    commands.insert_resource(VaultLibrary::load(&asset_path(VAULT_FOLDER)));
+}
+
+/// Filters the entities displaying the content of the current map.
+type MapContentFilter =
+    (Or<(With<Tile>, With<Item>, With<Trap>)>, With<OnDisplay>);
+
+/// Removes all entities (`Map`, `Tile`, `Item`, `Trap`, etc) related to the
+/// current map. The map is stored in the `WorldGraph` beforehand, and the
+/// current map becomes the destination of the exit taken by the player.
 pub fn cleanup_map(
     mut commands: Commands,
-    q_map: Query<Entity, (With<Map>, With<OnDisplay>)>,
-    q_tiles: Query<Entity, (With<Tile>, With<OnDisplay>)>,
+    q_map: Query<(Entity, &Map), With<OnDisplay>>,
+    q_entities: Query<Entity, MapContentFilter>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut current_map_number: ResMut<CurrentMapNumber>,
+    mut world: ResMut<WorldGraph>,
+    last_exit: Option<Res<LastMapExit>>,
 ) {
-    let entity = q_map.single();
+    let (entity, map) = q_map.single();
+    world.store_map(current_map_number.0, map);
     commands.entity(entity).despawn();
 
-    for entity in &q_tiles {
+    for entity in &q_entities {
         commands.entity(entity).despawn();
     }
     next_game_state.set(GameState::InitializingMap);
-    current_map_number.0 += 1;
+    current_map_number.0 = last_exit
+        .and_then(|last_exit| last_exit.exit.destination)
+        .expect("exit not linked to any map");
 }
 
-/// Checks if a player is on an exit tile. In that case, the game state is
-/// switched to `GameState::CleanupMap`.
+/// Represents the exit taken by the player for leaving the previous map. The
+/// resource is inserted once the player leaves the overworld for the first
+/// time.
+#[derive(Clone, Copy, Resource)]
+pub struct LastMapExit {
+    /// The id of the map left by the player.
+    pub map: usize,
+    /// The exit taken by the player.
+    pub exit: MapExit,
+}
+
+/// Checks if a player is on an exit tile. In that case, the exit is saved in
+/// `LastMapExit` and the game state is switched to `GameState::CleanupMap`.
 pub fn check_if_player_exit_map(
+    mut commands: Commands,
     q_map: Query<&Map, With<OnDisplay>>,
     q_actors: Query<(&MapPosition, &Actor), With<OnDisplay>>,
     mut next_game_state: ResMut<NextState<GameState>>,
+    current_map_number: Res<CurrentMapNumber>,
 ) {
     let map = q_map.single();
 
@@ -59,14 +184,18 @@ pub fn check_if_player_exit_map(
         .last()
         .expect("player position not found");
 
-    if map.exits.contains(player_position) {
+    if let Some(exit) = map.exit_at(player_position) {
+        commands.insert_resource(LastMapExit {
+            map: current_map_number.0,
+            exit: *exit,
+        });
         next_game_state.set(GameState::CleanupActors);
     }
 }
 
 /// Represents the environment where the actors interact together. A map is
 /// made of tiles which has different properties for the actors.
-#[derive(Component)]
+#[derive(Clone, Component, Default)]
 pub struct Map {
     /// The map's width.
     pub width: usize,
@@ -75,27 +204,102 @@ pub struct Map {
     /// All tiles for the map, the vector index corresponds to the tile
     /// coordinates.
     pub tiles: Vec<Tile>,
-    /// The exits positions for the map.
-    pub exits: Vec<MapPosition>,
+    /// The position where the player arrives on the map.
+    pub entrance: Option<MapPosition>,
+    /// The exits of the map.
+    pub exits: Vec<MapExit>,
+    /// The positions where mobs are spawned first, e.g. set by vaults.
+    pub mob_spawns: Vec<MapPosition>,
+    /// The positions where items are placed first (see `Map::lock_doors`),
+    /// e.g. set by vaults.
+    pub item_spawns: Vec<MapPosition>,
+    /// The items lying on the map.
+    pub items: Vec<MapItem>,
+    /// The traps set on the map.
+    pub traps: Vec<MapTrap>,
+    /// The traps triggered by actors, whose effects aren't resolved yet.
+    pub triggered_traps: Vec<MapTrap>,
+    /// Whether each tile has been seen by the player, stored like the tiles
+    /// (see `Map::remember`).
+    pub remembered: Vec<bool>,
+}
+
+/// Resources locating the current map in the world (see `initialize_map`).
+#[derive(SystemParam)]
+struct CurrentWorld<'w> {
+    current_map_number: Res<'w, CurrentMapNumber>,
+    last_exit: Option<Res<'w, LastMapExit>>,
+    overworld: Res<'w, Overworld>,
+    world: ResMut<'w, WorldGraph>,
+}
+
+/// Resources generating the maps visited for the first time (see
+/// `initialize_map`).
+#[derive(SystemParam)]
+struct MapGeneration<'w> {
+    run_seed: Res<'w, RunSeed>,
+    generators: Res<'w, MapGeneratorRegistry>,
+    vaults: Res<'w, VaultLibrary>,
+    rng: ResMut<'w, RunRng>,
 }
 
 /// Initialize a map by spawning tile entities depending on the map dimensions,
 /// the tile placement algorithm, etc.
+/// The overworld is built from the `Overworld` and a map already visited is
+/// restored from the `WorldGraph`. Otherwise, the tile placement algorithm is
+/// selected from the `MapGeneratorRegistry` depending on the map's location
+/// and depth, and the new map is linked to the world.
 /// Lastly, the map entity is spawned.
 fn initialize_map(
     mut commands: Commands,
     mut game_next_state: ResMut<NextState<GameState>>,
     tileset: Res<TilesetTerrain>,
+    registry: Res<TileRegistry>,
+    current: CurrentWorld,
+    generation: MapGeneration,
 ) {
-    let m = if rand::thread_rng().gen_bool(0.5) {
-        Map::from((PerlinNoise::new(), MAP_WIDTH, MAP_HEIGHT))
+    let CurrentWorld {
+        current_map_number,
+        last_exit,
+        overworld,
+        mut world,
+    } = current;
+    let MapGeneration {
+        run_seed,
+        generators,
+        vaults,
+        mut rng,
+    } = generation;
+    let id = current_map_number.0;
+    let stored = world.nodes.get(id).and_then(|node| node.map.clone());
+
+    let m = if id == OVERWORLD_MAP_ID {
+        overworld.to_map()
+    } else if let Some(map) = stored {
+        map
     } else {
-        let mut ca = CellularAutomaton::new(MAP_WIDTH, MAP_HEIGHT, 0.5);
-        for _ in 0..50 {
-            ca.transition();
-        }
-        ca.smooth();
-        Map::from(ca)
+        rng.reseed_map(*run_seed, id);
+        let rng = &mut rng.map;
+
+        let (depth, location) = (world.depth(id), world.location(id));
+        let location = location.expect("map outside of any location");
+        let generator = generators
+            .select(location, depth, rng)
+            .expect("no map generator available");
+        debug!(
+            "map {id} ({location:?}, depth {depth}) generated with {}",
+            generator.name
+        );
+        let mut map = generator.generate(
+            &registry,
+            MAP_WIDTH,
+            MAP_HEIGHT,
+            last_exit.as_ref().and_then(|last| last.exit.arrival_edge()),
+            &vaults.0,
+            rng,
+        );
+        world.link_new_map(id, &mut map, last_exit.as_deref());
+        map
     };
 
     for (i, tile) in m.tiles.iter().enumerate() {
@@ -103,7 +307,16 @@ fn initialize_map(
             x: i % m.width,
             y: i / m.width,
         };
-        commands.spawn((OnDisplay, TileBundle::new(pos_tile, &tileset, *tile)));
+        commands.spawn((
+            OnDisplay,
+            TileBundle::new(pos_tile, &tileset, &registry, *tile),
+        ));
+    }
+    for item in &m.items {
+        commands.spawn((OnDisplay, ItemBundle::new(item, &tileset)));
+    }
+    for trap in &m.traps {
+        commands.spawn((OnDisplay, TrapBundle::new(trap, &tileset)));
     }
 
     commands.spawn((OnDisplay, m));
@@ -112,19 +325,25 @@ fn initialize_map(
 }
 
 impl Map {
-    /// Returns random positions where an actor can spawn, meaning a position
-    /// with no other actors and that can be walkable.
+    /// Returns random positions where an actor of a given kind can spawn,
+    /// meaning a position with no other actors, that can be walked on and
+    /// that the actor can cross. Fails if there aren't enough of them.
     pub fn generate_random_positions(
         &self,
+        registry: &TileRegistry,
+        actor_kind: ActorKind,
         quantity: usize,
         pos_occupied: &[MapPosition],
+        rng: &mut impl Rng,
     ) -> Result<Vec<MapPosition>, Box<dyn std::error::Error>> {
+        let actor = Actor::new(actor_kind);
         let mut pos_spawnable: Vec<_> = self
             .tiles
             .iter()
             .enumerate()
             .filter(|(index, tile)| {
-                tile.is_walkable()
+                tile.is_walkable(registry)
+                    && actor.can_cross(registry, tile.kind)
                     && !pos_occupied.contains(&MapPosition {
                         x: index % self.width,
                         y: index / self.width,
@@ -136,57 +355,32 @@ impl Map {
             })
             .collect();
 
-        if pos_spawnable.is_empty() {
-            return Err("no spawnable positions".into());
+        if pos_spawnable.len() < quantity {
+            return Err(format!(
+                "not enough spawnable positions: expected {quantity}, found {}",
+                pos_spawnable.len()
+            )
+            .into());
         }
 
-        let mut rng = rand::thread_rng();
-        pos_spawnable.shuffle(&mut rng);
+        pos_spawnable.shuffle(rng);
+        pos_spawnable.truncate(quantity);
 
-        Ok(pos_spawnable[0..quantity].to_vec())
+        Ok(pos_spawnable)
     }
 
-    /// Adds an exit tile on the right side of the map. The position is
-    /// selected randomly.
-    pub fn add_exit_tile(&mut self) {
-        let pos_spawnable: Vec<_> = self
-            .tiles
-            .iter()
-            .enumerate()
-            .filter(|(index, tile)| {
-                tile.is_walkable() && *index % self.width == self.width - 1
-            })
-            .map(|(index, _)| index)
-            .collect();
-
-        assert!(
-            !pos_spawnable.is_empty(),
-            "mo positions available for exit tile",
-        );
-
-        let mut rng = rand::thread_rng();
-        let index = *pos_spawnable.choose(&mut rng).unwrap();
-
-        self.tiles[index].kind = TileKind::LevelExit;
-
-        let pos_exit = MapPosition {
-            x: index % self.width,
-            y: index / self.width,
-        };
-
-        self.exits.push(pos_exit);
-    }
-
-    /// Converts a given `MapPosition` to an index in the tiles vector.
+    /// Converts a given `MapPosition` to an index in the tiles vector. The
+    /// tiles are stored row by row, from the top left corner.
     pub fn as_tile_index(&self, pos: &MapPosition) -> Result<usize, String> {
-        let index = pos.x + pos.y * self.width;
-        if index >= self.tiles.len() {
+        if pos.x >= self.width || pos.y >= self.height {
             return Err("index out of bounds".into());
         }
-        Ok(index)
+        Ok(pos.x + pos.y * self.width)
     }
 
     /// Moves an actor from a tile to another and updates its map position.
+    /// The trap set on the new tile, if any, is triggered (see
+    /// `Map::trigger_trap`).
     pub fn move_actor(
         &mut self,
         pos_actor_old: &mut MapPosition,
@@ -196,22 +390,27 @@ impl Map {
         let index_to = self.as_tile_index(pos_actor_new)?;
         self.tiles[index_to].actor = self.tiles[index_from].actor.take();
         *pos_actor_old = *pos_actor_new;
+        self.trigger_trap(pos_actor_new);
         Ok(())
     }
 }
 
-impl From<CellularAutomaton> for Map {
+impl Map {
     /// Constructs a `Map` using a cellular automaton.
     ///
     /// # Arguments
     ///
     /// - `ca`: A `CellularAutomaton` initialized struct.
+    /// - `rng`: The random number generator used for the tiles variations.
     ///
     /// # Returns
     ///
     /// A `Map` where the tiles are determined by the cellular automaton state.
-    fn from(ca: CellularAutomaton) -> Self {
-        let mut map = Self {
+    pub fn from_cellular_automaton(
+        ca: &CellularAutomaton,
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self {
             width: ca.width,
             height: ca.height,
             tiles: ca
@@ -220,70 +419,154 @@ impl From<CellularAutomaton> for Map {
                 .map(|cellular_state| {
                     let tile_kind = match cellular_state {
                         CellularState::Alive => {
-                            let mut rng = rand::thread_rng();
                             if rng.gen_bool(PROBABILITY_STONE_DAMAGED) {
-                                TileKind::GrassWithStoneDamaged
+                                TileKind::GRASS_WITH_STONE_DAMAGED
                             } else {
-                                TileKind::GrassWithStone
+                                TileKind::GRASS_WITH_STONE
                             }
                         }
-                        CellularState::Dead => TileKind::Grass,
+                        CellularState::Dead => TileKind::GRASS,
                     };
                     Tile::from_kind(tile_kind)
                 })
                 .collect(),
-            exits: vec![],
-        };
-
-        map.add_exit_tile();
-        map
+            ..Default::default()
+        }
     }
-}
 
-impl From<(PerlinNoise, usize, usize)> for Map {
     /// Constructs a `Map` using Perlin noise.
     ///
+    /// The noise is sampled with a fractal Brownian motion, then the tile
+    /// kinds are assigned by percentiles: the lowest values become stones and
+    /// the highest ones flowers, whatever the noise amplitude is. The grass in
+    /// between is turned into tall grass, mud or water by percentiles of a
+    /// second sample of the noise, the moisture.
+    ///
     /// # Arguments
     ///
-    /// - `tuple`: A tuple with three parameters representing a `PerlinNoise` struct,
-    ///            the width, and the height of the map.
+    /// - `noise`: The `PerlinNoise` used for determining the tiles.
+    /// - `width`: The width of the map.
+    /// - `height`: The height of the map.
+    /// - `rng`: The random number generator used for the tiles variations.
     ///
     /// # Returns
     ///
     /// A `Map` where the tiles are determined by Perlin noise.
-    fn from(tuple: (PerlinNoise, usize, usize)) -> Self {
-        let mut cells: Vec<Tile> = Vec::new();
-        for i in 0..tuple.1 {
-            for j in 0..tuple.2 {
-                let x_scaled = i as f64 * PERLIN_NOISE_SCALE;
-                let y_scaled = j as f64 * PERLIN_NOISE_SCALE;
-                let noise_value = tuple.0.perlin_noise(x_scaled, y_scaled);
-
-                let kind = if noise_value > 2.2 {
-                    TileKind::GrassWithFlower
-                } else if noise_value > -0.25 {
-                    TileKind::Grass
-                } else {
-                    let mut rng = rand::thread_rng();
-                    if rng.gen_bool(PROBABILITY_STONE_DAMAGED) {
-                        TileKind::GrassWithStoneDamaged
-                    } else {
-                        TileKind::GrassWithStone
-                    }
-                };
-                cells.push(Tile::from_kind(kind));
+    pub fn from_perlin_noise(
+        noise: &PerlinNoise,
+        width: usize,
+        height: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut noise_values = Vec::with_capacity(width * height);
+        let mut moisture_values = Vec::with_capacity(width * height);
+        // the tiles are stored row by row, see `Map::as_tile_index`
+        for y in 0..height {
+            for x in 0..width {
+                let x_scaled = scale_coordinate(x, PERLIN_NOISE_SCALE);
+                let y_scaled = scale_coordinate(y, PERLIN_NOISE_SCALE);
+                noise_values.push(noise.fbm(
+                    x_scaled,
+                    y_scaled,
+                    &PERLIN_FRACTAL,
+                ));
+                moisture_values.push(noise.fbm(
+                    x_scaled + PERLIN_MOISTURE_OFFSET,
+                    y_scaled + PERLIN_MOISTURE_OFFSET,
+                    &PERLIN_FRACTAL,
+                ));
             }
         }
 
-        let mut map = Self {
-            width: tuple.1,
-            height: tuple.2,
-            tiles: cells,
-            exits: vec![],
+        let threshold_stone =
+            percentile(&noise_values, PERLIN_PERCENTILE_STONE);
+        let threshold_flower =
+            percentile(&noise_values, PERLIN_PERCENTILE_FLOWER);
+
+        let is_grass = |noise_value: f64| {
+            noise_value >= threshold_stone && noise_value < threshold_flower
         };
+        let grass_moisture: Vec<f64> = noise_values
+            .iter()
+            .zip(&moisture_values)
+            .filter(|(&noise_value, _)| is_grass(noise_value))
+            .map(|(_, &moisture)| moisture)
+            .collect();
+        let thresholds_moisture = [
+            (PERLIN_PERCENTILE_DEEP_WATER, TileKind::DEEP_WATER),
+            (PERLIN_PERCENTILE_SHALLOW_WATER, TileKind::SHALLOW_WATER),
+            (PERLIN_PERCENTILE_MUD, TileKind::MUD),
+            (PERLIN_PERCENTILE_TALL_GRASS, TileKind::TALL_GRASS),
+        ]
+        .map(|(proportion, kind)| {
+            (percentile(&grass_moisture, proportion), kind)
+        });
+
+        let cells = noise_values
+            .iter()
+            .zip(&moisture_values)
+            .map(|(&noise_value, &moisture)| {
+                let kind = if noise_value >= threshold_flower {
+                    TileKind::GRASS_WITH_FLOWER
+                } else if is_grass(noise_value) {
+                    thresholds_moisture
+                        .iter()
+                        .find(|(threshold, _)| moisture >= *threshold)
+                        .map_or(TileKind::GRASS, |(_, kind)| *kind)
+                } else if rng.gen_bool(PROBABILITY_STONE_DAMAGED) {
+                    TileKind::GRASS_WITH_STONE_DAMAGED
+                } else {
+                    TileKind::GRASS_WITH_STONE
+                };
+                Tile::from_kind(kind)
+            })
+            .collect();
 
-        map.add_exit_tile();
-        map
+        Self {
+            width,
+            height,
+            tiles: cells,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl Map {
+    /// Parses a map written as rows of glyphs for tests (see
+    /// `parse_tile_grid`). Besides the tile glyphs, `@` stands for the player
+    /// and `B` for a blob, both standing on grass.
+    pub fn parse_rows(rows: &[&str]) -> Self {
+        let mut actors = vec![];
+        let (width, height, kinds) =
+            parse_tile_grid(&rows.join("\n"), |_, _, glyph| {
+                let actor = match glyph {
+                    '@' => Some(Actor::new(ActorKind::Player)),
+                    'B' => Some(Actor::new(ActorKind::Blob)),
+                    _ => None,
+                };
+                actors.push(actor);
+                if actor.is_some() {
+                    Some(TileKind::GRASS)
+                } else {
+                    TileKind::from_glyph(glyph)
+                }
+            })
+            .unwrap();
+
+        Self {
+            width,
+            height,
+            tiles: kinds
+                .into_iter()
+                .zip(actors)
+                .map(|(kind, actor)| Tile {
+                    actor,
+                    ..Tile::from_kind(kind)
+                })
+                .collect(),
+            ..Default::default()
+        }
     }
 }
 
@@ -337,17 +620,112 @@ impl MapPosition {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_perlin_noise_map_proportions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = PerlinNoise::new(&mut rng);
+        let map = Map::from_perlin_noise(&noise, 40, 40, &mut rng);
+
+        let count = |kinds: &[TileKind]| {
+            map.tiles.iter().filter(|t| kinds.contains(&t.kind)).count()
+        };
+        let stones = count(&[
+            TileKind::GRASS_WITH_STONE,
+            TileKind::GRASS_WITH_STONE_DAMAGED,
+        ]);
+        let flowers = count(&[TileKind::GRASS_WITH_FLOWER]);
+
+        assert_eq!(40 * 40 * 3 / 10, stones);
+        assert_eq!(40 * 40 / 10, flowers);
+    }
+
+    #[test]
+    fn test_perlin_noise_map_terrain() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = PerlinNoise::new(&mut rng);
+        let map = Map::from_perlin_noise(&noise, 40, 40, &mut rng);
+
+        for kind in [
+            TileKind::GRASS,
+            TileKind::TALL_GRASS,
+            TileKind::MUD,
+            TileKind::SHALLOW_WATER,
+            TileKind::DEEP_WATER,
+        ] {
+            assert!(map.tiles.iter().any(|t| t.kind == kind), "no {kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_perlin_noise_map_is_row_major() {
+        let (width, height) = (37, 11);
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = PerlinNoise::new(&mut rng);
+        let map = Map::from_perlin_noise(&noise, width, height, &mut rng);
+        assert_eq!(width * height, map.tiles.len());
+
+        let noise_at = |x: usize, y: usize| {
+            noise.fbm(
+                scale_coordinate(x, PERLIN_NOISE_SCALE),
+                scale_coordinate(y, PERLIN_NOISE_SCALE),
+                &PERLIN_FRACTAL,
+            )
+        };
+        let values: Vec<_> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| noise_at(x, y))
+            .collect();
+        let threshold_flower = percentile(&values, PERLIN_PERCENTILE_FLOWER);
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = map.as_tile_index(&MapPosition::new(x, y)).unwrap();
+                assert_eq!(
+                    noise_at(x, y) >= threshold_flower,
+                    map.tiles[index].kind == TileKind::GRASS_WITH_FLOWER,
+                    "unexpected tile at ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_as_tile_index() {
+        let map = Map {
+            width: 3,
+            height: 2,
+            tiles: vec![Tile::default(); 6],
+            ..Default::default()
+        };
+
+        assert_eq!(Ok(0), map.as_tile_index(&MapPosition::new(0, 0)));
+        assert_eq!(Ok(2), map.as_tile_index(&MapPosition::new(2, 0)));
+        assert_eq!(Ok(3), map.as_tile_index(&MapPosition::new(0, 1)));
+        assert_eq!(Ok(5), map.as_tile_index(&MapPosition::new(2, 1)));
+        assert!(map.as_tile_index(&MapPosition::new(3, 0)).is_err());
+        assert!(map.as_tile_index(&MapPosition::new(0, 2)).is_err());
+    }
 
     #[test]
     fn test_generate_random_spawning_position_success() {
+        let registry = TileRegistry::shipped();
         let map1x1 = Map {
             width: 1,
             height: 1,
             tiles: vec![Tile::default()],
-            exits: vec![],
+            ..Default::default()
         };
 
-        let spawn = map1x1.generate_random_positions(1, &vec![]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let spawn = map1x1.generate_random_positions(
+            &registry,
+            ActorKind::Player,
+            1,
+            &[],
+            &mut rng,
+        );
 
         assert!(spawn.is_ok());
         assert_eq!(vec![MapPosition::new(0, 0)], spawn.unwrap());
@@ -355,23 +733,76 @@ mod tests {
 
     #[test]
     fn test_generate_random_spawning_position_failure() {
+        let registry = TileRegistry::shipped();
         let mut map1x1 = Map {
             width: 1,
             height: 1,
-            tiles: vec![Tile::from_kind(TileKind::GrassWithStone)],
-            exits: vec![],
+            tiles: vec![Tile::from_kind(TileKind::GRASS_WITH_STONE)],
+            ..Default::default()
         };
 
-        let spawn = map1x1.generate_random_positions(1, &vec![]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let spawn = map1x1.generate_random_positions(
+            &registry,
+            ActorKind::Player,
+            1,
+            &[],
+            &mut rng,
+        );
         assert!(spawn.is_err());
 
         map1x1.tiles = vec![Tile::default()];
 
-        let spawn = map1x1.generate_random_positions(1, &vec![]);
+        let spawn = map1x1.generate_random_positions(
+            &registry,
+            ActorKind::Player,
+            1,
+            &[],
+            &mut rng,
+        );
         assert!(spawn.is_ok());
 
-        let spawn = map1x1
-            .generate_random_positions(1, &vec![MapPosition { x: 0, y: 0 }]);
+        let spawn = map1x1.generate_random_positions(
+            &registry,
+            ActorKind::Player,
+            1,
+            &[MapPosition { x: 0, y: 0 }],
+            &mut rng,
+        );
+        assert!(spawn.is_err());
+    }
+
+    #[test]
+    fn test_generate_random_positions_where_actor_can_cross() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["~~~", "~.~", "~~~"]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let spawn = map.generate_random_positions(
+            &registry,
+            ActorKind::Blob,
+            1,
+            &[],
+            &mut rng,
+        );
+        assert_eq!(vec![MapPosition::new(1, 1)], spawn.unwrap());
+
+        let spawn = map.generate_random_positions(
+            &registry,
+            ActorKind::Blob,
+            2,
+            &[],
+            &mut rng,
+        );
         assert!(spawn.is_err());
+
+        let spawn = map.generate_random_positions(
+            &registry,
+            ActorKind::Rabbit,
+            9,
+            &[],
+            &mut rng,
+        );
+        assert_eq!(9, spawn.unwrap().len());
     }
 }
@@ -1,68 +1,189 @@
 use crate::prelude::*;
+use bevy::ecs::system::SystemParam;
+
+/// Components of the actors taking turns (see `move_mob`).
+type ScheduledComponents<'a> = (
+    Entity,
+    &'a mut MapPosition,
+    &'a Actor,
+    &'a mut MovementCooldown,
+    &'a mut Energy,
+);
+
+/// Resources updated once the mobs have acted (see `move_mob`).
+#[derive(SystemParam)]
+pub struct TurnProgress<'w> {
+    game_turn: ResMut<'w, CurrentTurnNumber>,
+    next_state: ResMut<'w, NextState<GameState>>,
+}
 
-// Moves mobs in the map depending on their `ActorHostility` type.
+// Moves mobs in the map depending on their `ActorHostility` type, in the
+// order given by their energy (see `schedule_turns`), until the player can act
+// again. The mobs slowed down by the terrain (see `MovementCooldown`) wait
+// instead.
 pub fn move_mob(
-    mut q_actors: Query<(&mut MapPosition, &Actor), With<OnDisplay>>,
+    mut q_actors: Query<ScheduledComponents, With<OnDisplay>>,
     mut q_map: Query<&mut Map, With<OnDisplay>>,
+    registry: Res<TileRegistry>,
+    mut rng: ResMut<RunRng>,
+    fov: Res<FieldOfView>,
+    mut attacks: ResMut<PendingAttacks>,
+    mut turn: TurnProgress,
 ) {
     let mut map = q_map.single_mut();
 
-    let pos_player = q_actors
+    // the mobs act in the order of their positions, row by row, and before
+    // the player in case of a tie
+    let mut actors: Vec<(bool, usize, usize, Entity)> = q_actors
+        .iter()
+        .map(|(entity, pos, actor, _, _)| {
+            (actor.is_player(), pos.y, pos.x, entity)
+        })
+        .collect();
+    actors.sort_unstable();
+    let entities: Vec<Entity> =
+        actors.iter().map(|(_, _, _, entity)| *entity).collect();
+    let mut scheduled: Vec<ScheduledActor> = entities
         .iter()
-        .filter(|(_, a)| a.is_player())
+        .map(|entity| {
+            let (_, _, actor, _, energy) = q_actors.get(*entity).unwrap();
+            ScheduledActor {
+                speed: actor.kind.speed(),
+                energy: *energy,
+            }
+        })
+        .collect();
+
+    let index_player = actors
         .last()
-        .expect("no player found")
-        .0
-        .clone();
-
-    for (mut mob, actor) in q_actors.iter_mut() {
-        if actor.is_player() {
-            continue;
+        .filter(|(is_player, _, _, _)| *is_player)
+        .map(|_| actors.len() - 1)
+        .expect("no player found");
+    let pos_player = *q_actors.get(entities[index_player]).unwrap().1;
+
+    let ticks = schedule_turns(&mut scheduled, index_player, |index| {
+        let (_, mut mob, actor, mut cooldown, _) =
+            q_actors.get_mut(entities[index]).unwrap();
+        if cooldown.wait() {
+            return;
+        }
+        // the field of view is symmetric, so the mobs seen by the player see
+        // the player as well
+        let sees_player = fov.is_visible(&mob);
+        let distance =
+            mob.x.abs_diff(pos_player.x) + mob.y.abs_diff(pos_player.y);
+
+        // the hostile mobs next to the player attack instead of moving
+        if actor.is_hostile() && sees_player && distance == 1 {
+            attacks.0.push(Attack {
+                attacker: *mob,
+                target: pos_player,
+            });
+            return;
         }
 
-        if actor.is_neutral() {
-            move_randomly(&mut mob, &mut map);
+        let pos_old = *mob;
+        if actor.is_neutral() || (actor.is_hostile() && !sees_player) {
+            move_randomly(&registry, &mut mob, &mut map, &mut rng.ai);
         } else if actor.is_hostile() {
-            move_to_player(&pos_player, &mut mob, &mut map);
+            move_along_path(
+                &registry,
+                &pos_player,
+                &mut mob,
+                &mut map,
+                actor.can_dig(),
+            );
+        }
+        if *mob != pos_old {
+            *cooldown =
+                MovementCooldown::after_moving_to(&map, &registry, &mob);
+        }
+    });
+
+    for (entity, actor) in entities.iter().zip(&scheduled) {
+        *q_actors.get_mut(*entity).unwrap().4 = actor.energy;
+    }
+    turn.game_turn.0 += ticks;
+    turn.next_state.set(GameState::PlayerTurn);
+}
+
+/// Moves a mob one step along the cheapest path to the player (see
+/// `Map::find_path`), stopping next to the player. A closed door on the path
+/// is opened instead of moving. Without any path, the mob moves towards the
+/// player in a straight line (see `move_to_player`).
+pub fn move_along_path(
+    registry: &TileRegistry,
+    player: &MapPosition,
+    mob: &mut MapPosition,
+    map: &mut Map,
+    can_dig: bool,
+) {
+    match map.find_path(registry, mob, player) {
+        Some(path) => {
+            if let Some(next) = path.first().filter(|next| *next != player) {
+                let index = map.as_tile_index(next).unwrap();
+                if map.tiles[index].is_walkable(registry) {
+                    map.move_actor(mob, next).unwrap();
+                } else {
+                    map.open_door(registry, next, &mut Inventory::default());
+                }
+            }
         }
+        None => move_to_player(registry, player, mob, map, can_dig),
     }
 }
 
-/// Moves a mob towards the player in a straight line.
+/// Moves a mob towards the player in a straight line. A mob able to dig
+/// damages the destructible tiles in its way instead of being blocked.
 pub fn move_to_player(
+    registry: &TileRegistry,
     player: &MapPosition,
     mut mob: &mut MapPosition,
     mut map: &mut Map,
+    can_dig: bool,
 ) {
     if mob.y == player.y && mob.x < player.x {
-        if can_move_right(&mob, &mut map) {
+        if can_move_right(&mob, &mut map, registry) {
             move_right(&mut map, &mut mob).unwrap();
+        } else if can_dig {
+            map.damage_tile(registry, &mob.right().unwrap());
         }
     }
     if mob.y == player.y && mob.x > player.x {
-        if can_move_left(&mob, &mut map) {
+        if can_move_left(&mob, &mut map, registry) {
             move_left(&mut map, &mut mob).unwrap();
+        } else if can_dig {
+            map.damage_tile(registry, &mob.left().unwrap());
         }
     }
     if mob.x == player.x && mob.y > player.y {
-        if can_move_up(&mob, &mut map) {
+        if can_move_up(&mob, &mut map, registry) {
             move_up(&mut map, &mut mob).unwrap();
+        } else if can_dig {
+            map.damage_tile(registry, &mob.up().unwrap());
         }
     }
     if mob.x == player.x && mob.y < player.y {
-        if can_move_down(&mob, &mut map) {
+        if can_move_down(&mob, &mut map, registry) {
             move_down(&mut map, &mut mob).unwrap();
+        } else if can_dig {
+            map.damage_tile(registry, &mob.down().unwrap());
         }
     }
 }
 
 /// Move mob actors to a random reachable position.
-pub fn move_randomly(mut pos_mob: &mut MapPosition, map: &mut Map) {
-    let pos_reachable = enumerate_reachable_positions(&pos_mob.clone(), &map);
+pub fn move_randomly(
+    registry: &TileRegistry,
+    mut pos_mob: &mut MapPosition,
+    map: &mut Map,
+    rng: &mut impl Rng,
+) {
+    let pos_reachable =
+        enumerate_reachable_positions(&pos_mob.clone(), &map, registry);
 
     if !pos_reachable.is_empty() {
-        let pos_random =
-            pos_reachable[rand::thread_rng().gen_range(0..pos_reachable.len())];
+        let pos_random = pos_reachable[rng.gen_range(0..pos_reachable.len())];
         map.move_actor(&mut pos_mob, &pos_random).unwrap();
     }
 }
@@ -104,28 +225,29 @@ pub fn move_down(
 pub fn enumerate_reachable_positions(
     position: &MapPosition,
     map: &Map,
+    registry: &TileRegistry,
 ) -> Vec<MapPosition> {
     let mut reachable_positions: Vec<MapPosition> = vec![];
 
-    if can_move_left(position, map) {
+    if can_move_left(position, map, registry) {
         reachable_positions.push(MapPosition {
             x: position.x - 1,
             y: position.y,
         });
     }
-    if can_move_right(position, map) {
+    if can_move_right(position, map, registry) {
         reachable_positions.push(MapPosition {
             x: position.x + 1,
             y: position.y,
         });
     }
-    if can_move_up(position, map) {
+    if can_move_up(position, map, registry) {
         reachable_positions.push(MapPosition {
             x: position.x,
             y: position.y - 1,
         });
     }
-    if can_move_down(position, map) {
+    if can_move_down(position, map, registry) {
         reachable_positions.push(MapPosition {
             x: position.x,
             y: position.y + 1,
@@ -134,33 +256,60 @@ pub fn enumerate_reachable_positions(
     return reachable_positions;
 }
 
-pub fn can_move_left(pos: &MapPosition, map: &Map) -> bool {
+/// Returns whether the actor at a given position can walk on the tile at a
+/// given index (see `Map::can_enter`).
+fn can_move_to(
+    pos: &MapPosition,
+    index: usize,
+    map: &Map,
+    registry: &TileRegistry,
+) -> bool {
+    map.can_enter(registry, pos.x + pos.y * map.width, index)
+}
+
+pub fn can_move_left(
+    pos: &MapPosition,
+    map: &Map,
+    registry: &TileRegistry,
+) -> bool {
     if pos.x > 0 {
-        map.tiles[pos.x + pos.y * map.width - 1].is_walkable()
+        can_move_to(pos, pos.x + pos.y * map.width - 1, map, registry)
     } else {
         false
     }
 }
 
-pub fn can_move_right(pos: &MapPosition, map: &Map) -> bool {
+pub fn can_move_right(
+    pos: &MapPosition,
+    map: &Map,
+    registry: &TileRegistry,
+) -> bool {
     if pos.x < map.width - 1 {
-        map.tiles[pos.x + pos.y * map.width + 1].is_walkable()
+        can_move_to(pos, pos.x + pos.y * map.width + 1, map, registry)
     } else {
         false
     }
 }
 
-pub fn can_move_up(pos: &MapPosition, map: &Map) -> bool {
+pub fn can_move_up(
+    pos: &MapPosition,
+    map: &Map,
+    registry: &TileRegistry,
+) -> bool {
     if pos.y > 0 {
-        map.tiles[pos.x + (pos.y - 1) * map.width].is_walkable()
+        can_move_to(pos, pos.x + (pos.y - 1) * map.width, map, registry)
     } else {
         false
     }
 }
 
-pub fn can_move_down(pos: &MapPosition, map: &Map) -> bool {
+pub fn can_move_down(
+    pos: &MapPosition,
+    map: &Map,
+    registry: &TileRegistry,
+) -> bool {
     if pos.y < map.height - 1 {
-        map.tiles[pos.x + (pos.y + 1) * map.width].is_walkable()
+        can_move_to(pos, pos.x + (pos.y + 1) * map.width, map, registry)
     } else {
         false
     }
@@ -176,26 +325,32 @@ mod tests {
             width: 3,
             height: 3,
             tiles: vec![Tile::default(); 3 * 3],
-            exits: vec![],
+            ..Default::default()
         }
     }
 
+    fn create_plain_map_with_actor() -> Map {
+        let mut map = create_plain_map();
+        map.tiles[4].actor = Some(Actor::new(ActorKind::Blob));
+        map
+    }
+
     fn create_stone_map() -> Map {
         Map {
             width: 3,
             height: 3,
             tiles: vec![
                 Tile::default(),
-                Tile::from_kind(TileKind::GrassWithStone),
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
                 Tile::default(),
-                Tile::from_kind(TileKind::GrassWithStone),
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
                 Tile::default(),
-                Tile::from_kind(TileKind::GrassWithStone),
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
                 Tile::default(),
-                Tile::from_kind(TileKind::GrassWithStone),
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
                 Tile::default(),
             ],
-            exits: vec![],
+            ..Default::default()
         }
     }
 
@@ -211,114 +366,298 @@ mod tests {
 
     #[test]
     fn test_can_move_left_without_actors() {
+        let registry = TileRegistry::shipped();
         let map_plain = create_plain_map();
-        assert!(!can_move_left(&POSITION_TOP_LEFT, &map_plain));
-        assert!(!can_move_left(&POSITION_BOTTOM_LEFT, &map_plain));
-        assert!(can_move_left(&POSITION_TOP_RIGHT, &map_plain));
-        assert!(can_move_left(&POSITION_BOTTOM_RIGHT, &map_plain));
-        assert!(can_move_left(&POSITION_MIDDLE, &map_plain));
+        assert!(!can_move_left(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(!can_move_left(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
+        assert!(can_move_left(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(can_move_left(&POSITION_BOTTOM_RIGHT, &map_plain, &registry));
+        assert!(can_move_left(&POSITION_MIDDLE, &map_plain, &registry));
 
         let map_stone = create_stone_map();
-        assert!(!can_move_left(&POSITION_TOP_LEFT, &map_stone));
-        assert!(!can_move_left(&POSITION_TOP_RIGHT, &map_stone));
-        assert!(!can_move_left(&POSITION_BOTTOM_LEFT, &map_stone));
-        assert!(!can_move_left(&POSITION_BOTTOM_RIGHT, &map_stone));
-        assert!(!can_move_left(&POSITION_MIDDLE, &map_stone));
+        assert!(!can_move_left(&POSITION_TOP_LEFT, &map_stone, &registry));
+        assert!(!can_move_left(&POSITION_TOP_RIGHT, &map_stone, &registry));
+        assert!(!can_move_left(&POSITION_BOTTOM_LEFT, &map_stone, &registry));
+        assert!(!can_move_left(
+            &POSITION_BOTTOM_RIGHT,
+            &map_stone,
+            &registry
+        ));
+        assert!(!can_move_left(&POSITION_MIDDLE, &map_stone, &registry));
     }
 
     #[test]
     fn test_can_move_left_with_actors() {
-        let map_plain = create_plain_map();
-
-        assert!(!can_move_left(&POSITION_MIDDLE_RIGHT, &map_plain,));
-        assert!(can_move_left(&POSITION_TOP_RIGHT, &map_plain,));
-        assert!(can_move_left(&POSITION_BOTTOM_RIGHT, &map_plain,));
+        let registry = TileRegistry::shipped();
+        let map_plain = create_plain_map_with_actor();
+
+        assert!(!can_move_left(
+            &POSITION_MIDDLE_RIGHT,
+            &map_plain,
+            &registry
+        ));
+        assert!(can_move_left(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(can_move_left(&POSITION_BOTTOM_RIGHT, &map_plain, &registry));
     }
 
     #[test]
     fn test_can_move_right_without_actors() {
+        let registry = TileRegistry::shipped();
         let map_plain = create_plain_map();
-        assert!(!can_move_right(&POSITION_TOP_RIGHT, &map_plain));
-        assert!(!can_move_right(&POSITION_BOTTOM_RIGHT, &map_plain));
-        assert!(can_move_right(&POSITION_TOP_LEFT, &map_plain));
-        assert!(can_move_right(&POSITION_BOTTOM_LEFT, &map_plain));
-        assert!(can_move_right(&POSITION_MIDDLE, &map_plain));
+        assert!(!can_move_right(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(!can_move_right(
+            &POSITION_BOTTOM_RIGHT,
+            &map_plain,
+            &registry
+        ));
+        assert!(can_move_right(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(can_move_right(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
+        assert!(can_move_right(&POSITION_MIDDLE, &map_plain, &registry));
 
         let map_stone = create_stone_map();
-        assert!(!can_move_right(&POSITION_TOP_LEFT, &map_stone));
-        assert!(!can_move_right(&POSITION_TOP_RIGHT, &map_stone));
-        assert!(!can_move_right(&POSITION_BOTTOM_LEFT, &map_stone));
-        assert!(!can_move_right(&POSITION_BOTTOM_RIGHT, &map_stone));
-        assert!(!can_move_right(&POSITION_MIDDLE, &map_stone));
+        assert!(!can_move_right(&POSITION_TOP_LEFT, &map_stone, &registry));
+        assert!(!can_move_right(&POSITION_TOP_RIGHT, &map_stone, &registry));
+        assert!(!can_move_right(
+            &POSITION_BOTTOM_LEFT,
+            &map_stone,
+            &registry
+        ));
+        assert!(!can_move_right(
+            &POSITION_BOTTOM_RIGHT,
+            &map_stone,
+            &registry
+        ));
+        assert!(!can_move_right(&POSITION_MIDDLE, &map_stone, &registry));
     }
 
     #[test]
     fn test_can_move_right_with_actors() {
-        let map_plain = create_plain_map();
-
-        assert!(!can_move_right(&POSITION_MIDDLE_LEFT, &map_plain,));
-        assert!(can_move_right(&POSITION_TOP_LEFT, &map_plain,));
-        assert!(can_move_right(&POSITION_BOTTOM_LEFT, &map_plain,));
+        let registry = TileRegistry::shipped();
+        let map_plain = create_plain_map_with_actor();
+
+        assert!(!can_move_right(
+            &POSITION_MIDDLE_LEFT,
+            &map_plain,
+            &registry
+        ));
+        assert!(can_move_right(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(can_move_right(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
     }
 
     #[test]
     fn test_can_move_up_without_actors() {
+        let registry = TileRegistry::shipped();
         let map_plain = create_plain_map();
-        assert!(!can_move_up(&POSITION_TOP_LEFT, &map_plain));
-        assert!(!can_move_up(&POSITION_TOP_RIGHT, &map_plain));
-        assert!(can_move_up(&POSITION_BOTTOM_LEFT, &map_plain));
-        assert!(can_move_up(&POSITION_BOTTOM_RIGHT, &map_plain));
-        assert!(can_move_up(&POSITION_MIDDLE, &map_plain));
+        assert!(!can_move_up(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(!can_move_up(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(can_move_up(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
+        assert!(can_move_up(&POSITION_BOTTOM_RIGHT, &map_plain, &registry));
+        assert!(can_move_up(&POSITION_MIDDLE, &map_plain, &registry));
 
         let map_stone = create_stone_map();
-        assert!(!can_move_up(&POSITION_TOP_LEFT, &map_stone));
-        assert!(!can_move_up(&POSITION_TOP_RIGHT, &map_stone));
-        assert!(!can_move_up(&POSITION_BOTTOM_LEFT, &map_stone));
-        assert!(!can_move_up(&POSITION_BOTTOM_RIGHT, &map_stone));
-        assert!(!can_move_up(&POSITION_MIDDLE, &map_stone));
+        assert!(!can_move_up(&POSITION_TOP_LEFT, &map_stone, &registry));
+        assert!(!can_move_up(&POSITION_TOP_RIGHT, &map_stone, &registry));
+        assert!(!can_move_up(&POSITION_BOTTOM_LEFT, &map_stone, &registry));
+        assert!(!can_move_up(&POSITION_BOTTOM_RIGHT, &map_stone, &registry));
+        assert!(!can_move_up(&POSITION_MIDDLE, &map_stone, &registry));
     }
 
     #[test]
     fn test_can_move_up_with_actors() {
-        let map_plain = create_plain_map();
+        let registry = TileRegistry::shipped();
+        let map_plain = create_plain_map_with_actor();
 
-        assert!(can_move_up(&POSITION_BOTTOM_RIGHT, &map_plain,));
-        assert!(can_move_up(&POSITION_BOTTOM_LEFT, &map_plain,));
-        assert!(!can_move_up(&POSITION_BOTTOM_MIDDLE, &map_plain,));
+        assert!(can_move_up(&POSITION_BOTTOM_RIGHT, &map_plain, &registry));
+        assert!(can_move_up(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
+        assert!(!can_move_up(&POSITION_BOTTOM_MIDDLE, &map_plain, &registry));
     }
 
     #[test]
     fn test_can_move_down_without_actors() {
+        let registry = TileRegistry::shipped();
         let map_plain = create_plain_map();
-        assert!(!can_move_down(&POSITION_BOTTOM_LEFT, &map_plain));
-        assert!(!can_move_down(&POSITION_BOTTOM_RIGHT, &map_plain));
-        assert!(can_move_down(&POSITION_TOP_LEFT, &map_plain));
-        assert!(can_move_down(&POSITION_TOP_RIGHT, &map_plain));
-        assert!(can_move_down(&POSITION_MIDDLE, &map_plain));
+        assert!(!can_move_down(&POSITION_BOTTOM_LEFT, &map_plain, &registry));
+        assert!(!can_move_down(
+            &POSITION_BOTTOM_RIGHT,
+            &map_plain,
+            &registry
+        ));
+        assert!(can_move_down(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(can_move_down(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(can_move_down(&POSITION_MIDDLE, &map_plain, &registry));
 
         let map_stone = create_stone_map();
-        assert!(!can_move_down(&POSITION_TOP_LEFT, &map_stone));
-        assert!(!can_move_down(&POSITION_TOP_RIGHT, &map_stone));
-        assert!(!can_move_down(&POSITION_BOTTOM_LEFT, &map_stone));
-        assert!(!can_move_down(&POSITION_BOTTOM_RIGHT, &map_stone));
-        assert!(!can_move_down(&POSITION_MIDDLE, &map_stone));
+        assert!(!can_move_down(&POSITION_TOP_LEFT, &map_stone, &registry));
+        assert!(!can_move_down(&POSITION_TOP_RIGHT, &map_stone, &registry));
+        assert!(!can_move_down(&POSITION_BOTTOM_LEFT, &map_stone, &registry));
+        assert!(!can_move_down(
+            &POSITION_BOTTOM_RIGHT,
+            &map_stone,
+            &registry
+        ));
+        assert!(!can_move_down(&POSITION_MIDDLE, &map_stone, &registry));
     }
 
     #[test]
     fn test_can_move_down_with_actors() {
-        let map_plain = create_plain_map();
+        let registry = TileRegistry::shipped();
+        let map_plain = create_plain_map_with_actor();
+
+        assert!(can_move_down(&POSITION_TOP_RIGHT, &map_plain, &registry));
+        assert!(can_move_down(&POSITION_TOP_LEFT, &map_plain, &registry));
+        assert!(!can_move_down(&POSITION_TOP_MIDDLE, &map_plain, &registry));
+    }
+
+    #[test]
+    fn test_move_to_player_digs_through_stones() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map {
+            width: 3,
+            height: 1,
+            tiles: vec![
+                Tile::default(),
+                Tile::from_kind(TileKind::GRASS_WITH_STONE),
+                Tile::default(),
+            ],
+            ..Default::default()
+        };
+        map.tiles[0].actor = Some(Actor::new(ActorKind::Blob));
+        map.tiles[2].actor = Some(Actor::new(ActorKind::Player));
+        let pos_player = MapPosition::new(2, 0);
+        let mut pos_mob = MapPosition::new(0, 0);
+
+        move_to_player(&registry, &pos_player, &mut pos_mob, &mut map, false);
+        assert_eq!(TileKind::GRASS_WITH_STONE, map.tiles[1].kind);
+
+        move_to_player(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(TileKind::GRASS_WITH_STONE_DAMAGED, map.tiles[1].kind);
+        move_to_player(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(TileKind::GRASS, map.tiles[1].kind);
+        assert_eq!(MapPosition::new(0, 0), pos_mob);
+
+        move_to_player(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(MapPosition::new(1, 0), pos_mob);
+    }
+
+    #[test]
+    fn test_can_move_respects_traversal_rules() {
+        let registry = TileRegistry::shipped();
+        let mut map = create_plain_map();
+        map.tiles[3] = Tile::from_kind(TileKind::SHALLOW_WATER);
+        map.tiles[5] = Tile::from_kind(TileKind::DEEP_WATER);
+
+        map.tiles[4].actor = Some(Actor::new(ActorKind::Blob));
+        assert!(!can_move_left(&POSITION_MIDDLE, &map, &registry));
+        assert!(!can_move_right(&POSITION_MIDDLE, &map, &registry));
+        assert!(can_move_up(&POSITION_MIDDLE, &map, &registry));
+
+        map.tiles[4].actor = Some(Actor::new(ActorKind::Rabbit));
+        assert!(can_move_left(&POSITION_MIDDLE, &map, &registry));
+        assert!(!can_move_right(&POSITION_MIDDLE, &map, &registry));
+    }
+
+    #[test]
+    fn test_move_along_path_with_movement_cost() {
+        let registry = TileRegistry::shipped();
+        let mut map = create_plain_map();
+        map.tiles[1] = Tile::from_kind(TileKind::MUD);
+        map.tiles[4] = Tile::from_kind(TileKind::MUD);
+        map.tiles[6].actor = Some(Actor::new(ActorKind::Blob));
+        map.tiles[2].actor = Some(Actor::new(ActorKind::Player));
+        let mut pos_mob = POSITION_BOTTOM_LEFT;
+
+        move_along_path(
+            &registry,
+            &POSITION_TOP_RIGHT,
+            &mut pos_mob,
+            &mut map,
+            true,
+        );
+        assert_eq!(POSITION_BOTTOM_MIDDLE, pos_mob);
+        move_along_path(
+            &registry,
+            &POSITION_TOP_RIGHT,
+            &mut pos_mob,
+            &mut map,
+            true,
+        );
+        assert_eq!(POSITION_BOTTOM_RIGHT, pos_mob);
+        move_along_path(
+            &registry,
+            &POSITION_TOP_RIGHT,
+            &mut pos_mob,
+            &mut map,
+            true,
+        );
+        assert_eq!(POSITION_MIDDLE_RIGHT, pos_mob);
+
+        // the mob stops next to the player
+        move_along_path(
+            &registry,
+            &POSITION_TOP_RIGHT,
+            &mut pos_mob,
+            &mut map,
+            true,
+        );
+        assert_eq!(POSITION_MIDDLE_RIGHT, pos_mob);
+
+        let mut cooldown = MovementCooldown::after_moving_to(
+            &map,
+            &registry,
+            &POSITION_TOP_MIDDLE,
+        );
+        assert!(cooldown.wait());
+        assert!(cooldown.wait());
+        assert!(!cooldown.wait());
+        assert_eq!(
+            0,
+            MovementCooldown::after_moving_to(
+                &map,
+                &registry,
+                &POSITION_BOTTOM_LEFT
+            )
+            .0
+        );
+    }
 
-        assert!(can_move_down(&POSITION_TOP_RIGHT, &map_plain,));
-        assert!(can_move_down(&POSITION_TOP_LEFT, &map_plain));
-        assert!(!can_move_down(&POSITION_TOP_MIDDLE, &map_plain));
+    #[test]
+    fn test_move_along_path_opens_doors() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map {
+            width: 4,
+            height: 1,
+            tiles: vec![
+                Tile::default(),
+                Tile::from_kind(TileKind::DOOR),
+                Tile::default(),
+                Tile::default(),
+            ],
+            ..Default::default()
+        };
+        map.tiles[0].actor = Some(Actor::new(ActorKind::Blob));
+        map.tiles[3].actor = Some(Actor::new(ActorKind::Player));
+        let pos_player = MapPosition::new(3, 0);
+        let mut pos_mob = MapPosition::new(0, 0);
+
+        move_along_path(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(TileKind::DOOR_OPEN, map.tiles[1].kind);
+        assert_eq!(MapPosition::new(0, 0), pos_mob);
+        move_along_path(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(MapPosition::new(1, 0), pos_mob);
+        move_along_path(&registry, &pos_player, &mut pos_mob, &mut map, true);
+        assert_eq!(MapPosition::new(2, 0), pos_mob);
     }
 
     #[test]
     fn test_enumerate_reachable_positions() {
+        let registry = TileRegistry::shipped();
         let map_plain = create_plain_map();
 
-        let reachable_positions =
-            enumerate_reachable_positions(&POSITION_MIDDLE, &map_plain);
+        let reachable_positions = enumerate_reachable_positions(
+            &POSITION_MIDDLE,
+            &map_plain,
+            &registry,
+        );
 
         assert_eq!(4, reachable_positions.len())
     }
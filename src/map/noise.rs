@@ -1,20 +1,36 @@
 use rand::seq::SliceRandom;
+use rand::Rng;
 
+/// Represents the parameters of a fractal Brownian motion, i.e. the sum of
+/// several layers (octaves) of noise with increasing frequency and decreasing
+/// amplitude.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FractalParameters {
+    /// The number of noise layers.
+    pub octaves: usize,
+    /// The frequency multiplier between two successive octaves.
+    pub lacunarity: f64,
+    /// The amplitude multiplier between two successive octaves.
+    pub persistence: f64,
+}
+
+/// Represents a 2D gradient noise (Perlin noise) generator. The noise only
+/// depends on its permutation table, which is shuffled with a random number
+/// generator.
 pub struct PerlinNoise {
     pub permutation: [u8; 512],
 }
 
 impl PerlinNoise {
-    pub fn new() -> Self {
+    pub fn new(rng: &mut impl Rng) -> Self {
         Self {
-            permutation: Self::generate_permutation(),
+            permutation: Self::generate_permutation(rng),
         }
     }
 
-    fn generate_permutation() -> [u8; 512] {
+    fn generate_permutation(rng: &mut impl Rng) -> [u8; 512] {
         let mut permutation: Vec<u8> = (0..=255).collect();
-        let mut rng = rand::thread_rng();
-        permutation.shuffle(&mut rng);
+        permutation.shuffle(rng);
 
         let mut result = [0; 512];
         result[..256].copy_from_slice(&permutation);
@@ -23,38 +39,60 @@ impl PerlinNoise {
         result
     }
 
+    /// Returns the noise value at given coordinates, in the range [-1, 1].
+    /// The noise is continuous and is zero on integer coordinates.
     pub fn perlin_noise(&self, x: f64, y: f64) -> f64 {
-        let x_wrapped = x as i32 & 255;
-        let y_wrapped = y as i32 & 255;
+        // coordinates of the grid cell, wrapped to the permutation size
+        let x_cell = floor_to_index(x.floor().rem_euclid(256.0));
+        let y_cell = floor_to_index(y.floor().rem_euclid(256.0));
 
         let x_frac = x - x.floor();
         let y_frac = y - y.floor();
 
         // Compute fade curves for x and y
-        let u = fade(x_frac);
-        let v = fade(y_frac);
-
-        // Hash coordinates of the 8 cube corners
-        let a = self.permutation[x_wrapped as usize] + y_wrapped as u8;
-        let aa = self.permutation[a as usize];
-        let ab = self.permutation[(a + 1) as usize];
-        let b = self.permutation[(x_wrapped + 1) as usize] + y_wrapped as u8;
-        let ba = self.permutation[b as usize];
-        let bb = self.permutation[(b + 1) as usize];
-
-        // And add blended results from 8 corners of the cube
-        let grad_aa = grad(self.permutation[aa as usize], x_frac, y_frac);
-        let grad_ba = grad(self.permutation[ba as usize], x_frac - 1.0, y_frac);
-        let grad_ab = grad(self.permutation[ab as usize], x_frac, y_frac - 1.0);
-        let grad_bb =
-            grad(self.permutation[bb as usize], x_frac - 1.0, y_frac - 1.0);
+        let fade_x = fade(x_frac);
+        let fade_y = fade(y_frac);
+
+        // Hash coordinates of the 4 cell corners
+        let permutation = &self.permutation;
+        let a = usize::from(permutation[x_cell]) + y_cell;
+        let b = usize::from(permutation[x_cell + 1]) + y_cell;
+
+        // And add blended results from the 4 corners of the cell
+        let top_left = grad(permutation[a], x_frac, y_frac);
+        let top_right = grad(permutation[b], x_frac - 1.0, y_frac);
+        let bottom_left = grad(permutation[a + 1], x_frac, y_frac - 1.0);
+        let bottom_right = grad(permutation[b + 1], x_frac - 1.0, y_frac - 1.0);
 
         // Weight the contributions from each corner
-        let x1 = lerp(grad_aa, grad_ba, u);
-        let x2 = lerp(grad_ab, grad_bb, u);
+        let x1 = lerp(top_left, top_right, fade_x);
+        let x2 = lerp(bottom_left, bottom_right, fade_x);
 
         // And interpolate the results along y
-        lerp(x1, x2, v)
+        lerp(x1, x2, fade_y)
+    }
+
+    /// Returns the fractal Brownian motion value at given coordinates, in the
+    /// range [-1, 1]. The octaves are normalized by their total amplitude.
+    pub fn fbm(&self, x: f64, y: f64, parameters: &FractalParameters) -> f64 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut total_amplitude = 0.0;
+
+        for _ in 0..parameters.octaves {
+            total +=
+                amplitude * self.perlin_noise(x * frequency, y * frequency);
+            total_amplitude += amplitude;
+            frequency *= parameters.lacunarity;
+            amplitude *= parameters.persistence;
+        }
+
+        if total_amplitude.abs() < f64::EPSILON {
+            0.0
+        } else {
+            total / total_amplitude
+        }
     }
 }
 
@@ -62,13 +100,18 @@ fn fade(t: f64) -> f64 {
     t * t * t * t.mul_add(t.mul_add(6.0, -15.0), 10.0)
 }
 
-fn grad(hash: u8, x: f64, _y: f64) -> f64 {
-    let h = hash & 15;
-    let grad = 1.0 + f64::from(h & 7); // Gradient value 1-8
-    if (h & 8) != 0 {
-        -grad * x
-    } else {
-        grad * x
+/// Returns the dot product between the distance vector `(x, y)` and one of
+/// the 8 gradient directions (straight or diagonal) picked by `hash`.
+fn grad(hash: u8, x: f64, y: f64) -> f64 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
     }
 }
 
@@ -76,15 +119,163 @@ fn lerp(a: f64, b: f64, t: f64) -> f64 {
     t.mul_add(b - a, a)
 }
 
+/// Returns the value below which a given proportion of the values falls.
+///
+/// # Arguments
+///
+/// - `values`: The values, in any order.
+/// - `proportion`: The proportion, between 0 and 1.
+pub fn percentile(values: &[f64], proportion: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let len = f64::from(u32::try_from(sorted.len()).unwrap_or(u32::MAX));
+    let index = floor_to_index(proportion.clamp(0.0, 1.0) * len);
+    sorted[index.min(sorted.len() - 1)]
+}
+
+/// Returns the noise coordinate of a tile coordinate, i.e. the tile coordinate
+/// multiplied by a scale. The coordinates above `u32::MAX` are clamped rather
+/// than cast with a loss of precision.
+pub fn scale_coordinate(coordinate: usize, scale: f64) -> f64 {
+    f64::from(u32::try_from(coordinate).unwrap_or(u32::MAX)) * scale
+}
+
+/// Returns the largest index not greater than a value, e.g. for turning a
+/// proportion of a length into an index. Negative values give 0, and the
+/// index is at most `u32::MAX`.
+///
+/// The index is built bit by bit rather than by casting the value, which
+/// could silently truncate it.
+pub fn floor_to_index(value: f64) -> usize {
+    let mut index: u32 = 0;
+    for bit in (0..u32::BITS).rev() {
+        let candidate = index | 1 << bit;
+        if f64::from(candidate) <= value {
+            index = candidate;
+        }
+    }
+    usize::try_from(index).unwrap_or(usize::MAX)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const FRACTAL: FractalParameters = FractalParameters {
+        octaves: 4,
+        lacunarity: 2.0,
+        persistence: 0.5,
+    };
+
+    /// Asserts that two noise values are equal, up to rounding errors.
+    fn assert_near(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < f64::EPSILON,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn sample_points() -> impl Iterator<Item = (f64, f64)> {
+        (-40..40).flat_map(|i| {
+            (-40..40).map(move |j| (f64::from(i) * 0.37, f64::from(j) * 0.29))
+        })
+    }
 
     #[test]
     pub fn test_perlin_noise() {
-        let noise = PerlinNoise::new();
+        let noise = PerlinNoise::new(&mut StdRng::seed_from_u64(0));
         let x = 0.5;
         let y = 0.5;
-        assert_eq!(noise.perlin_noise(x, y), noise.perlin_noise(x, y));
+        assert_near(noise.perlin_noise(x, y), noise.perlin_noise(x, y));
+    }
+
+    #[test]
+    fn test_perlin_noise_is_seedable() {
+        let noise_a = PerlinNoise::new(&mut StdRng::seed_from_u64(7));
+        let noise_b = PerlinNoise::new(&mut StdRng::seed_from_u64(7));
+        let noise_c = PerlinNoise::new(&mut StdRng::seed_from_u64(8));
+
+        assert!(sample_points().all(|(x, y)| {
+            (noise_a.perlin_noise(x, y) - noise_b.perlin_noise(x, y)).abs()
+                < f64::EPSILON
+        }));
+        assert!(sample_points().any(|(x, y)| {
+            (noise_a.perlin_noise(x, y) - noise_c.perlin_noise(x, y)).abs()
+                > f64::EPSILON
+        }));
+    }
+
+    #[test]
+    fn test_perlin_noise_range() {
+        for seed in 0..10 {
+            let noise = PerlinNoise::new(&mut StdRng::seed_from_u64(seed));
+            for (x, y) in sample_points() {
+                let value = noise.perlin_noise(x, y);
+                assert!((-1.0..=1.0).contains(&value));
+                let value = noise.fbm(x, y, &FRACTAL);
+                assert!((-1.0..=1.0).contains(&value));
+            }
+            assert_near(0.0, noise.perlin_noise(3.0, -5.0));
+        }
+    }
+
+    #[test]
+    fn test_perlin_noise_continuity() {
+        let noise = PerlinNoise::new(&mut StdRng::seed_from_u64(0));
+        let epsilon = 1e-4;
+
+        for (x, y) in sample_points() {
+            let value = noise.perlin_noise(x, y);
+            let value_x = noise.perlin_noise(x + epsilon, y);
+            let value_y = noise.perlin_noise(x, y + epsilon);
+            assert!((value - value_x).abs() < 1e-2);
+            assert!((value - value_y).abs() < 1e-2);
+
+            let value = noise.fbm(x, y, &FRACTAL);
+            let value_x = noise.fbm(x + epsilon, y, &FRACTAL);
+            assert!((value - value_x).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_perlin_noise_varies_along_both_axes() {
+        let noise = PerlinNoise::new(&mut StdRng::seed_from_u64(0));
+
+        let varies_along_y = (0..20).any(|j| {
+            let y = f64::from(j) * 0.3;
+            (noise.perlin_noise(0.5, y) - noise.perlin_noise(0.5, 0.5)).abs()
+                > f64::EPSILON
+        });
+        let varies_along_x = (0..20).any(|i| {
+            let x = f64::from(i) * 0.3;
+            (noise.perlin_noise(x, 0.5) - noise.perlin_noise(0.5, 0.5)).abs()
+                > f64::EPSILON
+        });
+        assert!(varies_along_x && varies_along_y);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<f64> = (0..100).map(f64::from).rev().collect();
+        assert_near(0.0, percentile(&values, 0.0));
+        assert_near(30.0, percentile(&values, 0.3));
+        assert_near(99.0, percentile(&values, 1.0));
+        assert_near(0.0, percentile(&[], 0.5));
+    }
+
+    #[test]
+    fn test_floor_to_index() {
+        assert_eq!(0, floor_to_index(-3.5));
+        assert_eq!(0, floor_to_index(0.99));
+        assert_eq!(1, floor_to_index(1.0));
+        assert_eq!(255, floor_to_index(255.9));
+        assert_eq!(4_000_000_000, floor_to_index(4e9));
+        assert_eq!(usize::try_from(u32::MAX).unwrap(), floor_to_index(1e12));
+        assert_eq!(0, floor_to_index(f64::NAN));
     }
 }
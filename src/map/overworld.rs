@@ -0,0 +1,220 @@
+use rand::rngs::StdRng;
+
+use super::noise::{percentile, scale_coordinate, PerlinNoise};
+use super::tile::{Tile, TileKind};
+use crate::prelude::*;
+
+/// Represents the kind of a location, deciding which generators create its
+/// maps (see `MapGeneratorEntry::locations`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LocationKind {
+    Forest,
+    Cave,
+    Ruins,
+}
+
+impl LocationKind {
+    pub const ALL: [Self; 3] = [Self::Forest, Self::Cave, Self::Ruins];
+
+    /// Returns the kind of tile representing the location on the overworld.
+    pub const fn tile_kind(self) -> TileKind {
+        match self {
+            Self::Forest => TileKind::FOREST,
+            Self::Cave => TileKind::CAVE,
+            Self::Ruins => TileKind::RUINS,
+        }
+    }
+}
+
+/// Represents a cell of the overworld.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location {
+    /// The kind of the location.
+    pub kind: LocationKind,
+    /// The id of the location's first map in the `WorldGraph`, or `None` if
+    /// the player never entered the location.
+    pub map: Option<usize>,
+}
+
+/// Represents the overworld, a coarse-grained map where each cell is a
+/// location. Entering a location leads to its local maps, and leaving them
+/// through an edge leads back to the overworld.
+///
+/// The overworld is displayed as the map `OVERWORLD_MAP_ID` of the
+/// `WorldGraph`.
+#[derive(Clone, Resource)]
+pub struct Overworld {
+    /// The overworld's width.
+    pub width: usize,
+    /// The overworld's height.
+    pub height: usize,
+    /// All locations, stored row by row like a `Map`'s tiles.
+    pub locations: Vec<Location>,
+    /// The position of the player on the overworld, i.e. the last location
+    /// entered.
+    pub position: MapPosition,
+}
+
+impl Overworld {
+    /// Generates an overworld of dimensions `width`x`height` with Perlin
+    /// noise: the lowest areas are caves and the others forests, scattered
+    /// with ruins. The player starts at the center.
+    pub fn generate(width: usize, height: usize, rng: &mut StdRng) -> Self {
+        let noise = PerlinNoise::new(rng);
+        let noise_values: Vec<_> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                noise.fbm(
+                    scale_coordinate(x, PERLIN_NOISE_SCALE),
+                    scale_coordinate(y, PERLIN_NOISE_SCALE),
+                    &PERLIN_FRACTAL,
+                )
+            })
+            .collect();
+        let threshold_cave =
+            percentile(&noise_values, OVERWORLD_PERCENTILE_CAVE);
+
+        let locations = noise_values
+            .iter()
+            .map(|&noise_value| {
+                let kind = if noise_value < threshold_cave {
+                    LocationKind::Cave
+                } else if rng.gen_bool(OVERWORLD_PROBABILITY_RUINS) {
+                    LocationKind::Ruins
+                } else {
+                    LocationKind::Forest
+                };
+                Location { kind, map: None }
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            locations,
+            position: MapPosition::new(width / 2, height / 2),
+        }
+    }
+
+    /// Returns the location at a given position, if any.
+    pub fn location_at(&self, pos: &MapPosition) -> Option<&Location> {
+        if pos.x >= self.width || pos.y >= self.height {
+            return None;
+        }
+        self.locations.get(pos.x + pos.y * self.width)
+    }
+
+    /// Enters the location at a given position. The location's first map is
+    /// added to the world the first time the location is entered.
+    ///
+    /// # Returns
+    ///
+    /// The exit leading from the overworld to the location's first map, or
+    /// `None` if there's no location at the position.
+    pub fn enter(
+        &mut self,
+        pos: MapPosition,
+        world: &mut WorldGraph,
+    ) -> Option<MapExit> {
+        let kind = self.location_at(&pos)?.kind;
+        let location = &mut self.locations[pos.x + pos.y * self.width];
+
+        let map = *location
+            .map
+            .get_or_insert_with(|| world.add_map(0, Some(kind)));
+        self.position = pos;
+
+        Some(MapExit {
+            position: pos,
+            edge: None,
+            destination: Some(map),
+        })
+    }
+
+    /// Returns the map displaying the overworld, where each tile is a
+    /// location. The player arrives on the last location entered.
+    pub fn to_map(&self) -> Map {
+        Map {
+            width: self.width,
+            height: self.height,
+            tiles: self
+                .locations
+                .iter()
+                .map(|location| Tile::from_kind(location.kind.tile_kind()))
+                .collect(),
+            entrance: Some(self.position),
+            ..Default::default()
+        }
+    }
+}
+
+/// Generates the `Overworld` from the run seed, unless it was restored from a
+/// saved run (see `SavedRun::resume`).
+pub fn initialize_overworld(
+    mut commands: Commands,
+    run_seed: Res<RunSeed>,
+    overworld: Option<Res<Overworld>>,
+) {
+    if overworld.is_some() {
+        return;
+    }
+    let mut rng = StdRng::seed_from_u64(run_seed.for_map(OVERWORLD_MAP_ID));
+    commands.insert_resource(Overworld::generate(
+        OVERWORLD_WIDTH,
+        OVERWORLD_HEIGHT,
+        &mut rng,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_overworld() {
+        let registry = TileRegistry::shipped();
+        let mut rng = StdRng::seed_from_u64(0);
+        let overworld =
+            Overworld::generate(OVERWORLD_WIDTH, OVERWORLD_HEIGHT, &mut rng);
+        assert_eq!(
+            OVERWORLD_WIDTH * OVERWORLD_HEIGHT,
+            overworld.locations.len()
+        );
+        for kind in LocationKind::ALL {
+            assert!(overworld.locations.iter().any(|l| l.kind == kind));
+        }
+
+        let map = overworld.to_map();
+        assert_eq!(overworld.locations.len(), map.tiles.len());
+        assert!(map.tiles.iter().all(|tile| tile.is_walkable(&registry)));
+        assert_eq!(Some(overworld.position), map.entrance);
+        assert!(map.exits.is_empty());
+    }
+
+    #[test]
+    fn test_enter_location() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut overworld = Overworld::generate(4, 3, &mut rng);
+        let mut world = WorldGraph::default();
+
+        let pos = MapPosition::new(3, 2);
+        let exit = overworld.enter(pos, &mut world).unwrap();
+        let map = exit.destination.unwrap();
+        assert_eq!(pos, overworld.position);
+        assert_eq!(0, world.depth(map));
+        assert_eq!(
+            overworld.location_at(&pos).map(|l| l.kind),
+            world.location(map)
+        );
+
+        // the location keeps its map when entered again
+        overworld.position = MapPosition::new(0, 0);
+        assert_eq!(Some(exit), overworld.enter(pos, &mut world));
+        assert_eq!(2, world.nodes.len());
+
+        assert!(overworld
+            .enter(MapPosition::new(4, 0), &mut world)
+            .is_none());
+        assert!(overworld.location_at(&MapPosition::new(0, 3)).is_none());
+    }
+}
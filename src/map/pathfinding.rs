@@ -0,0 +1,165 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::prelude::*;
+
+impl Map {
+    /// Returns whether the actor standing at a given tile can walk on another
+    /// tile, considering the tile's kind, the actors on it and the actor's
+    /// traversal rules (see `Actor::can_cross`).
+    pub fn can_enter(
+        &self,
+        registry: &TileRegistry,
+        index_from: usize,
+        index_to: usize,
+    ) -> bool {
+        let tile = &self.tiles[index_to];
+        tile.is_walkable(registry)
+            && self.tiles[index_from]
+                .actor
+                .is_none_or(|actor| actor.can_cross(registry, tile.kind))
+    }
+
+    /// Finds the cheapest path for the actor standing at `from` to reach `to`
+    /// with the A* algorithm, the cost of a step being the movement cost of
+    /// the tile entered. The tile `to` can be occupied by another actor, e.g.
+    /// when a mob is chasing the player. An actor able to open doors (see
+    /// `Actor::can_open_doors`) goes through the unlocked ones, opening a door
+    /// costing a step.
+    ///
+    /// # Returns
+    ///
+    /// The positions walked on, from the one next to `from` to `to`, or `None`
+    /// if `to` can't be reached.
+    pub fn find_path(
+        &self,
+        registry: &TileRegistry,
+        from: &MapPosition,
+        to: &MapPosition,
+    ) -> Option<Vec<MapPosition>> {
+        let start = self.as_tile_index(from).ok()?;
+        let goal = self.as_tile_index(to).ok()?;
+        let heuristic = |index: usize| {
+            let (x, y) = (index % self.width, index / self.width);
+            x.abs_diff(to.x) + y.abs_diff(to.y)
+        };
+
+        let actor = self.tiles[start].actor;
+        let can_cross = |kind: TileKind| {
+            actor.is_none_or(|actor| actor.can_cross(registry, kind))
+        };
+        let can_open = |kind: TileKind| {
+            actor.is_some_and(Actor::can_open_doors)
+                && kind.opened(registry).is_some()
+                && !kind.is_locked(registry)
+        };
+
+        let mut costs = vec![usize::MAX; self.tiles.len()];
+        let mut previous = vec![None; self.tiles.len()];
+        let mut open = BinaryHeap::from([Reverse((heuristic(start), start))]);
+        costs[start] = 0;
+
+        while let Some(Reverse((_, index))) = open.pop() {
+            if index == goal {
+                let mut path = vec![];
+                let mut current = goal;
+                while current != start {
+                    path.push(MapPosition::new(
+                        current % self.width,
+                        current / self.width,
+                    ));
+                    current = previous[current]?;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in self.cardinal_neighbors(index) {
+                let kind = self.tiles[neighbor].kind;
+                let occupied = self.tiles[neighbor].actor.is_some();
+                if !(kind.is_walkable(registry) || can_open(kind))
+                    || !can_cross(kind)
+                    || (occupied && neighbor != goal)
+                {
+                    continue;
+                }
+                let step_cost = match kind.opened(registry) {
+                    Some(opened) if !kind.is_walkable(registry) => {
+                        1 + opened.movement_cost(registry)
+                    }
+                    _ => kind.movement_cost(registry),
+                };
+                let cost = costs[index] + step_cost as usize;
+                if cost < costs[neighbor] {
+                    costs[neighbor] = cost;
+                    previous[neighbor] = Some(index);
+                    open.push(Reverse((cost + heuristic(neighbor), neighbor)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_path_avoids_costly_tiles() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["B,,@", "...."]);
+        let path = map
+            .find_path(
+                &registry,
+                &MapPosition::new(0, 0),
+                &MapPosition::new(3, 0),
+            )
+            .unwrap();
+        assert_eq!(
+            vec![
+                MapPosition::new(0, 1),
+                MapPosition::new(1, 1),
+                MapPosition::new(2, 1),
+                MapPosition::new(3, 1),
+                MapPosition::new(3, 0),
+            ],
+            path
+        );
+    }
+
+    #[test]
+    fn test_find_path_respects_traversal_rules() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["B~@", "#.#"]);
+        let (from, to) = (MapPosition::new(0, 0), MapPosition::new(2, 0));
+        assert!(map.find_path(&registry, &from, &to).is_none());
+        assert!(!map.can_enter(&registry, 0, 1));
+
+        // the player wades through the water
+        assert!(map.can_enter(&registry, 2, 1));
+        assert_eq!(
+            Some(vec![MapPosition::new(1, 0), from]),
+            map.find_path(&registry, &to, &from)
+        );
+    }
+
+    #[test]
+    fn test_find_path_through_doors() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["B+_@", "####"]);
+        let (from, to) = (MapPosition::new(0, 0), MapPosition::new(3, 0));
+        assert_eq!(
+            Some(vec![MapPosition::new(1, 0), MapPosition::new(2, 0), to]),
+            map.find_path(&registry, &from, &to)
+        );
+
+        // the locked doors need a key, which only the player carries
+        map.tiles[1].kind = TileKind::DOOR_LOCKED;
+        assert!(map.find_path(&registry, &from, &to).is_none());
+
+        // the neutral mobs never open doors
+        map.tiles[1].kind = TileKind::DOOR;
+        map.tiles[0].actor = Some(Actor::new(ActorKind::Rabbit));
+        assert!(map.find_path(&registry, &from, &to).is_none());
+    }
+}
@@ -0,0 +1,317 @@
+use super::tile::TileKind;
+use crate::prelude::*;
+
+impl Map {
+    /// Returns the flat indices of the tiles directly on the left, right, top
+    /// and bottom of a given tile, considering the map borders.
+    pub fn cardinal_neighbors(&self, index: usize) -> Vec<usize> {
+        let mut neighbors = Vec::with_capacity(4);
+        let x = index % self.width;
+        let y = index / self.width;
+        if x > 0 {
+            neighbors.push(index - 1);
+        }
+        if x < self.width - 1 {
+            neighbors.push(index + 1);
+        }
+        if y > 0 {
+            neighbors.push(index - self.width);
+        }
+        if y < self.height - 1 {
+            neighbors.push(index + self.width);
+        }
+        neighbors
+    }
+
+    /// Returns the regions of the map, a region being a set of passable tiles
+    /// (see `TileKind::is_passable`) connected together (in the cardinal
+    /// directions, as actors move). Actors standing on tiles, and the doors
+    /// being locked, are ignored.
+    ///
+    /// # Returns
+    ///
+    /// A vector of regions, each region being the sorted flat indices of its
+    /// tiles. The regions are sorted by their first tile index.
+    pub fn walkable_regions(&self, registry: &TileRegistry) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.tiles.len()];
+        let mut regions = vec![];
+
+        for start in 0..self.tiles.len() {
+            if visited[start] || !self.tiles[start].kind.is_passable(registry) {
+                continue;
+            }
+
+            visited[start] = true;
+            let mut region = vec![];
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                region.push(index);
+                for neighbor in self.cardinal_neighbors(index) {
+                    if !visited[neighbor]
+                        && self.tiles[neighbor].kind.is_passable(registry)
+                    {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            region.sort_unstable();
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    /// Connects all walkable regions of the map together, so every walkable
+    /// tile can be reached from any other one.
+    ///
+    /// The smaller regions are joined one by one to the largest region by
+    /// carving a corridor between their closest tiles (see
+    /// `TileKind::corridor_kind`). If the map has no walkable tile at all, its
+    /// center tile is carved.
+    ///
+    /// The `protected` areas (e.g. vaults) are only entered through their
+    /// passable tiles, unless a region can't be joined otherwise.
+    pub fn connect_regions(
+        &mut self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+    ) {
+        if self.tiles.is_empty() {
+            return;
+        }
+
+        loop {
+            let mut regions = self.walkable_regions(registry);
+            if regions.is_empty() {
+                let center = self.width / 2 + self.height / 2 * self.width;
+                self.tiles[center].kind = TileKind::GRASS;
+                continue;
+            }
+            if regions.len() == 1 {
+                return;
+            }
+
+            // stable sort, so the ties are broken by the regions' order
+            regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+            let (from, to) = self
+                .closest_tiles(registry, protected, &regions[1], &regions[0])
+                .or_else(|| {
+                    self.closest_tiles(registry, &[], &regions[1], &regions[0])
+                })
+                .expect("regions can't be empty");
+            self.carve_corridor(
+                registry,
+                from,
+                to,
+                self.tiles[from].kind.corridor_kind(),
+            );
+        }
+    }
+
+    /// Returns the pair of tiles (one in each region) with the smallest
+    /// Manhattan distance between them, such that the corridor between them
+    /// leaves the `protected` areas unchanged (see `Map::can_carve_corridor`).
+    /// The corridor goes from the first tile of the pair to the second one,
+    /// so both L shapes are tried.
+    fn closest_tiles(
+        &self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        region_a: &[usize],
+        region_b: &[usize],
+    ) -> Option<(usize, usize)> {
+        let distance = |a: usize, b: usize| {
+            (a % self.width).abs_diff(b % self.width)
+                + (a / self.width).abs_diff(b / self.width)
+        };
+
+        let mut closest: Option<(usize, usize, usize)> = None;
+        for (a, b) in region_a
+            .iter()
+            .flat_map(|&a| region_b.iter().map(move |&b| (a, b)))
+        {
+            let d = distance(a, b);
+            if closest.is_some_and(|(_, _, d_min)| d >= d_min) {
+                continue;
+            }
+            closest = [(a, b), (b, a)]
+                .into_iter()
+                .find(|&(from, to)| {
+                    self.can_carve_corridor(registry, protected, from, to)
+                })
+                .map(|(from, to)| (from, to, d))
+                .or(closest);
+        }
+        closest.map(|(from, to, _)| (from, to))
+    }
+
+    /// Returns the flat indices of the tiles of the L-shaped corridor between
+    /// two tiles, horizontally first then vertically.
+    fn corridor_tiles(&self, from: usize, to: usize) -> Vec<usize> {
+        let (mut x, mut y) = (from % self.width, from / self.width);
+        let (x_to, y_to) = (to % self.width, to / self.width);
+
+        let mut tiles = vec![];
+        loop {
+            tiles.push(x + y * self.width);
+            if x != x_to {
+                x = if x < x_to { x + 1 } else { x - 1 };
+            } else if y != y_to {
+                y = if y < y_to { y + 1 } else { y - 1 };
+            } else {
+                return tiles;
+            }
+        }
+    }
+
+    /// Returns whether a corridor between two tiles (see
+    /// `Map::carve_corridor`) only crosses the `protected` areas through
+    /// passable tiles, leaving them unchanged.
+    pub fn can_carve_corridor(
+        &self,
+        registry: &TileRegistry,
+        protected: &[MapArea],
+        from: usize,
+        to: usize,
+    ) -> bool {
+        protected.is_empty()
+            || self.corridor_tiles(from, to).into_iter().all(|index| {
+                self.tiles[index].kind.is_passable(registry)
+                    || !self.is_in_areas(protected, index)
+            })
+    }
+
+    /// Returns whether a tile is inside one of the given areas.
+    pub fn is_in_areas(&self, areas: &[MapArea], index: usize) -> bool {
+        let (x, y) = (index % self.width, index / self.width);
+        areas.iter().any(|area| area.contains(x, y))
+    }
+
+    /// Carves a L-shaped corridor of a given tile kind between two tiles,
+    /// horizontally first then vertically. Tiles already passable are left
+    /// unchanged.
+    pub fn carve_corridor(
+        &mut self,
+        registry: &TileRegistry,
+        from: usize,
+        to: usize,
+        kind: TileKind,
+    ) {
+        for index in self.corridor_tiles(from, to) {
+            if !self.tiles[index].kind.is_passable(registry) {
+                self.tiles[index].kind = kind;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    /// Returns whether a position can be reached from another one by walking,
+    /// actors standing on tiles being ignored.
+    fn is_reachable(map: &Map, from: &MapPosition, to: &MapPosition) -> bool {
+        let registry = TileRegistry::shipped();
+        let (Ok(index_from), Ok(index_to)) =
+            (map.as_tile_index(from), map.as_tile_index(to))
+        else {
+            return false;
+        };
+
+        map.walkable_regions(&registry)
+            .iter()
+            .any(|r| r.contains(&index_from) && r.contains(&index_to))
+    }
+
+    #[test]
+    fn test_walkable_regions() {
+        let registry = TileRegistry::shipped();
+        let map = Map::parse_rows(&["..o.", "ooo.", ".o.."]);
+
+        let regions = map.walkable_regions(&registry);
+        assert_eq!(vec![vec![0, 1], vec![3, 7, 10, 11], vec![8]], regions);
+    }
+
+    #[test]
+    fn test_connect_regions() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["..o.", "ooo.", ".o.."]);
+
+        map.connect_regions(&registry, &[]);
+        assert_eq!(1, map.walkable_regions(&registry).len());
+        assert!(is_reachable(
+            &map,
+            &MapPosition::new(0, 0),
+            &MapPosition::new(0, 2)
+        ));
+
+        let mut map = Map::parse_rows(&["ooo", "ooo", "ooo"]);
+        map.connect_regions(&registry, &[]);
+        assert_eq!(vec![vec![4]], map.walkable_regions(&registry));
+    }
+
+    #[test]
+    fn test_connect_regions_around_protected_areas() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&[".###.", ".###.", ".ooo."]);
+        let vault = MapArea {
+            x: 1,
+            y: 0,
+            width: 3,
+            height: 2,
+        };
+
+        map.connect_regions(&registry, &[vault]);
+        assert_eq!(1, map.walkable_regions(&registry).len());
+        for index in [1, 2, 3, 6, 7, 8] {
+            assert_eq!(TileKind::WALL, map.tiles[index].kind);
+        }
+    }
+
+    #[test]
+    fn test_exits_reachable_from_every_spawn() {
+        let generators = MapGeneratorRegistry::shipped();
+        let registry = TileRegistry::shipped();
+
+        for seed in 0..2000 {
+            let mut rng = StdRng::seed_from_u64(seed as u64);
+            let entry = &generators.entries[seed % generators.entries.len()];
+            let edge =
+                [None, Some(MapEdge::Left), Some(MapEdge::Top)][seed % 3];
+            let map = entry.generate(
+                &registry,
+                MAP_WIDTH,
+                MAP_HEIGHT,
+                edge,
+                &[],
+                &mut rng,
+            );
+
+            let regions = map.walkable_regions(&registry);
+            assert_eq!(1, regions.len(), "disconnected map for seed {seed}");
+            for exit in &map.exits {
+                let index = map.as_tile_index(&exit.position).unwrap();
+                assert!(regions[0].contains(&index));
+            }
+
+            let mut spawns = map
+                .generate_random_positions(
+                    &registry,
+                    ActorKind::Player,
+                    10,
+                    &[],
+                    &mut rng,
+                )
+                .unwrap();
+            spawns.extend(map.entrance);
+            for spawn in &spawns {
+                for exit in &map.exits {
+                    assert!(is_reachable(&map, spawn, &exit.position));
+                }
+            }
+        }
+    }
+}
@@ -8,16 +8,10 @@ pub struct Tile {
     pub actor: Option<Actor>,
 }
 
-/// Represent all kind of tiles.
-#[derive(Clone, Copy, Default)]
-pub enum TileKind {
-    #[default]
-    Grass,
-    GrassWithFlower,
-    GrassWithStone,
-    GrassWithStoneDamaged,
-    LevelExit,
-}
+/// Represents a kind of tile, as the id of its definition in the
+/// `TileRegistry`. The kinds used by the game itself are built in.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TileKind(pub usize);
 
 impl Default for Tile {
     fn default() -> Self {
@@ -36,29 +30,159 @@ impl Tile {
     }
 
     /// Returns whether or not a tile can be walked on by an actor.
-    pub const fn is_walkable(self) -> bool {
-        match self.kind {
-            TileKind::Grass
-            | TileKind::GrassWithFlower
-            | TileKind::LevelExit => self.actor.is_none(),
-            TileKind::GrassWithStone | TileKind::GrassWithStoneDamaged => false,
-        }
+    pub fn is_walkable(self, registry: &TileRegistry) -> bool {
+        self.kind.is_walkable(registry) && self.actor.is_none()
     }
 }
 impl TileKind {
-    /// Returns the sprite index for a given `TileType`. The index corresponds
-    /// to the location in the tilesheet where the corresponding tile is.
-    pub const fn to_sprite_idx(kind: Self) -> usize {
-        match kind {
-            Self::Grass => TILESET_TERRAIN_IDX_GRASS,
-            Self::GrassWithFlower => TILESET_TERRAIN_IDX_GRASS_WITH_FLOWER,
-            Self::GrassWithStone => TILESET_TERRAIN_IDX_GRASS_WITH_STONE,
-            Self::GrassWithStoneDamaged => {
-                TILESET_TERRAIN_IDX_GRASS_WITH_STONE_DAMAGED
-            }
-            Self::LevelExit => TILESET_TERRAIN_IDX_SIGNPOST,
+    pub const GRASS: Self = Self(0);
+    pub const GRASS_WITH_FLOWER: Self = Self(1);
+    pub const GRASS_WITH_STONE: Self = Self(2);
+    pub const GRASS_WITH_STONE_DAMAGED: Self = Self(3);
+    pub const LEVEL_EXIT: Self = Self(4);
+    pub const WALL: Self = Self(5);
+    pub const FLOOR: Self = Self(6);
+    pub const DOOR: Self = Self(7);
+    pub const FOREST: Self = Self(8);
+    pub const CAVE: Self = Self(9);
+    pub const RUINS: Self = Self(10);
+    pub const SHALLOW_WATER: Self = Self(11);
+    pub const DEEP_WATER: Self = Self(12);
+    pub const MUD: Self = Self(13);
+    pub const TALL_GRASS: Self = Self(14);
+    pub const DOOR_OPEN: Self = Self(15);
+    pub const DOOR_LOCKED: Self = Self(16);
+
+    /// The names of the built-in kinds in the tile definitions, in the order
+    /// of their ids.
+    pub const BUILTIN: [&'static str; 17] = [
+        "grass",
+        "grass_with_flower",
+        "grass_with_stone",
+        "grass_with_stone_damaged",
+        "level_exit",
+        "wall",
+        "floor",
+        "door",
+        "forest",
+        "cave",
+        "ruins",
+        "shallow_water",
+        "deep_water",
+        "mud",
+        "tall_grass",
+        "door_open",
+        "door_locked",
+    ];
+
+    /// Returns the definition of this kind in a `TileRegistry`, or `None` if
+    /// the kind isn't registered.
+    pub fn definition(
+        self,
+        registry: &TileRegistry,
+    ) -> Option<&TileDefinition> {
+        registry.get(self)
+    }
+
+    /// Returns whether or not a tile of this kind can be walked on, regardless
+    /// of the actors standing on it. The unknown kinds can't be walked on.
+    pub fn is_walkable(self, registry: &TileRegistry) -> bool {
+        self.definition(registry).is_some_and(|tile| tile.walkable)
+    }
+
+    /// Returns whether or not the sight goes through a tile of this kind (see
+    /// `Map::field_of_view`). The unknown kinds block the sight.
+    pub fn is_transparent(self, registry: &TileRegistry) -> bool {
+        self.definition(registry)
+            .is_some_and(|tile| tile.transparent)
+    }
+
+    /// Returns the cost for an actor to walk on a tile of this kind, i.e. the
+    /// number of turns it takes (see `MovementCooldown`). The unknown kinds
+    /// cost 0, like the other kinds which can't be walked on.
+    pub fn movement_cost(self, registry: &TileRegistry) -> u32 {
+        self.definition(registry)
+            .map_or(0, |tile| tile.movement_cost)
+    }
+
+    /// Returns whether a tile of this kind has a given tag.
+    pub fn has_tag(self, registry: &TileRegistry, tag: &str) -> bool {
+        self.definition(registry)
+            .is_some_and(|tile| tile.tags.iter().any(|t| t == tag))
+    }
+
+    /// Returns the color multiplied with the sprite of a tile of this kind.
+    pub fn sprite_color(self, registry: &TileRegistry) -> Color {
+        self.definition(registry)
+            .and_then(|tile| tile.tint)
+            .map_or(Color::WHITE, |[r, g, b]| Color::rgb_u8(r, g, b))
+    }
+
+    /// Returns the kind of tile replacing a tile of this kind once damaged,
+    /// or `None` if this kind isn't destructible.
+    pub fn damaged(self, registry: &TileRegistry) -> Option<Self> {
+        self.definition(registry)
+            .filter(|tile| tile.destructible)
+            .and_then(|tile| tile.damaged)
+    }
+
+    /// Returns the kind of tile replacing a tile of this kind once opened,
+    /// or `None` if this kind can't be opened (see `Map::open_door`).
+    pub fn opened(self, registry: &TileRegistry) -> Option<Self> {
+        self.definition(registry).and_then(|tile| tile.opened)
+    }
+
+    /// Returns whether a tile of this kind needs a key to be opened.
+    pub fn is_locked(self, registry: &TileRegistry) -> bool {
+        self.opened(registry).is_some()
+            && self.has_tag(registry, TILE_TAG_LOCKED)
+    }
+
+    /// Returns whether an actor can get through a tile of this kind, by
+    /// walking on it or by opening it first.
+    pub fn is_passable(self, registry: &TileRegistry) -> bool {
+        self.is_walkable(registry) || self.opened(registry).is_some()
+    }
+
+    /// Returns the tile kind represented by a character in text maps (e.g.
+    /// vaults), or `None` if the character doesn't represent any kind.
+    pub const fn from_glyph(glyph: char) -> Option<Self> {
+        match glyph {
+            '.' => Some(Self::GRASS),
+            '*' => Some(Self::GRASS_WITH_FLOWER),
+            'o' => Some(Self::GRASS_WITH_STONE),
+            '%' => Some(Self::GRASS_WITH_STONE_DAMAGED),
+            '#' => Some(Self::WALL),
+            '_' => Some(Self::FLOOR),
+            '+' => Some(Self::DOOR),
+            '\'' => Some(Self::DOOR_OPEN),
+            '~' => Some(Self::SHALLOW_WATER),
+            '=' => Some(Self::DEEP_WATER),
+            ',' => Some(Self::MUD),
+            '"' => Some(Self::TALL_GRASS),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of tile used when carving a corridor from a tile of
+    /// this kind, so corridors match the surrounding terrain.
+    pub const fn corridor_kind(self) -> Self {
+        match self {
+            Self::WALL
+            | Self::FLOOR
+            | Self::DOOR
+            | Self::DOOR_OPEN
+            | Self::DOOR_LOCKED => Self::FLOOR,
+            _ => Self::GRASS,
         }
     }
+
+    /// Returns the sprite index for a given `TileKind`. The index corresponds
+    /// to the location in the tilesheet where the corresponding tile is. The
+    /// unknown kinds use the first sprite.
+    pub fn to_sprite_idx(kind: Self, registry: &TileRegistry) -> usize {
+        kind.definition(registry).map_or(0, |tile| tile.sprite)
+    }
 }
 
 /// Bundle for creating an entity representing a tile.
@@ -77,6 +201,7 @@ impl TileBundle {
     pub fn new(
         map_position: MapPosition,
         tileset: &TilesetTerrain,
+        registry: &TileRegistry,
         tile: Tile,
     ) -> Self {
         let (sprite_x, sprite_y) = map_position.as_sprite_coordinates();
@@ -89,11 +214,14 @@ impl TileBundle {
                     sprite_y,
                     Z_INDEX_TILE,
                 ),
-                sprite: Sprite::default(),
+                sprite: Sprite {
+                    color: tile.kind.sprite_color(registry),
+                    ..Default::default()
+                },
                 texture: tileset.1.clone(),
                 atlas: TextureAtlas {
                     layout: tileset.0.clone(),
-                    index: TileKind::to_sprite_idx(tile.kind),
+                    index: TileKind::to_sprite_idx(tile.kind, registry),
                 },
                 ..Default::default()
             },
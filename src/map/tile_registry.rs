@@ -0,0 +1,456 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::tile::TileKind;
+use crate::prelude::*;
+
+/// Represents the properties of a kind of tile, as defined in the tile
+/// definitions file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TileDefinition {
+    /// The name identifying the tile.
+    pub name: String,
+    /// The index of the tile's sprite in the terrain tileset.
+    pub sprite: usize,
+    /// Whether the tile can be walked on by an actor.
+    pub walkable: bool,
+    /// Whether the tile can be seen through.
+    pub transparent: bool,
+    /// The cost for an actor to walk on the tile.
+    pub movement_cost: u32,
+    /// Whether the tile can be damaged (see `Map::damage_tile`).
+    pub destructible: bool,
+    /// The kind of tile replacing this one once damaged, required for the
+    /// destructible tiles.
+    pub damaged: Option<TileKind>,
+    /// The kind of tile replacing this one once opened, e.g. for doors (see
+    /// `Map::open_door`).
+    pub opened: Option<TileKind>,
+    /// The color multiplied with the sprite, as RGB components, e.g. for
+    /// telling water apart from grass using the same sprite.
+    pub tint: Option<[u8; 3]>,
+    /// The tags deciding how actors interact with the tile (see
+    /// `Actor::can_cross`), e.g. `water` or `locked`.
+    pub tags: Vec<String>,
+    /// The text describing the tile to the player.
+    pub description: String,
+}
+
+/// Lists the kinds of tile, a `TileKind` being the index of its definition.
+///
+/// The tile kinds are defined in a text file, each definition starting with
+/// the tile's name between brackets, followed by one property per line:
+///
+/// ```text
+/// [grass]
+/// sprite = 0
+/// walkable = true
+/// transparent = true
+/// movement_cost = 1
+/// destructible = false
+/// description = A patch of grass.
+/// ```
+///
+/// The optional properties are `damaged` (the name of the tile replacing a
+/// destructible tile once damaged), `opened` (the name of the tile replacing
+/// a door once opened), `tint` (a `#RRGGBB` color multiplied with the sprite)
+/// and `tags` (a comma-separated list).
+///
+/// The built-in kinds (see `TileKind::BUILTIN`) must all be defined, and keep
+/// their ids whatever their order in the file is. The other kinds get the
+/// following ids, in the file's order.
+///
+/// The registry used by the game is loaded from `TILE_DEFINITIONS_FILE` with
+/// the other assets (see `load_tile_definitions`).
+#[derive(Debug, Default, Resource)]
+pub struct TileRegistry {
+    definitions: Vec<TileDefinition>,
+}
+
+/// Represents a tile definition being parsed.
+struct ParsedTile<'a> {
+    /// The section defining the tile.
+    section: &'a IniSection<'a>,
+    definition: TileDefinition,
+}
+
+impl FromStr for TileRegistry {
+    type Err = String;
+
+    /// Parses and validates tile definitions written as text (see
+    /// `parse_sections`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sections = parse_sections(s)?;
+        let mut tiles: Vec<ParsedTile> = vec![];
+
+        for section in &sections {
+            let (name, line_number) = (section.name, section.line_number);
+            if name.is_empty() {
+                return Err(format!("line {line_number}: empty tile name"));
+            }
+            if tiles.iter().any(|tile| tile.definition.name == name) {
+                return Err(format!(
+                    "line {line_number}: duplicate tile '{name}'"
+                ));
+            }
+
+            let mut definition = TileDefinition {
+                name: name.into(),
+                ..Default::default()
+            };
+            for (key, value, line_number) in &section.properties {
+                // the names of other tiles are resolved once all are parsed
+                if *key == "damaged" || *key == "opened" {
+                    continue;
+                }
+                set_property(&mut definition, key, value)
+                    .map_err(|e| format!("line {line_number}: {e}"))?;
+            }
+            tiles.push(ParsedTile {
+                section,
+                definition,
+            });
+        }
+
+        for tile in &tiles {
+            let name = &tile.definition.name;
+            let line_number = tile.section.line_number;
+            if let Some(key) =
+                TILE_PROPERTIES.iter().find(|key| !tile.section.has(key))
+            {
+                return Err(format!(
+                    "line {line_number}: tile '{name}' has no property '{key}'"
+                ));
+            }
+            if tile.definition.walkable && tile.definition.movement_cost == 0 {
+                return Err(format!(
+                    "line {line_number}: tile '{name}' is walkable but has no \
+                     movement cost"
+                ));
+            }
+            if tile.definition.destructible && !tile.section.has("damaged") {
+                return Err(format!(
+                    "line {line_number}: tile '{name}' is destructible but \
+                     has no property 'damaged'"
+                ));
+            }
+        }
+
+        // the built-in kinds come first, in the order of their ids
+        let mut ordered = Vec::with_capacity(tiles.len());
+        for name in TileKind::BUILTIN {
+            let index = tiles
+                .iter()
+                .position(|tile| tile.definition.name == name)
+                .ok_or_else(|| format!("missing tile '{name}'"))?;
+            ordered.push(tiles.remove(index));
+        }
+        ordered.extend(tiles);
+
+        let id = |tile: &ParsedTile, property: &str| {
+            tile.section
+                .get(property)
+                .map(|name| {
+                    ordered
+                        .iter()
+                        .position(|tile| tile.definition.name == name)
+                        .map(TileKind)
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: unknown {property} tile '{name}'",
+                                tile.section.line_number
+                            )
+                        })
+                })
+                .transpose()
+        };
+        let mut definitions = Vec::with_capacity(ordered.len());
+        for tile in &ordered {
+            let mut definition = tile.definition.clone();
+            definition.damaged = id(tile, "damaged")?;
+            definition.opened = id(tile, "opened")?;
+            definitions.push(definition);
+        }
+
+        Ok(Self { definitions })
+    }
+}
+
+/// The properties every tile definition must have.
+const TILE_PROPERTIES: [&str; 6] = [
+    "sprite",
+    "walkable",
+    "transparent",
+    "movement_cost",
+    "destructible",
+    "description",
+];
+
+/// Sets a property of a tile definition from its text value.
+fn set_property(
+    definition: &mut TileDefinition,
+    key: &str,
+    value: &str,
+) -> Result<(), String> {
+    let parse_bool = |value: &str| {
+        value
+            .parse::<bool>()
+            .map_err(|_| format!("invalid value '{value}' for '{key}'"))
+    };
+    let parse_number = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| format!("invalid value '{value}' for '{key}'"))
+    };
+
+    match key {
+        "sprite" => {
+            let sprite = parse_number(value)? as usize;
+            if sprite >= TILESET_TERRAIN_ROWS * TILESET_TERRAIN_COLUMNS {
+                return Err(format!("sprite {sprite} out of the tileset"));
+            }
+            definition.sprite = sprite;
+        }
+        "walkable" => definition.walkable = parse_bool(value)?,
+        "transparent" => definition.transparent = parse_bool(value)?,
+        "movement_cost" => definition.movement_cost = parse_number(value)?,
+        "destructible" => definition.destructible = parse_bool(value)?,
+        "description" => definition.description = value.into(),
+        "tint" => definition.tint = Some(parse_color(value)?),
+        "tags" => {
+            definition.tags = value
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        _ => return Err(format!("unknown property '{key}'")),
+    }
+    Ok(())
+}
+
+/// Parses a `#RRGGBB` color into its RGB components.
+fn parse_color(value: &str) -> Result<[u8; 3], String> {
+    let invalid = || format!("invalid color '{value}'");
+    let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let mut rgb = [0; 3];
+    for (i, component) in rgb.iter_mut().enumerate() {
+        *component = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| invalid())?;
+    }
+    Ok(rgb)
+}
+
+impl TileRegistry {
+    /// Loads and validates the tile definitions of a file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse())
+            .map_err(|e| format!("invalid tiles {}: {e}", path.display()))
+    }
+
+    /// Returns the definition of a tile kind, if it is registered.
+    pub fn get(&self, kind: TileKind) -> Option<&TileDefinition> {
+        self.definitions.get(kind.0)
+    }
+}
+
+#[cfg(test)]
+impl TileRegistry {
+    /// Returns the registry of the shipped tile definitions, for the tests
+    /// using the built-in kinds.
+    pub fn shipped() -> Self {
+        Self::load(&asset_path(TILE_DEFINITIONS_FILE)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = "\
+sprite = 0
+walkable = true
+transparent = true
+movement_cost = 1
+destructible = false
+description = A tile.
+";
+
+    /// Returns the definitions of the built-in tiles, in a given order.
+    fn builtin_definitions(
+        names: impl Iterator<Item = &'static str>,
+    ) -> String {
+        names.fold(String::new(), |text, name| {
+            text + "[" + name + "]\n" + DEFINITION + "\n"
+        })
+    }
+
+    #[test]
+    fn test_builtin_ids() {
+        let text = builtin_definitions(TileKind::BUILTIN.into_iter().rev())
+            + "[lava]\n"
+            + &DEFINITION.replace("walkable = true", "walkable = false");
+        let registry: TileRegistry = text.parse().unwrap();
+
+        for (id, name) in TileKind::BUILTIN.iter().enumerate() {
+            assert_eq!(*name, registry.get(TileKind(id)).unwrap().name);
+        }
+        let lava = registry.get(TileKind(TileKind::BUILTIN.len())).unwrap();
+        assert_eq!("lava", lava.name);
+        assert!(!lava.walkable);
+        assert_eq!("A tile.", lava.description);
+        assert!(registry
+            .get(TileKind(TileKind::BUILTIN.len() + 1))
+            .is_none());
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let parse = |text: &str| text.parse::<TileRegistry>().map(|_| ());
+
+        assert_eq!(
+            Err("missing tile 'grass'".into()),
+            parse(&builtin_definitions(TileKind::BUILTIN.into_iter().skip(1)))
+        );
+        assert_eq!(
+            Err("line 8: duplicate tile 'lava'".into()),
+            parse(&format!("[lava]\n{DEFINITION}[lava]\n"))
+        );
+        assert_eq!(
+            Err("line 1: property outside of a section".into()),
+            parse("sprite = 0")
+        );
+        assert_eq!(
+            Err("line 3: invalid value 'maybe' for 'walkable'".into()),
+            parse("[lava]\nsprite = 0\nwalkable = maybe\n")
+        );
+        assert_eq!(
+            Err("line 2: sprite 99 out of the tileset".into()),
+            parse("[lava]\nsprite = 99\n")
+        );
+        assert_eq!(
+            Err("line 2: unknown property 'color'".into()),
+            parse("[lava]\ncolor = red\n")
+        );
+        assert_eq!(
+            Err("line 1: tile 'lava' has no property 'description'".into()),
+            parse(&format!(
+                "[lava]\n{}",
+                DEFINITION.replace("description", "#")
+            ))
+        );
+
+        let destructible =
+            DEFINITION.replace("destructible = false", "destructible = true");
+        assert_eq!(
+            Err("line 1: tile 'lava' is destructible but has no property \
+                 'damaged'"
+                .into()),
+            parse(&format!("[lava]\n{destructible}"))
+        );
+        let builtin = builtin_definitions(TileKind::BUILTIN.into_iter());
+        assert_eq!(
+            Err(format!(
+                "line {}: unknown damaged tile 'ice'",
+                builtin.lines().count() + 1
+            )),
+            parse(&format!("{builtin}[lava]\n{destructible}damaged = ice\n"))
+        );
+        assert_eq!(
+            Err(format!(
+                "line {}: unknown opened tile 'gate'",
+                builtin.lines().count() + 1
+            )),
+            parse(&format!("{builtin}[lava]\n{DEFINITION}opened = gate\n"))
+        );
+    }
+
+    #[test]
+    fn test_damaged_tiles() {
+        let builtin = builtin_definitions(TileKind::BUILTIN.into_iter());
+        let text = builtin
+            + "[lava]\n"
+            + &DEFINITION
+                .replace("destructible = false", "destructible = true")
+            + "damaged = grass\nopened = wall\n";
+        let registry: TileRegistry = text.parse().unwrap();
+
+        let lava = registry.get(TileKind(TileKind::BUILTIN.len())).unwrap();
+        assert_eq!(Some(TileKind::GRASS), lava.damaged);
+        assert_eq!(Some(TileKind::WALL), lava.opened);
+        assert_eq!(None, registry.get(TileKind::GRASS).unwrap().damaged);
+    }
+
+    #[test]
+    fn test_optional_properties() {
+        let builtin = builtin_definitions(TileKind::BUILTIN.into_iter());
+        let text = builtin
+            + "[lava]\n"
+            + DEFINITION
+            + "tint = #Ff8000\ntags = hot, liquid,\n";
+        let registry: TileRegistry = text.parse().unwrap();
+
+        let lava = registry.get(TileKind(TileKind::BUILTIN.len())).unwrap();
+        assert_eq!(Some([255, 128, 0]), lava.tint);
+        assert_eq!(vec!["hot", "liquid"], lava.tags);
+        let grass = registry.get(TileKind::GRASS).unwrap();
+        assert_eq!(None, grass.tint);
+        assert!(grass.tags.is_empty());
+
+        let parse = |text: &str| text.parse::<TileRegistry>().map(|_| ());
+        assert_eq!(
+            Err("line 2: invalid color 'ff8000'".into()),
+            parse("[lava]\ntint = ff8000\n")
+        );
+        assert_eq!(
+            Err("line 2: invalid color '#ff80zz'".into()),
+            parse("[lava]\ntint = #ff80zz\n")
+        );
+        assert_eq!(
+            Err("line 1: tile 'lava' is walkable but has no movement cost"
+                .into()),
+            parse(&format!(
+                "[lava]\n{}",
+                DEFINITION.replace("movement_cost = 1", "movement_cost = 0")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_load_definitions_file() {
+        let registry =
+            TileRegistry::load(&asset_path(TILE_DEFINITIONS_FILE)).unwrap();
+        assert!(registry.get(TileKind::GRASS).unwrap().walkable);
+        assert!(!registry.get(TileKind::WALL).unwrap().walkable);
+        let mud = registry.get(TileKind::MUD).unwrap();
+        assert!(mud.walkable && mud.movement_cost > 1);
+        assert!(registry
+            .get(TileKind::DEEP_WATER)
+            .unwrap()
+            .tags
+            .contains(&TILE_TAG_WATER.to_string()));
+
+        let error = TileRegistry::load(Path::new("missing.txt")).unwrap_err();
+        assert!(error.starts_with("invalid tiles missing.txt"));
+    }
+
+    #[test]
+    fn test_unknown_kind_lookups() {
+        let registry = TileRegistry::shipped();
+        let unknown = TileKind(usize::MAX);
+        assert!(unknown.definition(&registry).is_none());
+        assert!(!unknown.is_walkable(&registry));
+        assert!(!unknown.is_transparent(&registry));
+        assert_eq!(0, unknown.movement_cost(&registry));
+        assert_eq!(None, unknown.damaged(&registry));
+        assert_eq!(0, TileKind::to_sprite_idx(unknown, &registry));
+    }
+}
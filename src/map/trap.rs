@@ -0,0 +1,412 @@
+use crate::prelude::*;
+
+/// Represents a kind of trap, triggered when an actor steps on it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrapKind {
+    /// Wounds the actor for `TRAP_SPIKE_DAMAGE` damage, who also loses
+    /// `TRAP_SPIKE_TURNS` turns.
+    Spike,
+    /// Holds the actor in place for `TRAP_SNARE_TURNS` turns.
+    Snare,
+    /// Moves the actor to a random position of the map, reachable from the
+    /// trap without going through a locked door.
+    Teleport,
+    /// Draws all blobs of the map one step towards the trap.
+    Alarm,
+}
+
+impl TrapKind {
+    pub const ALL: [Self; 4] =
+        [Self::Spike, Self::Snare, Self::Teleport, Self::Alarm];
+
+    /// Returns the color multiplied with the trap's sprite.
+    pub const fn sprite_color(self) -> Color {
+        match self {
+            Self::Spike => TRAP_SPIKE_COLOR,
+            Self::Snare => TRAP_SNARE_COLOR,
+            Self::Teleport => TRAP_TELEPORT_COLOR,
+            Self::Alarm => TRAP_ALARM_COLOR,
+        }
+    }
+}
+
+/// Represents a trap set on a map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MapTrap {
+    /// The kind of the trap.
+    pub kind: TrapKind,
+    /// The position of the trap on the map.
+    pub position: MapPosition,
+    /// Whether the trap is still unknown to the player. A trap is revealed
+    /// once detected (see `Map::search_traps`) or triggered.
+    pub hidden: bool,
+}
+
+/// Marker component for the entities representing the traps of the current
+/// map.
+#[derive(Component)]
+pub struct Trap;
+
+impl Map {
+    /// Returns the trap set at a given position, if any.
+    pub fn trap_at(&self, pos: &MapPosition) -> Option<&MapTrap> {
+        self.traps.iter().find(|trap| trap.position == *pos)
+    }
+
+    /// Triggers the trap set at a given position, if any: the trap is revealed
+    /// and queued in `Map::triggered_traps` until its effect is resolved (see
+    /// `trigger_traps`).
+    pub fn trigger_trap(&mut self, pos: &MapPosition) {
+        if let Some(trap) = self.traps.iter_mut().find(|t| t.position == *pos) {
+            trap.hidden = false;
+            self.triggered_traps.push(*trap);
+        }
+    }
+
+    /// Sets up to `count` hidden traps of random kinds on free walkable tiles,
+    /// i.e. neither the entrance, an exit nor a tile with an item or a trap.
+    pub fn set_traps(
+        &mut self,
+        registry: &TileRegistry,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        let mut free: Vec<_> = (0..self.tiles.len())
+            .filter(|&i| self.tiles[i].kind.is_walkable(registry))
+            .filter(|&i| !self.is_reserved(i))
+            .map(|i| MapPosition::new(i % self.width, i / self.width))
+            .filter(|pos| {
+                self.item_at(pos).is_none() && self.trap_at(pos).is_none()
+            })
+            .collect();
+        free.shuffle(rng);
+
+        for position in free.into_iter().take(count) {
+            self.traps.push(MapTrap {
+                kind: *TrapKind::ALL.choose(rng).unwrap(),
+                position,
+                hidden: true,
+            });
+        }
+    }
+
+    /// Searches for the hidden traps around a given position, each trap within
+    /// `TRAP_SEARCH_RADIUS` tiles (in Manhattan distance) being revealed with
+    /// a given chance.
+    ///
+    /// # Returns
+    ///
+    /// The number of traps revealed.
+    pub fn search_traps(
+        &mut self,
+        pos: &MapPosition,
+        chance: f64,
+        rng: &mut impl Rng,
+    ) -> usize {
+        let chance = chance.clamp(0.0, 1.0);
+        let mut revealed = 0;
+        for trap in &mut self.traps {
+            let distance = trap.position.x.abs_diff(pos.x)
+                + trap.position.y.abs_diff(pos.y);
+            if trap.hidden
+                && distance <= TRAP_SEARCH_RADIUS
+                && rng.gen_bool(chance)
+            {
+                trap.hidden = false;
+                revealed += 1;
+            }
+        }
+        revealed
+    }
+
+    /// Returns a random destination for an actor stepping on a teleport trap:
+    /// a free tile the actor can cross, with no trap, and reachable from the
+    /// trap without going through a locked door.
+    pub fn teleport_destination(
+        &self,
+        registry: &TileRegistry,
+        actor: Actor,
+        from: &MapPosition,
+        rng: &mut impl Rng,
+    ) -> Option<MapPosition> {
+        let start = self.as_tile_index(from).ok()?;
+        let reached = self.reachable_tiles(registry, start, |i| {
+            !self.tiles[i].kind.is_locked(registry)
+        });
+        let destinations: Vec<_> = (0..self.tiles.len())
+            .filter(|&i| i != start && reached[i])
+            .filter(|&i| {
+                self.tiles[i].is_walkable(registry)
+                    && actor.can_cross(registry, self.tiles[i].kind)
+            })
+            .map(|i| MapPosition::new(i % self.width, i / self.width))
+            .filter(|pos| self.trap_at(pos).is_none())
+            .collect();
+        destinations.choose(rng).copied()
+    }
+}
+
+/// Returns the chance to detect a trap when searching, given the number of
+/// turns spent searching in a row.
+pub fn search_chance(turns: u32) -> f64 {
+    (TRAP_SEARCH_CHANCE_PER_TURN * f64::from(turns)).min(1.0)
+}
+
+/// Bundle for creating an entity representing a trap. The sprite is hidden
+/// while the trap is hidden.
+#[derive(Bundle)]
+pub struct TrapBundle {
+    /// Marker component for the trap.
+    pub trap: Trap,
+    /// Sprite bundle for rendering the trap.
+    pub sprite: SpriteSheetBundle,
+    /// The position on the map where the trap is.
+    pub map_position: MapPosition,
+}
+
+impl TrapBundle {
+    /// Creates a new entity using the `TrapBundle` struct components.
+    pub fn new(trap: &MapTrap, tileset: &TilesetTerrain) -> Self {
+        let (sprite_x, sprite_y) = trap.position.as_sprite_coordinates();
+        Self {
+            trap: Trap,
+            sprite: SpriteSheetBundle {
+                transform: Transform::from_xyz(
+                    sprite_x,
+                    sprite_y,
+                    Z_INDEX_ITEM,
+                ),
+                sprite: Sprite {
+                    color: trap.kind.sprite_color(),
+                    ..Default::default()
+                },
+                texture: tileset.1.clone(),
+                atlas: TextureAtlas {
+                    layout: tileset.0.clone(),
+                    index: TILESET_TERRAIN_IDX_TRAP,
+                },
+                visibility: trap_visibility(trap),
+                ..Default::default()
+            },
+            map_position: trap.position,
+        }
+    }
+}
+
+/// Returns the visibility of a trap's sprite.
+const fn trap_visibility(trap: &MapTrap) -> Visibility {
+    if trap.hidden {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    }
+}
+
+/// Shows the sprites of the traps revealed on the current map, unless they
+/// are set on unseen tiles.
+pub fn update_trap_sprites(
+    q_map: Query<&Map, With<OnDisplay>>,
+    mut q_traps: Query<(&mut Visibility, &MapPosition), With<Trap>>,
+    fov: Res<FieldOfView>,
+) {
+    let Ok(map) = q_map.get_single() else {
+        return;
+    };
+
+    for (mut visibility, pos) in &mut q_traps {
+        if let Some(trap) = map.trap_at(pos) {
+            *visibility = match map.tile_visibility(pos, &fov) {
+                TileVisibility::Unseen => Visibility::Hidden,
+                _ => trap_visibility(trap),
+            };
+        }
+    }
+}
+
+/// Resolves the effects of the traps triggered on the current map (see
+/// `Map::trigger_trap`) on the actors standing on them. The mobs killed by a
+/// trap are despawned and removed from the map, and the `CauseOfDeath` is
+/// recorded when the player is killed.
+pub fn trigger_traps(
+    mut commands: Commands,
+    mut q_map: Query<&mut Map, With<OnDisplay>>,
+    mut q_actors: Query<
+        (
+            Entity,
+            &mut MapPosition,
+            &Actor,
+            &mut MovementCooldown,
+            &mut Health,
+        ),
+        With<OnDisplay>,
+    >,
+    registry: Res<TileRegistry>,
+    mut rng: ResMut<RunRng>,
+) {
+    let Ok(mut map) = q_map.get_single_mut() else {
+        return;
+    };
+
+    for trap in std::mem::take(&mut map.triggered_traps) {
+        if trap.kind == TrapKind::Alarm {
+            for (_, mut position, actor, mut cooldown, _) in &mut q_actors {
+                if actor.kind == ActorKind::Blob {
+                    let pos_old = *position;
+                    move_along_path(
+                        &registry,
+                        &trap.position,
+                        &mut position,
+                        &mut map,
+                        actor.can_dig(),
+                    );
+                    if *position != pos_old {
+                        *cooldown = MovementCooldown::after_moving_to(
+                            &map, &registry, &position,
+                        );
+                    }
+                }
+            }
+            continue;
+        }
+
+        let Some((entity, mut position, actor, mut cooldown, mut health)) =
+            q_actors
+                .iter_mut()
+                .find(|(_, position, _, _, _)| **position == trap.position)
+        else {
+            continue;
+        };
+        match trap.kind {
+            TrapKind::Spike => {
+                cooldown.0 += TRAP_SPIKE_TURNS;
+                health.take_damage(TRAP_SPIKE_DAMAGE);
+                if health.is_dead() && actor.is_player() {
+                    commands.insert_resource(CauseOfDeath(
+                        "Killed by a spike trap".to_string(),
+                    ));
+                } else if health.is_dead() {
+                    if let Ok(index) = map.as_tile_index(&position) {
+                        map.tiles[index].actor = None;
+                    }
+                    commands.entity(entity).despawn();
+                }
+            }
+            TrapKind::Snare => cooldown.0 += TRAP_SNARE_TURNS,
+            TrapKind::Teleport => {
+                if let Some(pos) = map.teleport_destination(
+                    &registry,
+                    *actor,
+                    &position,
+                    &mut rng.ai,
+                ) {
+                    map.move_actor(&mut position, &pos).unwrap();
+                }
+            }
+            TrapKind::Alarm => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn create_trapped_map() -> Map {
+        let mut map = Map {
+            width: 5,
+            height: 1,
+            tiles: vec![Tile::default(); 5],
+            ..Default::default()
+        };
+        map.tiles[0].actor = Some(Actor::new(ActorKind::Player));
+        for (x, kind) in [(1, TrapKind::Snare), (4, TrapKind::Alarm)] {
+            map.traps.push(MapTrap {
+                kind,
+                position: MapPosition::new(x, 0),
+                hidden: true,
+            });
+        }
+        map
+    }
+
+    #[test]
+    fn test_move_actor_triggers_traps() {
+        let mut map = create_trapped_map();
+        let mut pos = MapPosition::new(0, 0);
+
+        map.move_actor(&mut pos, &MapPosition::new(1, 0)).unwrap();
+        assert!(!map.traps[0].hidden);
+        assert_eq!(vec![map.traps[0]], map.triggered_traps);
+
+        map.triggered_traps.clear();
+        map.move_actor(&mut pos, &MapPosition::new(2, 0)).unwrap();
+        assert!(map.triggered_traps.is_empty());
+        assert!(map.traps[1].hidden);
+    }
+
+    #[test]
+    fn test_search_traps() {
+        let mut map = create_trapped_map();
+        let mut rng = StdRng::seed_from_u64(0);
+        let pos = MapPosition::new(0, 0);
+
+        assert_eq!(0, map.search_traps(&pos, 0.0, &mut rng));
+        assert_eq!(1, map.search_traps(&pos, 1.0, &mut rng));
+        assert!(!map.traps[0].hidden);
+        // the alarm is too far away
+        assert!(map.traps[1].hidden);
+        assert_eq!(0, map.search_traps(&pos, 1.0, &mut rng));
+
+        assert!(search_chance(0).abs() < f64::EPSILON);
+        assert!(search_chance(1) < search_chance(2));
+        assert!((1.0 - search_chance(100)).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_set_traps() {
+        let registry = TileRegistry::shipped();
+        let mut map = create_trapped_map();
+        map.traps.clear();
+        map.entrance = Some(MapPosition::new(0, 0));
+        let mut rng = StdRng::seed_from_u64(0);
+
+        map.set_traps(&registry, 10, &mut rng);
+        assert_eq!(4, map.traps.len());
+        assert!(map.traps.iter().all(|trap| trap.hidden));
+        assert!(map.trap_at(&MapPosition::new(0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_teleport_destination() {
+        let registry = TileRegistry::shipped();
+        let mut map = Map::parse_rows(&["B~.+."]);
+        map.tiles[3].kind = TileKind::DOOR_LOCKED;
+        let blob = Actor::new(ActorKind::Blob);
+        let rabbit = Actor::new(ActorKind::Rabbit);
+        let from = MapPosition::new(0, 0);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // the water can't be crossed by blobs, nor the locked door by anyone
+        for _ in 0..10 {
+            assert_eq!(
+                Some(MapPosition::new(2, 0)),
+                map.teleport_destination(&registry, blob, &from, &mut rng)
+            );
+            let pos = map
+                .teleport_destination(&registry, rabbit, &from, &mut rng)
+                .unwrap();
+            assert!(pos.x == 1 || pos.x == 2);
+        }
+
+        map.traps.push(MapTrap {
+            kind: TrapKind::Spike,
+            position: MapPosition::new(2, 0),
+            hidden: true,
+        });
+        assert_eq!(
+            None,
+            map.teleport_destination(&registry, blob, &from, &mut rng)
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::tile::TileKind;
+use crate::prelude::*;
+
+/// Represents a hand-designed room template, stamped as is into generated
+/// maps.
+///
+/// Vaults are written as text, one character (glyph) per tile:
+///
+/// | Glyph | Tile                         |
+/// |-------|------------------------------|
+/// | `.`   | Grass                        |
+/// | `*`   | Grass with flower            |
+/// | `o`   | Grass with stone             |
+/// | `%`   | Grass with damaged stone     |
+/// | `#`   | Wall                         |
+/// | `_`   | Floor                        |
+/// | `+`   | Closed door                  |
+/// | `'`   | Open door                    |
+/// | `~`   | Shallow water                |
+/// | `=`   | Deep water                   |
+/// | `,`   | Mud                          |
+/// | `"`   | Tall grass                   |
+/// | `m`   | Floor, where a mob spawns    |
+/// | `i`   | Floor, where an item spawns  |
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vault {
+    /// The name of the vault, i.e. its file name.
+    pub name: String,
+    /// The vault's width.
+    pub width: usize,
+    /// The vault's height.
+    pub height: usize,
+    /// The tiles of the vault, stored row by row.
+    pub tiles: Vec<TileKind>,
+    /// The mob spawns, relative to the vault's top left corner.
+    pub mob_spawns: Vec<MapPosition>,
+    /// The item spawns, relative to the vault's top left corner.
+    pub item_spawns: Vec<MapPosition>,
+}
+
+impl FromStr for Vault {
+    type Err = String;
+
+    /// Parses a vault from its text representation (see `parse_tile_grid`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mob_spawns = vec![];
+        let mut item_spawns = vec![];
+        let (width, height, tiles) =
+            parse_tile_grid(s, |x, y, glyph| match glyph {
+                'm' => {
+                    mob_spawns.push(MapPosition::new(x, y));
+                    Some(TileKind::FLOOR)
+                }
+                'i' => {
+                    item_spawns.push(MapPosition::new(x, y));
+                    Some(TileKind::FLOOR)
+                }
+                _ => TileKind::from_glyph(glyph),
+            })?;
+
+        Ok(Self {
+            name: String::new(),
+            width,
+            height,
+            tiles,
+            mob_spawns,
+            item_spawns,
+        })
+    }
+}
+
+/// Parses a grid of tiles written as text, one character (glyph) per tile.
+/// The trailing empty lines are ignored, and all rows must have the same
+/// length.
+///
+/// # Arguments
+///
+/// - `s`: The text to parse.
+/// - `glyph_kind`: Returns the tile kind of a glyph found at given
+///   coordinates, or `None` if the glyph is unknown.
+///
+/// # Returns
+///
+/// The width, the height and the tiles (stored row by row) of the grid, or
+/// an error pointing at the faulty line and column.
+pub fn parse_tile_grid(
+    s: &str,
+    mut glyph_kind: impl FnMut(usize, usize, char) -> Option<TileKind>,
+) -> Result<(usize, usize, Vec<TileKind>), String> {
+    let rows: Vec<&str> = s.trim_end().lines().collect();
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    if width == 0 {
+        return Err("grid is empty".into());
+    }
+
+    let mut tiles = Vec::with_capacity(width * rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        let columns = row.chars().count();
+        if columns != width {
+            return Err(format!(
+                "line {}: expected {width} columns, found {columns}",
+                y + 1
+            ));
+        }
+
+        for (x, glyph) in row.chars().enumerate() {
+            let kind = glyph_kind(x, y, glyph).ok_or_else(|| {
+                format!(
+                    "line {}, column {}: unknown glyph '{glyph}'",
+                    y + 1,
+                    x + 1
+                )
+            })?;
+            tiles.push(kind);
+        }
+    }
+
+    Ok((width, rows.len(), tiles))
+}
+
+/// Lists the vaults available for being stamped into generated maps.
+#[derive(Default, Resource)]
+pub struct VaultLibrary(pub Vec<Vault>);
+
+impl VaultLibrary {
+    /// Loads the vault files (see `VAULT_FILE_EXTENSION`) of a folder, sorted
+    /// by name. Invalid vaults are reported and left out, and a missing
+    /// folder gives an empty library.
+    pub fn load(folder: &Path) -> Self {
+        let entries = match fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("no vaults loaded from {}: {e}", folder.display());
+                return Self::default();
+            }
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == VAULT_FILE_EXTENSION)
+            })
+            .collect();
+        paths.sort();
+
+        let mut vaults = vec![];
+        for path in paths {
+            let vault = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| text.parse::<Vault>());
+
+            match vault {
+                Ok(mut vault) => {
+                    if let Some(stem) = path.file_stem() {
+                        vault.name = stem.to_string_lossy().into_owned();
+                    }
+                    vaults.push(vault);
+                }
+                Err(e) => warn!("invalid vault {}: {e}", path.display()),
+            }
+        }
+
+        Self(vaults)
+    }
+}
+
+impl Map {
+    /// Stamps up to `count` vaults, picked randomly, into the map. The vaults
+    /// never overlap each other nor the map borders, and a vault is skipped
+    /// if there is no room left for it. The vaults' spawns are added to the
+    /// map's spawns.
+    ///
+    /// # Returns
+    ///
+    /// The areas where the vaults were stamped.
+    pub fn stamp_vaults(
+        &mut self,
+        vaults: &[Vault],
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<MapArea> {
+        let mut stamped: Vec<MapArea> = vec![];
+        if vaults.is_empty() {
+            return stamped;
+        }
+
+        for _ in 0..count {
+            let vault = vaults.choose(rng).unwrap();
+            if vault.width + 2 > self.width || vault.height + 2 > self.height {
+                continue;
+            }
+
+            let candidates: Vec<_> = (1..self.height - vault.height)
+                .flat_map(|y| {
+                    (1..self.width - vault.width).map(move |x| (x, y))
+                })
+                .map(|(x, y)| MapArea {
+                    x,
+                    y,
+                    width: vault.width,
+                    height: vault.height,
+                })
+                .filter(|area| !stamped.iter().any(|s| s.intersects(area)))
+                .collect();
+
+            if let Some(area) = candidates.choose(rng) {
+                self.stamp_vault(vault, area);
+                stamped.push(*area);
+            }
+        }
+        stamped
+    }
+
+    /// Copies the vault's tiles and spawns into a given area of the map.
+    fn stamp_vault(&mut self, vault: &Vault, area: &MapArea) {
+        for (index, kind) in vault.tiles.iter().enumerate() {
+            let x = area.x + index % vault.width;
+            let y = area.y + index / vault.width;
+            self.tiles[x + y * self.width].kind = *kind;
+        }
+
+        let offset = |pos: &MapPosition| {
+            MapPosition::new(area.x + pos.x, area.y + pos.y)
+        };
+        self.mob_spawns.extend(vault.mob_spawns.iter().map(offset));
+        self.item_spawns
+            .extend(vault.item_spawns.iter().map(offset));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tile::Tile;
+    use super::*;
+    use rand::rngs::StdRng;
+
+    const SHRINE: &str = "\
+#####
+#_m_#
+#_i_+
+#####
+";
+
+    fn create_grass_map(width: usize, height: usize) -> Map {
+        Map {
+            width,
+            height,
+            tiles: vec![Tile::default(); width * height],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_vault() {
+        let vault: Vault = SHRINE.parse().unwrap();
+
+        assert_eq!((5, 4), (vault.width, vault.height));
+        assert_eq!(20, vault.tiles.len());
+        assert_eq!(TileKind::WALL, vault.tiles[0]);
+        assert_eq!(TileKind::FLOOR, vault.tiles[7]);
+        assert_eq!(TileKind::DOOR, vault.tiles[14]);
+        assert_eq!(vec![MapPosition::new(2, 1)], vault.mob_spawns);
+        assert_eq!(vec![MapPosition::new(2, 2)], vault.item_spawns);
+    }
+
+    #[test]
+    fn test_parse_vault_errors() {
+        assert_eq!(
+            Err("line 2, column 2: unknown glyph 'q'".into()),
+            "###\n#q#\n".parse::<Vault>().map(|_| ())
+        );
+        assert_eq!(
+            Err("line 3: expected 3 columns, found 2".into()),
+            "###\n#_#\n##\n".parse::<Vault>().map(|_| ())
+        );
+        assert!("".parse::<Vault>().is_err());
+        assert!("\r\n\n".parse::<Vault>().is_err());
+        assert!("#_#\r\n###\r\n".parse::<Vault>().is_ok());
+    }
+
+    #[test]
+    fn test_stamp_vaults() {
+        let vaults: [Vault; 1] = [SHRINE.parse().unwrap()];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..50 {
+            let mut map = create_grass_map(20, 12);
+            let areas = map.stamp_vaults(&vaults, 3, &mut rng);
+            assert!(!areas.is_empty());
+
+            for (i, area) in areas.iter().enumerate() {
+                assert!(area.x >= 1 && area.x + area.width < map.width);
+                assert!(area.y >= 1 && area.y + area.height < map.height);
+                assert!(areas[i + 1..].iter().all(|a| !a.intersects(area)));
+
+                let index = (area.x + 1) + (area.y + 1) * map.width;
+                assert_eq!(TileKind::FLOOR, map.tiles[index].kind);
+            }
+
+            assert_eq!(areas.len(), map.mob_spawns.len());
+            assert_eq!(areas.len(), map.item_spawns.len());
+            for spawn in map.mob_spawns.iter().chain(&map.item_spawns) {
+                let index = map.as_tile_index(spawn).unwrap();
+                assert_eq!(TileKind::FLOOR, map.tiles[index].kind);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stamp_vaults_without_room() {
+        let vaults: [Vault; 1] = [SHRINE.parse().unwrap()];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut map = create_grass_map(6, 6);
+        assert!(map.stamp_vaults(&vaults, 1, &mut rng).is_empty());
+
+        let mut map = create_grass_map(7, 6);
+        assert_eq!(1, map.stamp_vaults(&vaults, 2, &mut rng).len());
+        assert_eq!(1, map.mob_spawns.len());
+    }
+
+    #[test]
+    fn test_vaults_in_generated_maps() {
+        let registry = TileRegistry::shipped();
+        let vaults: [Vault; 1] = [SHRINE.parse().unwrap()];
+        let entry = MapGeneratorEntry::new("perlin", PerlinNoiseGenerator)
+            .with_vaults(1);
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let map = entry.generate(
+                &registry, MAP_WIDTH, MAP_HEIGHT, None, &vaults, &mut rng,
+            );
+
+            assert_eq!(1, map.walkable_regions(&registry).len());
+            assert_eq!(1, map.mob_spawns.len());
+            let index = map.as_tile_index(&map.mob_spawns[0]).unwrap();
+            assert!(map.tiles[index].is_walkable(&registry));
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+
+use super::tile::{Tile, TileKind};
+use crate::prelude::*;
+
+/// The directions between two adjacent tiles: left, right, up and down.
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Generates maps with the Wave Function Collapse algorithm, reproducing the
+/// local patterns of a sample map.
+///
+/// The adjacency rules (which tile kinds can be next to each other, in each
+/// direction) and the tile kinds' frequencies are learned from the sample.
+/// The generated map starts with every kind possible on every tile, then the
+/// tiles with the fewest possibilities are collapsed one by one to a single
+/// kind, the rules being propagated to the other tiles after each choice.
+/// When a tile ends up with no possible kind, the last choices are undone
+/// (backtracking).
+#[derive(Clone)]
+pub struct WaveFunctionCollapse {
+    /// The tile kinds found in the sample.
+    kinds: Vec<TileKind>,
+    /// The number of occurrences of each tile kind in the sample.
+    weights: Vec<u32>,
+    /// For each tile kind and direction, the set (as a bit mask over `kinds`)
+    /// of tile kinds allowed next to it.
+    rules: Vec<[u64; 4]>,
+    /// The maximum number of choices undone before starting over.
+    pub max_backtracks: usize,
+}
+
+impl FromStr for WaveFunctionCollapse {
+    type Err = String;
+
+    /// Learns the rules from a sample map written as text (see
+    /// `parse_tile_grid`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height, sample) =
+            parse_tile_grid(s, |_, _, glyph| TileKind::from_glyph(glyph))?;
+
+        let mut kinds: Vec<TileKind> = vec![];
+        let mut weights = vec![];
+        let mut sample_ids = Vec::with_capacity(sample.len());
+        for kind in sample {
+            let id =
+                kinds.iter().position(|k| *k == kind).unwrap_or_else(|| {
+                    kinds.push(kind);
+                    weights.push(0);
+                    kinds.len() - 1
+                });
+            weights[id] += 1;
+            sample_ids.push(id);
+        }
+        if kinds.len() > 64 {
+            return Err("sample has more than 64 tile kinds".into());
+        }
+
+        let mut rules = vec![[0; 4]; kinds.len()];
+        for (index, &id) in sample_ids.iter().enumerate() {
+            for (direction, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+                if let Some(neighbor) =
+                    neighbor_index(index, dx, dy, width, height)
+                {
+                    rules[id][direction] |= 1 << sample_ids[neighbor];
+                }
+            }
+        }
+
+        Ok(Self {
+            kinds,
+            weights,
+            rules,
+            max_backtracks: WFC_MAX_BACKTRACKS,
+        })
+    }
+}
+
+impl MapGenerator for WaveFunctionCollapse {
+    fn generate(
+        &self,
+        _registry: &TileRegistry,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Map {
+        let ids = (0..WFC_MAX_ATTEMPTS)
+            .find_map(|_| self.collapse(width, height, rng))
+            .unwrap_or_else(|| {
+                // the rules are too strict, use the most common kind
+                let most_common = (0..self.kinds.len())
+                    .max_by_key(|&id| self.weights[id])
+                    .unwrap_or_default();
+                vec![most_common; width * height]
+            });
+
+        Map {
+            width,
+            height,
+            tiles: ids
+                .into_iter()
+                .map(|id| {
+                    Tile::from_kind(
+                        self.kinds.get(id).copied().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Represents a choice made while collapsing the wave, kept for undoing it.
+struct Decision {
+    /// The wave before the choice.
+    wave: Vec<u64>,
+    /// The tile which was collapsed.
+    index: usize,
+    /// The tile kind chosen for the tile.
+    id: usize,
+}
+
+impl WaveFunctionCollapse {
+    /// Learns the rules from the sample map of a file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse())
+            .map_err(|e| format!("invalid sample {}: {e}", path.display()))
+    }
+
+    /// Collapses a wave of dimensions `width`x`height`.
+    ///
+    /// # Returns
+    ///
+    /// The tile kind (as an index in `kinds`) of every tile, stored row by
+    /// row, or `None` if too many choices had to be undone.
+    fn collapse(
+        &self,
+        width: usize,
+        height: usize,
+        rng: &mut StdRng,
+    ) -> Option<Vec<usize>> {
+        let all_kinds = u64::MAX >> (64 - self.kinds.len().max(1));
+        let mut wave = vec![all_kinds; width * height];
+        let mut decisions: Vec<Decision> = vec![];
+        let mut backtracks = 0;
+
+        while let Some(index) = Self::lowest_entropy(&wave, rng) {
+            let id = self.choose_kind(wave[index], rng);
+            decisions.push(Decision {
+                wave: wave.clone(),
+                index,
+                id,
+            });
+            wave[index] = 1 << id;
+            let mut changed = vec![index];
+
+            // undo the choices until the rules can be satisfied again
+            while !self.propagate(&mut wave, &mut changed, width, height) {
+                backtracks += 1;
+                if backtracks > self.max_backtracks {
+                    return None;
+                }
+                let decision = decisions.pop()?;
+                wave = decision.wave;
+                wave[decision.index] &= !(1 << decision.id);
+                changed = vec![decision.index];
+            }
+        }
+
+        Some(wave.iter().map(|w| w.trailing_zeros() as usize).collect())
+    }
+
+    /// Returns the tile with the fewest possible kinds among the tiles which
+    /// aren't collapsed yet, ties being broken randomly. Returns `None` if
+    /// all tiles are collapsed.
+    fn lowest_entropy(wave: &[u64], rng: &mut StdRng) -> Option<usize> {
+        let entropy = wave
+            .iter()
+            .map(|w| w.count_ones())
+            .filter(|&count| count > 1)
+            .min()?;
+
+        let candidates: Vec<_> = (0..wave.len())
+            .filter(|&index| wave[index].count_ones() == entropy)
+            .collect();
+        candidates.choose(rng).copied()
+    }
+
+    /// Chooses randomly a tile kind among the possible ones, according to
+    /// their frequency in the sample.
+    fn choose_kind(&self, possible: u64, rng: &mut StdRng) -> usize {
+        let ids: Vec<_> = (0..self.kinds.len())
+            .filter(|id| possible & (1 << id) != 0)
+            .collect();
+        *ids.choose_weighted(rng, |&id| self.weights[id]).unwrap()
+    }
+
+    /// Removes the tile kinds breaking the rules from the neighbors of the
+    /// changed tiles, repeatedly.
+    ///
+    /// # Returns
+    ///
+    /// `false` if a tile has no possible kind left (a contradiction).
+    fn propagate(
+        &self,
+        wave: &mut [u64],
+        changed: &mut Vec<usize>,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        while let Some(index) = changed.pop() {
+            if wave[index] == 0 {
+                return false;
+            }
+
+            for (direction, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+                let Some(neighbor) =
+                    neighbor_index(index, dx, dy, width, height)
+                else {
+                    continue;
+                };
+
+                let allowed = (0..self.kinds.len())
+                    .filter(|id| wave[index] & (1 << id) != 0)
+                    .fold(0, |acc, id| acc | self.rules[id][direction]);
+
+                let possible = wave[neighbor] & allowed;
+                if possible != wave[neighbor] {
+                    wave[neighbor] = possible;
+                    if possible == 0 {
+                        return false;
+                    }
+                    changed.push(neighbor);
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns whether a tile kind can be placed next to another one, in a
+    /// given direction (see `DIRECTIONS`).
+    #[cfg(test)]
+    fn allows(
+        &self,
+        kind: TileKind,
+        direction: usize,
+        other: TileKind,
+    ) -> bool {
+        let id = |k| self.kinds.iter().position(|&kind| kind == k);
+        match (id(kind), id(other)) {
+            (Some(a), Some(b)) => self.rules[a][direction] & (1 << b) != 0,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the flat index of the tile at a given offset from another one, or
+/// `None` if it is out of the grid.
+fn neighbor_index(
+    index: usize,
+    dx: isize,
+    dy: isize,
+    width: usize,
+    height: usize,
+) -> Option<usize> {
+    let x = (index % width).checked_add_signed(dx)?;
+    let y = (index / width).checked_add_signed(dy)?;
+    (x < width && y < height).then_some(x + y * width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the index of the direction opposite to a given one.
+    const fn opposite(direction: usize) -> usize {
+        direction ^ 1
+    }
+
+    const RIVER: &str = "\
+..o..
+.o%o.
+..o..
+#####
+__+__
+";
+
+    #[test]
+    fn test_learn_rules() {
+        let wfc: WaveFunctionCollapse = RIVER.parse().unwrap();
+
+        assert_eq!(6, wfc.kinds.len());
+        assert!(wfc.allows(TileKind::GRASS, 1, TileKind::GRASS_WITH_STONE));
+        assert!(wfc.allows(TileKind::GRASS_WITH_STONE, 0, TileKind::GRASS));
+        assert!(wfc.allows(TileKind::WALL, 3, TileKind::FLOOR));
+        assert!(wfc.allows(TileKind::FLOOR, 2, TileKind::WALL));
+        assert!(!wfc.allows(TileKind::WALL, 2, TileKind::FLOOR));
+        assert!(!wfc.allows(TileKind::GRASS, 3, TileKind::FLOOR));
+        assert!(!wfc.allows(TileKind::DOOR, 0, TileKind::DOOR));
+
+        assert_eq!(
+            Err("line 1, column 2: unknown glyph 'x'".into()),
+            ".x\n..".parse::<WaveFunctionCollapse>().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_generated_maps_follow_rules() {
+        let registry = TileRegistry::shipped();
+        let wfc: WaveFunctionCollapse = RIVER.parse().unwrap();
+        let (width, height) = (MAP_WIDTH, MAP_HEIGHT + 3);
+
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let ids = wfc.collapse(width, height, &mut rng);
+            assert!(ids.is_some(), "failed to collapse with seed {seed}");
+
+            let map = wfc.generate(&registry, width, height, &mut rng);
+            assert_eq!(width * height, map.tiles.len());
+            for (index, tile) in map.tiles.iter().enumerate() {
+                for (direction, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+                    if let Some(neighbor) =
+                        neighbor_index(index, dx, dy, width, height)
+                    {
+                        let other = map.tiles[neighbor].kind;
+                        assert!(wfc.allows(tile.kind, direction, other));
+                        assert!(wfc.allows(
+                            other,
+                            opposite(direction),
+                            tile.kind
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_backtracking() {
+        let registry = TileRegistry::shipped();
+        // a sample with few allowed neighbors, leading to contradictions
+        let wfc: WaveFunctionCollapse = "_*o\n**_\n*#o\n".parse().unwrap();
+        let without_backtracking = WaveFunctionCollapse {
+            max_backtracks: 0,
+            ..wfc.clone()
+        };
+
+        let mut contradictions = 0;
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert!(wfc.collapse(12, 12, &mut rng).is_some());
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            if without_backtracking.collapse(12, 12, &mut rng).is_none() {
+                contradictions += 1;
+            }
+        }
+        assert!(contradictions > 0);
+
+        // the map is still generated when the collapse keeps failing
+        let mut rng = StdRng::seed_from_u64(0);
+        let map = without_backtracking.generate(&registry, 40, 40, &mut rng);
+        assert_eq!(40 * 40, map.tiles.len());
+    }
+
+    #[test]
+    fn test_default_sample() {
+        let wfc =
+            WaveFunctionCollapse::load(&asset_path(WFC_SAMPLE_MEADOW_FILE))
+                .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(wfc.collapse(MAP_WIDTH, MAP_HEIGHT, &mut rng).is_some());
+    }
+}
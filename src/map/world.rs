@@ -0,0 +1,333 @@
+use super::tile::TileKind;
+use crate::prelude::*;
+
+/// Represents a map of the world, which may not be generated yet.
+#[derive(Clone, Default)]
+pub struct WorldNode {
+    /// The dungeon level of the map in its location, 0 for the maps entered
+    /// from the overworld.
+    pub depth: usize,
+    /// The location the map belongs to, `None` for the overworld.
+    pub location: Option<LocationKind>,
+    /// The map as the player last left it, `None` if the player never left
+    /// it.
+    pub map: Option<Map>,
+    /// The mobs left on the map.
+    pub mobs: Vec<StoredMob>,
+}
+
+/// Represents a mob left on a map by the player, restored as it was when the
+/// player comes back (see `spawn_mobs_on_current_map`).
+#[derive(Clone, Copy)]
+pub struct StoredMob {
+    pub actor: Actor,
+    pub position: MapPosition,
+    pub health: Health,
+    pub cooldown: MovementCooldown,
+    pub energy: Energy,
+}
+
+/// Represents all the maps of the world, linked together by their exits. The
+/// maps are identified by their index (see `CurrentMapNumber`), the first one
+/// being the overworld (see `Overworld`).
+///
+/// The maps are generated the first time the player enters them, then stored
+/// when the player leaves them, so they can be revisited as they were left.
+#[derive(Clone, Resource)]
+pub struct WorldGraph {
+    pub nodes: Vec<WorldNode>,
+}
+
+impl Default for WorldGraph {
+    /// Creates a world with the overworld only.
+    fn default() -> Self {
+        Self {
+            nodes: vec![WorldNode::default()],
+        }
+    }
+}
+
+impl WorldGraph {
+    /// Adds a map, not generated yet, at a given depth of a location.
+    ///
+    /// # Returns
+    ///
+    /// The id of the added map.
+    pub fn add_map(
+        &mut self,
+        depth: usize,
+        location: Option<LocationKind>,
+    ) -> usize {
+        self.nodes.push(WorldNode {
+            depth,
+            location,
+            ..Default::default()
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Returns the depth of a map, 0 for unknown maps.
+    pub fn depth(&self, id: usize) -> usize {
+        self.nodes.get(id).map_or(0, |node| node.depth)
+    }
+
+    /// Returns the location a map belongs to, `None` for the overworld and
+    /// unknown maps.
+    pub fn location(&self, id: usize) -> Option<LocationKind> {
+        self.nodes.get(id).and_then(|node| node.location)
+    }
+
+    /// Links a newly generated map to the rest of the world: the exits on the
+    /// edges lead back to the overworld, the stairs lead to a new map one
+    /// level deeper in the same location, and the entrance becomes an exit
+    /// back to the map the player comes from, if any.
+    ///
+    /// # Arguments
+    ///
+    /// - `id`: The id of the generated map.
+    /// - `map`: The generated map.
+    /// - `from`: The map left by the player and the exit taken, if any.
+    pub fn link_new_map(
+        &mut self,
+        id: usize,
+        map: &mut Map,
+        from: Option<&LastMapExit>,
+    ) {
+        let (depth, location) = (self.depth(id), self.location(id));
+        for exit in &mut map.exits {
+            if exit.destination.is_some() {
+                continue;
+            }
+            exit.destination = Some(match exit.edge {
+                Some(_) => OVERWORLD_MAP_ID,
+                None => self.add_map(depth + 1, location),
+            });
+        }
+
+        let (Some(from), Some(entrance)) = (from, map.entrance) else {
+            return;
+        };
+        let index = map.as_tile_index(&entrance).unwrap();
+        map.tiles[index].kind = TileKind::LEVEL_EXIT;
+        // first, so the player arrives at the entrance (see
+        // `Map::arrival_position`)
+        map.exits.insert(
+            0,
+            MapExit {
+                position: entrance,
+                edge: from.exit.arrival_edge(),
+                destination: Some(from.map),
+            },
+        );
+    }
+
+    /// Stores a map left by the player. The actors standing on the map's
+    /// tiles are removed, as they are restored from the stored mobs (see
+    /// `WorldGraph::store_mobs`).
+    pub fn store_map(&mut self, id: usize, map: &Map) {
+        let mut map = map.clone();
+        for tile in &mut map.tiles {
+            tile.actor = None;
+        }
+
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.map = Some(map);
+        }
+    }
+
+    /// Stores the mobs left on a map by the player.
+    pub fn store_mobs(&mut self, id: usize, mobs: Vec<StoredMob>) {
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.mobs = mobs;
+        }
+    }
+
+    /// Returns whether a map was already left by the player.
+    pub fn is_visited(&self, id: usize) -> bool {
+        self.nodes.get(id).is_some_and(|node| node.map.is_some())
+    }
+}
+
+impl Map {
+    /// Returns the position where the player arrives on the map: next to the
+    /// exit leading back to the map the player comes from, or on the entrance
+    /// if there's no such exit.
+    pub fn arrival_position(
+        &self,
+        registry: &TileRegistry,
+        from: Option<usize>,
+    ) -> Option<MapPosition> {
+        let exit_back = self
+            .exits
+            .iter()
+            .find(|exit| from.is_some() && exit.destination == from);
+
+        let Some(exit_back) = exit_back else {
+            return self.entrance;
+        };
+
+        let index = self.as_tile_index(&exit_back.position).ok()?;
+        self.cardinal_neighbors(index)
+            .into_iter()
+            .map(|i| MapPosition::new(i % self.width, i / self.width))
+            .find(|pos| {
+                let i = self.as_tile_index(pos).unwrap();
+                self.tiles[i].is_walkable(registry)
+                    && self.exit_at(pos).is_none()
+            })
+            .or(self.entrance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tile::Tile;
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn generate_map(entrance_edge: Option<MapEdge>, seed: u64) -> Map {
+        let registry = TileRegistry::shipped();
+        let entry = MapGeneratorEntry::new("perlin", PerlinNoiseGenerator);
+        let mut rng = StdRng::seed_from_u64(seed);
+        entry.generate(
+            &registry,
+            MAP_WIDTH,
+            MAP_HEIGHT,
+            entrance_edge,
+            &[],
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn test_link_map_without_exits() {
+        let mut world = WorldGraph::default();
+        let id = world.add_map(0, Some(LocationKind::Forest));
+        let from_overworld = LastMapExit {
+            map: OVERWORLD_MAP_ID,
+            exit: MapExit {
+                position: MapPosition::new(0, 0),
+                edge: None,
+                destination: Some(id),
+            },
+        };
+
+        let mut map = Map::parse_rows(&["...", "..."]);
+        map.entrance = Some(MapPosition::new(0, 1));
+        world.link_new_map(id, &mut map, Some(&from_overworld));
+
+        // the entrance is the only way out, back to the overworld
+        assert_eq!(1, map.exits.len());
+        let exit = map.exit_at(&MapPosition::new(0, 1)).unwrap();
+        assert_eq!(Some(OVERWORLD_MAP_ID), exit.destination);
+        assert_eq!(TileKind::LEVEL_EXIT, map.tiles[3].kind);
+        assert_eq!(2, world.nodes.len());
+    }
+
+    #[test]
+    fn test_link_maps_both_ways() {
+        let registry = TileRegistry::shipped();
+        let mut world = WorldGraph::default();
+        let id = world.add_map(0, Some(LocationKind::Cave));
+        let from_overworld = LastMapExit {
+            map: OVERWORLD_MAP_ID,
+            exit: MapExit {
+                position: MapPosition::new(0, 0),
+                edge: None,
+                destination: Some(id),
+            },
+        };
+
+        let mut first = generate_map(None, 0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let stairs = first.add_exit(&registry, &[], None, &mut rng).unwrap();
+        world.link_new_map(id, &mut first, Some(&from_overworld));
+
+        // the entrance and the edges lead back to the overworld, the stairs
+        // lead one level deeper in the same location
+        assert_eq!(first.entrance, Some(first.exits[0].position));
+        assert_eq!(Some(OVERWORLD_MAP_ID), first.exits[0].destination);
+        for exit in &first.exits[1..] {
+            let destination = exit.destination.unwrap();
+            if exit.edge.is_some() {
+                assert_eq!(OVERWORLD_MAP_ID, destination);
+            } else {
+                assert_eq!(1, world.depth(destination));
+                assert_eq!(
+                    Some(LocationKind::Cave),
+                    world.location(destination)
+                );
+            }
+        }
+
+        let stairs = *first.exit_at(&stairs.position).unwrap();
+        let last_exit = LastMapExit {
+            map: id,
+            exit: stairs,
+        };
+        let destination = stairs.destination.unwrap();
+        let mut second = generate_map(None, 1);
+        world.link_new_map(destination, &mut second, Some(&last_exit));
+
+        // the entrance of the second map leads back to the first one
+        let exit_back = second.exit_at(&second.entrance.unwrap()).unwrap();
+        assert_eq!(Some(id), exit_back.destination);
+        assert_eq!(None, exit_back.edge);
+
+        // the player arrives next to the exits linking both maps
+        let arrival = second.arrival_position(&registry, Some(id)).unwrap();
+        assert!(second.exit_at(&arrival).is_none());
+        let index = second.as_tile_index(&exit_back.position).unwrap();
+        let arrival_index = second.as_tile_index(&arrival).unwrap();
+        assert!(second.cardinal_neighbors(index).contains(&arrival_index));
+
+        let arrival = first
+            .arrival_position(&registry, Some(destination))
+            .unwrap();
+        let index = first.as_tile_index(&stairs.position).unwrap();
+        let arrival_index = first.as_tile_index(&arrival).unwrap();
+        assert!(first.cardinal_neighbors(index).contains(&arrival_index));
+
+        // coming back from the overworld, the player arrives at the entrance
+        let arrival = first
+            .arrival_position(&registry, Some(OVERWORLD_MAP_ID))
+            .unwrap();
+        let index = first.as_tile_index(&first.entrance.unwrap()).unwrap();
+        let arrival_index = first.as_tile_index(&arrival).unwrap();
+        assert!(first.cardinal_neighbors(index).contains(&arrival_index));
+    }
+
+    #[test]
+    fn test_store_map() {
+        let mut world = WorldGraph::default();
+        let mut map = Map {
+            width: 3,
+            height: 1,
+            tiles: vec![Tile::default(); 3],
+            ..Default::default()
+        };
+        let blob = Actor::new(ActorKind::Blob);
+        map.tiles[1].actor = Some(blob);
+        map.tiles[2].kind = TileKind::GRASS_WITH_STONE;
+
+        assert!(!world.is_visited(0));
+        world.store_mobs(
+            0,
+            vec![StoredMob {
+                actor: blob,
+                position: MapPosition::new(1, 0),
+                health: blob.kind.health(),
+                cooldown: MovementCooldown::default(),
+                energy: Energy::default(),
+            }],
+        );
+        assert!(!world.is_visited(0));
+        world.store_map(0, &map);
+        assert!(world.is_visited(0));
+
+        let stored = world.nodes[0].map.as_ref().unwrap();
+        assert!(stored.tiles.iter().all(|tile| tile.actor.is_none()));
+        assert_eq!(TileKind::GRASS_WITH_STONE, stored.tiles[2].kind);
+        assert_eq!(1, world.nodes[0].mobs.len());
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub const UI_MENU_BACKGROUND_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+pub const UI_PAUSE_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+pub const UI_MENU_ITEM_COLOR: Color = Color::NONE;
+pub const UI_MENU_ITEM_SELECTED_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+pub const UI_MENU_TEXT_COLOR: Color = Color::WHITE;
+pub const UI_MENU_TITLE_SIZE: f32 = 48.0;
+pub const UI_MENU_TEXT_SIZE: f32 = 24.0;
+
+/// The width in pixels of the menu items.
+pub const UI_MENU_ITEM_WIDTH: f32 = 320.0;
+
+/// The maximum number of digits of a seed typed in the main menu.
+pub const MENU_SEED_MAX_DIGITS: usize = 20;
+
+pub const KEYS_MENU_UP: [KeyCode; 2] = [KeyCode::KeyW, KeyCode::ArrowUp];
+pub const KEYS_MENU_DOWN: [KeyCode; 2] = [KeyCode::KeyS, KeyCode::ArrowDown];
+pub const KEYS_MENU_CONFIRM: [KeyCode; 2] = [KeyCode::Enter, KeyCode::Space];
+pub const KEY_MENU_ERASE: KeyCode = KeyCode::Backspace;
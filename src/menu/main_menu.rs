@@ -0,0 +1,212 @@
+use std::path::Path;
+
+use crate::prelude::*;
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+
+/// Represents the pages of the main menu.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MainMenuPage {
+    #[default]
+    Main,
+    /// The page where the seed of the next run is typed.
+    Seed,
+    Settings,
+}
+
+/// Represents the state of the main menu, displayed again whenever it
+/// changes (see `show_main_menu`).
+#[derive(Default, Resource)]
+pub struct MainMenu {
+    pub page: MainMenuPage,
+    /// The digits typed on the seed page.
+    pub seed_input: String,
+}
+
+impl MainMenu {
+    /// Opens a page of the main menu, with its first item selected.
+    const fn open(
+        &mut self,
+        page: MainMenuPage,
+        selection: &mut MenuSelection,
+    ) {
+        self.page = page;
+        selection.0 = 0;
+    }
+}
+
+/// Marker component for the camera displaying the main menu.
+#[derive(Component)]
+pub struct MainMenuCamera;
+
+/// Creates the camera and the state of the main menu.
+pub fn setup_main_menu(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), MainMenuCamera));
+    commands.insert_resource(MainMenu::default());
+    commands.insert_resource(MenuSelection::default());
+}
+
+/// Displays the current page of the main menu. The main page offers to
+/// continue the saved run, if any (see `SavedRun`).
+pub fn show_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    main_menu: Res<MainMenu>,
+    settings: Res<Settings>,
+    run_seed: Res<RunSeed>,
+    q_menus: Query<Entity, With<UiMenu>>,
+) {
+    for entity in &q_menus {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let font = asset_server.load(UI_FONT);
+    match main_menu.page {
+        MainMenuPage::Main => {
+            let mut items = vec![(MenuAction::NewGame, "New Game".into())];
+            if SavedRun::exists() {
+                items.push((MenuAction::Continue, "Continue".into()));
+            }
+            items.extend([
+                (MenuAction::EnterSeed, "Seed".into()),
+                (MenuAction::Settings, "Settings".into()),
+                (MenuAction::Quit, "Quit".into()),
+            ]);
+            spawn_menu(
+                &mut commands,
+                &font,
+                WINDOW_TITLE,
+                &[format!("Seed {}", run_seed.0)],
+                &items,
+                UI_MENU_BACKGROUND_COLOR,
+            );
+        }
+        MainMenuPage::Seed => {
+            spawn_menu(
+                &mut commands,
+                &font,
+                "Seed",
+                &[format!("{}_", main_menu.seed_input)],
+                &[
+                    (MenuAction::ConfirmSeed, "Confirm".into()),
+                    (MenuAction::Back, "Back".into()),
+                ],
+                UI_MENU_BACKGROUND_COLOR,
+            );
+        }
+        MainMenuPage::Settings => {
+            spawn_menu(
+                &mut commands,
+                &font,
+                "Settings",
+                &[],
+                &settings.menu_items(),
+                UI_MENU_BACKGROUND_COLOR,
+            );
+        }
+    }
+}
+
+/// Types the digits of the seed on the seed page, `KEY_MENU_ERASE` erasing
+/// the last one.
+pub fn type_seed_via_keys(
+    mut characters: EventReader<ReceivedCharacter>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut main_menu: ResMut<MainMenu>,
+) {
+    if main_menu.page != MainMenuPage::Seed {
+        characters.clear();
+        return;
+    }
+    for event in characters.read() {
+        for digit in event.char.chars().filter(char::is_ascii_digit) {
+            if main_menu.seed_input.len() < MENU_SEED_MAX_DIGITS {
+                main_menu.seed_input.push(digit);
+            }
+        }
+    }
+    if input.just_pressed(KEY_MENU_ERASE) {
+        main_menu.seed_input.pop();
+    }
+}
+
+/// The menu state changed when navigating between the pages of the main menu.
+#[derive(SystemParam)]
+pub struct MainMenuNavigation<'w> {
+    main_menu: ResMut<'w, MainMenu>,
+    selection: ResMut<'w, MenuSelection>,
+}
+
+/// Performs the actions chosen in the main menu.
+///
+/// The save file of a continued run is removed once the run is restored (see
+/// `spawn_mobs_on_current_map`).
+pub fn handle_main_menu_actions(
+    mut commands: Commands,
+    mut chosen: EventReader<MenuActionChosen>,
+    navigation: MainMenuNavigation,
+    mut settings: ResMut<Settings>,
+    registry: Res<TileRegistry>,
+    mut app_next_state: ResMut<NextState<AppState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let MainMenuNavigation {
+        mut main_menu,
+        mut selection,
+    } = navigation;
+    for MenuActionChosen(action) in chosen.read() {
+        match action {
+            MenuAction::NewGame => app_next_state.set(AppState::InGame),
+            MenuAction::Continue => {
+                match SavedRun::load(Path::new(SAVE_FILE), &registry) {
+                    Ok(run) => {
+                        run.resume(&mut commands);
+                        app_next_state.set(AppState::InGame);
+                    }
+                    Err(e) => error!("{e}"),
+                }
+            }
+            MenuAction::EnterSeed => {
+                main_menu.seed_input.clear();
+                main_menu.open(MainMenuPage::Seed, &mut selection);
+            }
+            MenuAction::ConfirmSeed => {
+                // an empty or too large number keeps the current seed
+                if let Ok(seed) = main_menu.seed_input.parse() {
+                    commands.insert_resource(RunSeed(seed));
+                    commands.insert_resource(RunRng::from(RunSeed(seed)));
+                }
+                main_menu.open(MainMenuPage::Main, &mut selection);
+            }
+            MenuAction::Settings => {
+                main_menu.open(MainMenuPage::Settings, &mut selection);
+            }
+            MenuAction::ToggleFullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+            }
+            MenuAction::Back => {
+                main_menu.open(MainMenuPage::Main, &mut selection);
+            }
+            MenuAction::Quit => {
+                app_next_state.set(AppState::Finished);
+                exit_events.send(AppExit);
+            }
+            // handled by the pause menu
+            MenuAction::Resume
+            | MenuAction::SaveAndQuit
+            | MenuAction::QuitWithoutSaving => {}
+        }
+    }
+}
+
+/// Removes the main menu and its camera before the game starts.
+pub fn cleanup_main_menu(
+    mut commands: Commands,
+    q_menus: Query<Entity, With<UiMenu>>,
+    q_cameras: Query<Entity, With<MainMenuCamera>>,
+) {
+    for entity in q_menus.iter().chain(&q_cameras) {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MainMenu>();
+}
@@ -0,0 +1,266 @@
+mod constants;
+mod main_menu;
+mod pause_menu;
+
+pub use constants::*;
+pub use main_menu::*;
+pub use pause_menu::*;
+
+use crate::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuActionChosen>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<Settings>()
+            .add_systems(
+                Update,
+                (
+                    select_menu_item_via_keys,
+                    select_menu_item_via_mouse,
+                    update_menu_item_colors,
+                )
+                    .chain()
+                    .run_if(any_with_component::<MenuItem>),
+            )
+            .add_systems(
+                Update,
+                apply_settings.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
+            .add_systems(
+                Update,
+                (
+                    type_seed_via_keys,
+                    handle_main_menu_actions.after(select_menu_item_via_mouse),
+                    show_main_menu.run_if(
+                        resource_exists_and_changed::<MainMenu>
+                            .or_else(resource_changed::<Settings>)
+                            .or_else(resource_changed::<RunSeed>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::MainMenu)),
+            )
+            .add_systems(OnExit(AppState::MainMenu), cleanup_main_menu)
+            .add_systems(OnEnter(PauseState::Paused), setup_pause_menu)
+            .add_systems(
+                Update,
+                (
+                    handle_pause_menu_actions.after(select_menu_item_via_mouse),
+                    save_run_and_quit.after(select_menu_item_via_mouse),
+                    show_pause_menu.run_if(
+                        resource_exists_and_changed::<PauseMenu>
+                            .or_else(resource_changed::<Settings>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(OnExit(PauseState::Paused), cleanup_pause_menu);
+    }
+}
+
+/// Represents what happens when a menu item is chosen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MenuAction {
+    NewGame,
+    Continue,
+    EnterSeed,
+    ConfirmSeed,
+    Settings,
+    ToggleFullscreen,
+    Back,
+    Quit,
+    /// Closes the pause menu.
+    Resume,
+    SaveAndQuit,
+    QuitWithoutSaving,
+}
+
+/// Event sent when a menu item is chosen, with the keyboard or the mouse.
+#[derive(Event)]
+pub struct MenuActionChosen(pub MenuAction);
+
+/// Represents an item of the displayed menu.
+#[derive(Component)]
+pub struct MenuItem {
+    pub action: MenuAction,
+    /// The position of the item in the menu, from the top.
+    pub index: usize,
+}
+
+/// Represents the index of the selected item in the displayed menu.
+#[derive(Default, Resource)]
+pub struct MenuSelection(pub usize);
+
+/// Marker component for the root of the displayed menu.
+#[derive(Component)]
+pub struct UiMenu;
+
+/// Represents the settings of the game, changed from the menus.
+#[derive(Default, Resource)]
+pub struct Settings {
+    /// Whether the window covers the whole screen.
+    pub fullscreen: bool,
+}
+
+impl Settings {
+    /// Returns the items of the settings menu.
+    pub fn menu_items(&self) -> Vec<(MenuAction, String)> {
+        let fullscreen = if self.fullscreen { "on" } else { "off" };
+        vec![
+            (
+                MenuAction::ToggleFullscreen,
+                format!("Fullscreen: {fullscreen}"),
+            ),
+            (MenuAction::Back, "Back".into()),
+        ]
+    }
+}
+
+/// Spawns a menu in the middle of the screen, made of a title, some lines of
+/// text and the items which can be chosen, over a background of the given
+/// color.
+///
+/// # Returns
+///
+/// The root entity of the menu, marked with `UiMenu`.
+pub fn spawn_menu(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    title: &str,
+    lines: &[String],
+    items: &[(MenuAction, String)],
+    background: Color,
+) -> Entity {
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: UI_MENU_TEXT_COLOR,
+            },
+        )
+    };
+
+    commands
+        .spawn((
+            UiMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(UI_MENU_TEXT_SIZE / 2.0),
+                    ..default()
+                },
+                background_color: background.into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(text(title, UI_MENU_TITLE_SIZE));
+            for line in lines {
+                parent.spawn(text(line, UI_MENU_TEXT_SIZE));
+            }
+            for (index, (action, label)) in items.iter().enumerate() {
+                parent
+                    .spawn((
+                        MenuItem {
+                            action: *action,
+                            index,
+                        },
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(UI_MENU_ITEM_WIDTH),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: UI_MENU_ITEM_COLOR.into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(text(label, UI_MENU_TEXT_SIZE));
+                    });
+            }
+        })
+        .id()
+}
+
+/// Moves the selection in the displayed menu with `KEYS_MENU_UP` and
+/// `KEYS_MENU_DOWN`, and chooses the selected item with `KEYS_MENU_CONFIRM`.
+pub fn select_menu_item_via_keys(
+    input: Res<ButtonInput<KeyCode>>,
+    q_items: Query<&MenuItem>,
+    mut selection: ResMut<MenuSelection>,
+    mut chosen: EventWriter<MenuActionChosen>,
+) {
+    let count = q_items.iter().count();
+    if input.any_just_pressed(KEYS_MENU_UP) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if input.any_just_pressed(KEYS_MENU_DOWN) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+    if input.any_just_pressed(KEYS_MENU_CONFIRM) {
+        if let Some(item) = q_items.iter().find(|i| i.index == selection.0) {
+            chosen.send(MenuActionChosen(item.action));
+        }
+    }
+}
+
+/// Selects the menu item hovered by the mouse, and chooses it when clicked.
+pub fn select_menu_item_via_mouse(
+    q_items: Query<(&Interaction, &MenuItem), Changed<Interaction>>,
+    mut selection: ResMut<MenuSelection>,
+    mut chosen: EventWriter<MenuActionChosen>,
+) {
+    for (interaction, item) in &q_items {
+        match interaction {
+            Interaction::Hovered => selection.0 = item.index,
+            Interaction::Pressed => {
+                selection.0 = item.index;
+                chosen.send(MenuActionChosen(item.action));
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+/// Highlights the selected item of the displayed menu.
+pub fn update_menu_item_colors(
+    mut q_items: Query<(&MenuItem, &mut BackgroundColor)>,
+    selection: Res<MenuSelection>,
+) {
+    for (item, mut color) in &mut q_items {
+        *color = if item.index == selection.0 {
+            UI_MENU_ITEM_SELECTED_COLOR.into()
+        } else {
+            UI_MENU_ITEM_COLOR.into()
+        };
+    }
+}
+
+/// Applies the `Settings` to the window.
+pub fn apply_settings(
+    settings: Res<Settings>,
+    mut q_windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = q_windows.get_single_mut() else {
+        return;
+    };
+    window.mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+}
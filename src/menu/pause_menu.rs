@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use crate::prelude::*;
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+
+/// Represents the pages of the pause menu.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PauseMenuPage {
+    #[default]
+    Main,
+    Settings,
+}
+
+/// Represents the state of the pause menu, displayed again whenever it
+/// changes (see `show_pause_menu`).
+#[derive(Default, Resource)]
+pub struct PauseMenu {
+    pub page: PauseMenuPage,
+}
+
+impl PauseMenu {
+    /// Opens a page of the pause menu, with its first item selected.
+    const fn open(
+        &mut self,
+        page: PauseMenuPage,
+        selection: &mut MenuSelection,
+    ) {
+        self.page = page;
+        selection.0 = 0;
+    }
+}
+
+/// Components of the player saved with the run.
+type SavedPlayerComponents<'a> = (
+    &'a Actor,
+    &'a MapPosition,
+    &'a Health,
+    &'a Inventory,
+    &'a MovementCooldown,
+    &'a SearchStreak,
+    &'a Energy,
+);
+
+/// Resources of the current run saved with it (see `SavedRun`).
+#[derive(SystemParam)]
+pub struct CurrentRun<'w> {
+    world: Res<'w, WorldGraph>,
+    overworld: Res<'w, Overworld>,
+    seed: Res<'w, RunSeed>,
+    rng: Res<'w, RunRng>,
+    turn_number: Res<'w, CurrentTurnNumber>,
+    map_number: Res<'w, CurrentMapNumber>,
+    last_exit: Option<Res<'w, LastMapExit>>,
+}
+
+/// Creates the state of the pause menu.
+pub fn setup_pause_menu(mut commands: Commands) {
+    commands.insert_resource(PauseMenu::default());
+    commands.insert_resource(MenuSelection::default());
+}
+
+/// Displays the current page of the pause menu over the game.
+pub fn show_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pause_menu: Res<PauseMenu>,
+    settings: Res<Settings>,
+    q_menus: Query<Entity, With<UiMenu>>,
+) {
+    for entity in &q_menus {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let font = asset_server.load(UI_FONT);
+    let (title, items) = match pause_menu.page {
+        PauseMenuPage::Main => (
+            "Pause",
+            vec![
+                (MenuAction::Resume, "Resume".into()),
+                (MenuAction::SaveAndQuit, "Save & Quit".into()),
+                (MenuAction::Settings, "Settings".into()),
+                (MenuAction::QuitWithoutSaving, "Quit without saving".into()),
+            ],
+        ),
+        PauseMenuPage::Settings => ("Settings", settings.menu_items()),
+    };
+    spawn_menu(
+        &mut commands,
+        &font,
+        title,
+        &[],
+        &items,
+        UI_PAUSE_BACKGROUND_COLOR,
+    );
+}
+
+/// Performs the actions chosen in the pause menu, except saving the run (see
+/// `save_run_and_quit`).
+pub fn handle_pause_menu_actions(
+    mut chosen: EventReader<MenuActionChosen>,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut selection: ResMut<MenuSelection>,
+    mut settings: ResMut<Settings>,
+    mut pause_next_state: ResMut<NextState<PauseState>>,
+    mut app_next_state: ResMut<NextState<AppState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    for MenuActionChosen(action) in chosen.read() {
+        match action {
+            MenuAction::Resume => pause_next_state.set(PauseState::Running),
+            MenuAction::Settings => {
+                pause_menu.open(PauseMenuPage::Settings, &mut selection);
+            }
+            MenuAction::ToggleFullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+            }
+            MenuAction::Back => {
+                pause_menu.open(PauseMenuPage::Main, &mut selection);
+            }
+            MenuAction::QuitWithoutSaving => {
+                app_next_state.set(AppState::Finished);
+                exit_events.send(AppExit);
+            }
+            // handled by the main menu, or by `save_run_and_quit`
+            MenuAction::NewGame
+            | MenuAction::Continue
+            | MenuAction::EnterSeed
+            | MenuAction::ConfirmSeed
+            | MenuAction::Quit
+            | MenuAction::SaveAndQuit => {}
+        }
+    }
+}
+
+/// Saves the run in `SAVE_FILE` and quits the game, when chosen in the pause
+/// menu. The current map and its mobs are stored in a copy of the
+/// `WorldGraph`, as if the player left the map. The game stays paused if the
+/// run can't be saved.
+pub fn save_run_and_quit(
+    mut chosen: EventReader<MenuActionChosen>,
+    q_map: Query<&Map, With<OnDisplay>>,
+    q_actors: Query<SavedPlayerComponents, With<OnDisplay>>,
+    run: CurrentRun,
+    mut app_next_state: ResMut<NextState<AppState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let save_and_quit = chosen
+        .read()
+        .any(|MenuActionChosen(action)| *action == MenuAction::SaveAndQuit);
+    if !save_and_quit {
+        return;
+    }
+
+    let mut world = run.world.clone();
+    let mut mobs = vec![];
+    let mut player = None;
+    for (actor, position, health, inventory, cooldown, search_streak, energy) in
+        &q_actors
+    {
+        if actor.is_player() {
+            player = Some(SavedPlayer {
+                position: *position,
+                health: *health,
+                inventory: inventory.clone(),
+                cooldown: *cooldown,
+                search_streak: *search_streak,
+                energy: *energy,
+            });
+        } else {
+            mobs.push(StoredMob {
+                actor: *actor,
+                position: *position,
+                health: *health,
+                cooldown: *cooldown,
+                energy: *energy,
+            });
+        }
+    }
+    let Some(player) = player else {
+        error!("failed to save the run: no player found");
+        return;
+    };
+    world.store_map(run.map_number.0, q_map.single());
+    world.store_mobs(run.map_number.0, mobs);
+
+    let saved_run = SavedRun {
+        seed: *run.seed,
+        turn: run.turn_number.0,
+        rng: run.rng.position(),
+        map: run.map_number.0,
+        last_exit: run.last_exit.map(|last_exit| *last_exit),
+        player,
+        overworld: run.overworld.clone(),
+        world,
+    };
+    match saved_run.save(Path::new(SAVE_FILE)) {
+        Ok(()) => {
+            app_next_state.set(AppState::Finished);
+            exit_events.send(AppExit);
+        }
+        Err(e) => error!("{e}"),
+    }
+}
+
+/// Removes the pause menu once the game resumes.
+pub fn cleanup_pause_menu(
+    mut commands: Commands,
+    q_menus: Query<Entity, With<UiMenu>>,
+) {
+    for entity in &q_menus {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<PauseMenu>();
+}
@@ -1,36 +1,45 @@
+mod seed;
 mod tileset;
 
+pub use seed::*;
 pub use tileset::*;
 
+use std::path::PathBuf;
+
 use crate::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
 
 pub struct ResourcesPlugin;
 
 impl Plugin for ResourcesPlugin {
     fn build(&self, app: &mut App) {
+        let run_seed = RunSeed::from_args();
         app.insert_resource(CurrentTurnNumber::default())
             .insert_resource(CurrentMapNumber::default())
+            .insert_resource(run_seed)
+            .insert_resource(RunRng::from(run_seed))
             .add_systems(OnEnter(AppState::InGame), initialize_resources);
     }
 }
 
-/// Represents the current map number. The map number is increased every time
-/// the player exits to another map.
+/// Represents the id of the current map in the `WorldGraph`. The map number
+/// changes every time the player exits to another map.
 #[derive(Default, Resource)]
 pub struct CurrentMapNumber(pub usize);
 
-/// Represents the current game turn. A turn is passed each time all actors
-/// have performed an action or a move.
+/// Represents the current game turn. A turn is passed each tick, when all
+/// actors gain energy (see `schedule_turns`).
 #[derive(Default, Resource)]
 pub struct CurrentTurnNumber(pub usize);
 
-/// Increases the `CurrentTurnNumber` value by 1.
-pub fn increase_game_turn(
-    mut next_state: ResMut<NextState<GameState>>,
-    mut game_turn: ResMut<CurrentTurnNumber>,
-) {
-    game_turn.0 += 1;
-    next_state.set(GameState::PlayerTurn);
+/// Returns the path of a file in the `ASSET_FOLDER`, for the assets read
+/// without the `AssetServer`. The folder is found the same way as the
+/// `AssetServer` does (see `FileAssetReader::get_base_path`), so it doesn't
+/// depend on the working directory.
+pub fn asset_path(path: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join(ASSET_FOLDER)
+        .join(path)
 }
 
 /// Initializes image resources.
@@ -38,6 +47,7 @@ fn initialize_resources(
     mut commands: Commands,
     tileset_folder: Res<TilesetFolder>,
     loaded_folders: Res<Assets<LoadedFolder>>,
+    mut images: ResMut<Assets<Image>>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut game_next_state: ResMut<NextState<GameState>>,
 ) {
@@ -58,10 +68,15 @@ fn initialize_resources(
                         "terrain" => {
                             initialize_tileset_terrain_resource(
                                 handle,
+                                find_tileset(folder, "structure")
+                                    .expect("no structure tileset found"),
+                                &mut images,
                                 &mut texture_atlases,
                                 &mut commands,
                             );
                         }
+                        // appended to the terrain tileset
+                        "structure" => {}
                         _ => {
                             panic!("tileset unused");
                         }
@@ -73,3 +88,17 @@ fn initialize_resources(
 
     game_next_state.set(GameState::InitializingMap);
 }
+
+/// Returns the handle of the tileset with a given file stem in the tileset
+/// folder.
+fn find_tileset<'a>(
+    folder: &'a LoadedFolder,
+    stem: &str,
+) -> Option<&'a UntypedHandle> {
+    folder.handles.iter().find(|handle| {
+        handle
+            .path()
+            .and_then(|path| path.path().file_stem())
+            .is_some_and(|file_stem| file_stem == stem)
+    })
+}
@@ -0,0 +1,263 @@
+use rand::rngs::StdRng;
+
+use crate::prelude::*;
+
+/// Command line flag used for setting the run seed at startup.
+pub const RUN_SEED_ARG: &str = "--seed";
+
+/// Represents the seed of the current run. Every random decision taken during
+/// the run (map generation, actors spawning, actors behavior, combat) derives
+/// from it, so the same seed always produces the same sequence of maps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Resource)]
+pub struct RunSeed(pub u64);
+
+impl RunSeed {
+    /// Returns the seed given on the command line (e.g. `--seed 42`). A random
+    /// seed is picked if none is provided, or if the given one is invalid.
+    pub fn from_args() -> Self {
+        match Self::parse_args(std::env::args()) {
+            Ok(Some(seed)) => seed,
+            Ok(None) => Self::random(),
+            Err(e) => {
+                error!("{e}, using a random seed");
+                Self::random()
+            }
+        }
+    }
+
+    /// Parses the seed following `RUN_SEED_ARG` in command line arguments.
+    ///
+    /// # Returns
+    ///
+    /// The seed, `None` if there is no `RUN_SEED_ARG`, or an error if its
+    /// value is missing or isn't a valid unsigned integer.
+    fn parse_args(
+        args: impl Iterator<Item = String>,
+    ) -> Result<Option<Self>, String> {
+        let mut args = args.skip_while(|arg| arg != RUN_SEED_ARG);
+        if args.next().is_none() {
+            return Ok(None);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {RUN_SEED_ARG}"))?;
+        value
+            .parse()
+            .map(|seed| Some(Self(seed)))
+            .map_err(|_| format!("invalid value '{value}' for {RUN_SEED_ARG}"))
+    }
+
+    /// Returns a random seed, e.g. for a new run started from the game over
+    /// screen.
+    pub fn random() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+
+    /// Returns the seed used for generating the map with a given number. Each
+    /// map gets its own stream so that it only depends on the run seed and its
+    /// number, not on what happened on the previous maps.
+    pub const fn for_map(self, map_number: usize) -> u64 {
+        splitmix64(self.0 ^ splitmix64(map_number as u64))
+    }
+
+    /// Returns the seed used for the actors behavior.
+    pub const fn for_ai(self) -> u64 {
+        splitmix64(!self.0)
+    }
+
+    /// Returns the seed used for the combat rolls.
+    pub const fn for_combat(self) -> u64 {
+        splitmix64(self.0.rotate_left(32))
+    }
+}
+
+/// Represents a random number generator counting the 32-bit words drawn from
+/// its stream, so it can be resumed at the same position (see
+/// `RunRng::resumed`).
+pub struct TrackedRng {
+    rng: StdRng,
+    /// The number of 32-bit words drawn so far.
+    words: u64,
+}
+
+impl TrackedRng {
+    /// Creates a generator at the start of the stream of a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            words: 0,
+        }
+    }
+
+    /// Creates a generator for a seed, after a given number of words were
+    /// drawn from its stream.
+    pub fn resumed(seed: u64, words: u64) -> Self {
+        let mut rng = Self::new(seed);
+        for _ in 0..words {
+            rng.next_u32();
+        }
+        rng
+    }
+
+    /// Returns the number of 32-bit words drawn so far.
+    pub const fn words(&self) -> u64 {
+        self.words
+    }
+}
+
+impl RngCore for TrackedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.words += 1;
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.words += 2;
+        self.rng.next_u64()
+    }
+
+    /// Fills the bytes, a partially used word being dropped.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.words += dest.len().div_ceil(4) as u64;
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.words += dest.len().div_ceil(4) as u64;
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Represents the position of the actors behavior and combat generators in
+/// their streams, saved with a run (see `SavedRun`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RunRngPosition {
+    /// The words drawn by the actors behavior generator.
+    pub ai: u64,
+    /// The words drawn by the combat generator.
+    pub combat: u64,
+}
+
+/// Represents the random number generators of the current run. They are all
+/// seeded from the `RunSeed`.
+#[derive(Resource)]
+pub struct RunRng {
+    /// Generator used for the map generation and the actors spawning. It is
+    /// reseeded every time a new map is initialized.
+    pub map: StdRng,
+    /// Generator used for the actors behavior (e.g. random movements).
+    pub ai: TrackedRng,
+    /// Generator used for the combat rolls (see `CombatStats::roll_damage`).
+    pub combat: TrackedRng,
+}
+
+impl RunRng {
+    /// Creates the generators of a resumed run (see `SavedRun`), at the
+    /// position they were saved, so the run goes on as if it was never
+    /// saved. The map generator is reseeded once a map is initialized anyway.
+    pub fn resumed(seed: RunSeed, position: RunRngPosition) -> Self {
+        Self {
+            map: StdRng::seed_from_u64(seed.for_map(0)),
+            ai: TrackedRng::resumed(seed.for_ai(), position.ai),
+            combat: TrackedRng::resumed(seed.for_combat(), position.combat),
+        }
+    }
+
+    /// Returns the position of the actors behavior and combat generators.
+    pub const fn position(&self) -> RunRngPosition {
+        RunRngPosition {
+            ai: self.ai.words(),
+            combat: self.combat.words(),
+        }
+    }
+
+    /// Reseeds the map generator for a given map number.
+    pub fn reseed_map(&mut self, seed: RunSeed, map_number: usize) {
+        self.map = StdRng::seed_from_u64(seed.for_map(map_number));
+    }
+}
+
+impl From<RunSeed> for RunRng {
+    fn from(seed: RunSeed) -> Self {
+        Self {
+            map: StdRng::seed_from_u64(seed.for_map(0)),
+            ai: TrackedRng::new(seed.for_ai()),
+            combat: TrackedRng::new(seed.for_combat()),
+        }
+    }
+}
+
+/// Scrambles the bits of a 64-bit value (`SplitMix64` finalizer), used for
+/// deriving independent seeds from the run seed.
+const fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_rng_is_reproducible() {
+        let mut rng_a = RunRng::from(RunSeed(42));
+        let mut rng_b = RunRng::from(RunSeed(42));
+
+        let values_a: Vec<u32> = (0..10).map(|_| rng_a.map.gen()).collect();
+        let values_b: Vec<u32> = (0..10).map(|_| rng_b.map.gen()).collect();
+        assert_eq!(values_a, values_b);
+
+        rng_a.reseed_map(RunSeed(42), 3);
+        rng_b.reseed_map(RunSeed(42), 3);
+        assert_eq!(rng_a.map.gen::<u64>(), rng_b.map.gen::<u64>());
+    }
+
+    #[test]
+    fn test_resumed_run_rng() {
+        let mut rng = RunRng::from(RunSeed(42));
+        let _: (u32, u64, bool) = (rng.ai.gen(), rng.ai.gen(), rng.ai.gen());
+        let _: [u8; 5] = rng.combat.gen();
+        let mut bytes = [0; 6];
+        rng.combat.fill_bytes(&mut bytes);
+
+        // the resumed generators draw the same values as the original ones
+        let mut resumed = RunRng::resumed(RunSeed(42), rng.position());
+        assert_eq!(rng.position(), resumed.position());
+        for _ in 0..10 {
+            assert_eq!(rng.ai.gen::<u64>(), resumed.ai.gen::<u64>());
+            assert_eq!(rng.combat.gen::<u32>(), resumed.combat.gen::<u32>());
+        }
+    }
+
+    #[test]
+    fn test_parse_seed_args() {
+        let parse = |args: &[&str]| {
+            RunSeed::parse_args(args.iter().map(|arg| (*arg).to_owned()))
+        };
+
+        assert_eq!(Ok(None), parse(&["roguelike"]));
+        assert_eq!(
+            Ok(Some(RunSeed(42))),
+            parse(&["roguelike", "--seed", "42"])
+        );
+        assert_eq!(
+            Err("missing value for --seed".into()),
+            parse(&["roguelike", "--seed"])
+        );
+        assert_eq!(
+            Err("invalid value '4x2' for --seed".into()),
+            parse(&["roguelike", "--seed", "4x2"])
+        );
+    }
+
+    #[test]
+    fn test_map_seeds_differ() {
+        let seed = RunSeed(42);
+        assert_ne!(seed.for_map(0), seed.for_map(1));
+        assert_ne!(seed.for_map(0), RunSeed(43).for_map(0));
+        assert_ne!(seed.for_map(0), seed.for_ai());
+        assert_ne!(seed.for_ai(), seed.for_combat());
+    }
+}
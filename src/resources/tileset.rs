@@ -1,4 +1,6 @@
 use bevy::asset::LoadedFolder;
+use bevy::render::render_resource::{Extent3d, TextureDimension};
+use bevy::render::texture::TextureFormatPixelInfo;
 
 use crate::prelude::*;
 
@@ -29,8 +31,12 @@ pub fn initialize_tileset_actor_resource(
     commands.insert_resource(TilesetActor(atlas_handle, img_handle));
 }
 
+/// Initializes the terrain tileset, the structure tileset being appended to
+/// its right (see `append_tileset`).
 pub fn initialize_tileset_terrain_resource(
-    handle: &UntypedHandle,
+    terrain: &UntypedHandle,
+    structure: &UntypedHandle,
+    images: &mut ResMut<Assets<Image>>,
     texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
     commands: &mut Commands,
 ) {
@@ -42,6 +48,91 @@ pub fn initialize_tileset_terrain_resource(
         None,
     );
     let atlas_handle = texture_atlases.add(texture_atlas);
-    let img_handle: Handle<Image> = handle.clone().typed();
+    let image = images
+        .get(terrain.clone().typed::<Image>())
+        .zip(images.get(structure.clone().typed::<Image>()))
+        .and_then(|(terrain, structure)| append_tileset(terrain, structure))
+        .expect("terrain and structure tilesets can't be appended");
+    let img_handle = images.add(image);
     commands.insert_resource(TilesetTerrain(atlas_handle, img_handle));
 }
+
+/// Appends the columns of a tileset to the right of another one, so both are
+/// used as a single tileset.
+///
+/// # Returns
+///
+/// The appended tilesets, or `None` if their heights are different or if the
+/// second tileset can't be converted to the format of the first one.
+pub fn append_tileset(left: &Image, right: &Image) -> Option<Image> {
+    let format = left.texture_descriptor.format;
+    let right = if right.texture_descriptor.format == format {
+        right.clone()
+    } else {
+        right.convert(format)?
+    };
+    if left.height() != right.height() {
+        return None;
+    }
+
+    let row_left = usize::try_from(left.width()).ok()? * format.pixel_size();
+    let row_right = usize::try_from(right.width()).ok()? * format.pixel_size();
+    let data = left
+        .data
+        .chunks(row_left)
+        .zip(right.data.chunks(row_right))
+        .flat_map(|(row_left, row_right)| row_left.iter().chain(row_right))
+        .copied()
+        .collect();
+
+    let mut image = Image::new(
+        Extent3d {
+            width: left.width() + right.width(),
+            height: left.height(),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        left.asset_usage,
+    );
+    image.sampler = left.sampler.clone();
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::render_asset::RenderAssetUsages;
+    use bevy::render::render_resource::TextureFormat;
+
+    fn create_image(width: u32, height: u32, value: u8) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[value, value, value, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    #[test]
+    fn test_append_tileset() {
+        let image =
+            append_tileset(&create_image(2, 2, 10), &create_image(1, 2, 20))
+                .unwrap();
+        assert_eq!((3, 2), (image.width(), image.height()));
+        let reds: Vec<_> = image.data.chunks(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(vec![10, 10, 20, 10, 10, 20], reds);
+
+        assert!(append_tileset(
+            &create_image(2, 2, 10),
+            &create_image(2, 1, 20)
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,971 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::prelude::*;
+
+/// Represents the state of the player in a saved run, restored when the
+/// player is spawned (see `spawn_mobs_on_current_map`).
+#[derive(Clone, Resource)]
+pub struct SavedPlayer {
+    pub position: MapPosition,
+    pub health: Health,
+    pub inventory: Inventory,
+    pub cooldown: MovementCooldown,
+    pub search_streak: SearchStreak,
+    pub energy: Energy,
+}
+
+/// Represents a run saved in a file from the pause menu, and continued from
+/// the main menu.
+///
+/// The save file is made of sections, each one starting with its name
+/// between brackets, followed by one property per line:
+///
+/// ```text
+/// [run]
+/// seed = 42
+/// turn = 120
+/// rng = 35 12
+/// map = 1
+/// last_exit = 0 3,4 stairs 1
+///
+/// [player]
+/// position = 2,0
+/// health = 12/20
+/// cooldown = 0
+/// search_streak = 0
+/// energy = 0
+/// inventory = key
+///
+/// [overworld]
+/// size = 2x1
+/// position = 0,0
+/// locations = forest 1; cave
+///
+/// [map 0]
+/// depth = 0
+/// location = none
+/// mobs =
+///
+/// [map 1]
+/// depth = 0
+/// location = forest
+/// mobs = blob 1,0 5/8 0 50; rabbit 0,0 4/4 2 0
+/// size = 3x1
+/// tiles = 0 0 6
+/// remembered = 011
+/// entrance = 0,0
+/// exits = 2,0 right 0
+/// mob_spawns =
+/// item_spawns =
+/// items = key 1,0
+/// traps = spike 0,0 hidden
+/// ```
+///
+/// The maps of the `WorldGraph` follow in the order of their ids, and the
+/// properties from `size` onwards are only set for the maps left by the
+/// player (see `WorldGraph::store_map`). The lists are separated by `;`, the
+/// tiles are the ids of their kinds, and the exits are written as their
+/// position, their edge (or `stairs`) and their destination (or `none`). The
+/// mobs are written as their kind, their position, their health, their
+/// cooldown and their energy. The
+/// `rng` property is the position of the actors behavior and combat
+/// generators (see `RunRngPosition`).
+pub struct SavedRun {
+    pub seed: RunSeed,
+    pub turn: usize,
+    pub rng: RunRngPosition,
+    /// The id of the map where the player is.
+    pub map: usize,
+    pub last_exit: Option<LastMapExit>,
+    pub player: SavedPlayer,
+    pub overworld: Overworld,
+    pub world: WorldGraph,
+}
+
+/// The names of the actor kinds in save files.
+const ACTOR_KINDS: [(ActorKind, &str); 3] = [
+    (ActorKind::Blob, "blob"),
+    (ActorKind::Rabbit, "rabbit"),
+    (ActorKind::Player, "player"),
+];
+
+/// The names of the item kinds in save files.
+const ITEM_KINDS: [(ItemKind, &str); 1] = [(ItemKind::Key, "key")];
+
+/// The names of the trap kinds in save files.
+const TRAP_KINDS: [(TrapKind, &str); 4] = [
+    (TrapKind::Spike, "spike"),
+    (TrapKind::Snare, "snare"),
+    (TrapKind::Teleport, "teleport"),
+    (TrapKind::Alarm, "alarm"),
+];
+
+/// The names of the location kinds in save files.
+const LOCATION_KINDS: [(LocationKind, &str); 3] = [
+    (LocationKind::Forest, "forest"),
+    (LocationKind::Cave, "cave"),
+    (LocationKind::Ruins, "ruins"),
+];
+
+/// The names of the exits' edges in save files, `None` being the stairs.
+const MAP_EDGES: [(Option<MapEdge>, &str); 5] = [
+    (Some(MapEdge::Left), "left"),
+    (Some(MapEdge::Right), "right"),
+    (Some(MapEdge::Top), "top"),
+    (Some(MapEdge::Bottom), "bottom"),
+    (None, "stairs"),
+];
+
+/// Returns the name of a value in a table of names, e.g. `ITEM_KINDS`.
+fn name_of<T: PartialEq>(
+    names: &[(T, &'static str)],
+    value: &T,
+) -> &'static str {
+    names
+        .iter()
+        .find(|(v, _)| v == value)
+        .map(|(_, name)| *name)
+        .expect("value without a name")
+}
+
+/// Formats a position as `x,y`.
+fn format_position(pos: &MapPosition) -> String {
+    format!("{},{}", pos.x, pos.y)
+}
+
+/// Formats health points as `current/max`.
+fn format_health(health: Health) -> String {
+    format!("{}/{}", health.current, health.max)
+}
+
+/// Formats an exit as its position, its edge and its destination.
+fn format_exit(exit: &MapExit) -> String {
+    format!(
+        "{} {} {}",
+        format_position(&exit.position),
+        name_of(&MAP_EDGES, &exit.edge),
+        exit.destination
+            .map_or_else(|| "none".into(), |id| id.to_string())
+    )
+}
+
+/// Formats a list of values separated by `;`.
+fn format_list<T>(values: &[T], format: impl Fn(&T) -> String) -> String {
+    values.iter().map(format).collect::<Vec<_>>().join("; ")
+}
+
+/// Writes a `property = value` line.
+fn write_property(
+    f: &mut fmt::Formatter,
+    key: &str,
+    value: &str,
+) -> fmt::Result {
+    if value.is_empty() {
+        writeln!(f, "{key} =")
+    } else {
+        writeln!(f, "{key} = {value}")
+    }
+}
+
+/// Writes the map stored in a `[map N]` section.
+fn write_map(f: &mut fmt::Formatter, map: &Map) -> fmt::Result {
+    let tiles: Vec<String> = map
+        .tiles
+        .iter()
+        .map(|tile| tile.kind.0.to_string())
+        .collect();
+    let remembered: String = map
+        .remembered
+        .iter()
+        .map(|remembered| if *remembered { '1' } else { '0' })
+        .collect();
+
+    write_property(f, "size", &format!("{}x{}", map.width, map.height))?;
+    write_property(f, "tiles", &tiles.join(" "))?;
+    write_property(f, "remembered", &remembered)?;
+    write_property(
+        f,
+        "entrance",
+        &map.entrance
+            .as_ref()
+            .map_or_else(|| "none".into(), format_position),
+    )?;
+    write_property(f, "exits", &format_list(&map.exits, format_exit))?;
+    write_property(
+        f,
+        "mob_spawns",
+        &format_list(&map.mob_spawns, format_position),
+    )?;
+    write_property(
+        f,
+        "item_spawns",
+        &format_list(&map.item_spawns, format_position),
+    )?;
+    write_property(
+        f,
+        "items",
+        &format_list(&map.items, |item| {
+            format!(
+                "{} {}",
+                name_of(&ITEM_KINDS, &item.kind),
+                format_position(&item.position)
+            )
+        }),
+    )?;
+    write_property(
+        f,
+        "traps",
+        &format_list(&map.traps, |trap| {
+            format!(
+                "{} {} {}",
+                name_of(&TRAP_KINDS, &trap.kind),
+                format_position(&trap.position),
+                if trap.hidden { "hidden" } else { "revealed" }
+            )
+        }),
+    )
+}
+
+impl fmt::Display for SavedRun {
+    /// Writes the run in the format parsed by `SavedRun::from_str`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[run]")?;
+        write_property(f, "seed", &self.seed.0.to_string())?;
+        write_property(f, "turn", &self.turn.to_string())?;
+        write_property(
+            f,
+            "rng",
+            &format!("{} {}", self.rng.ai, self.rng.combat),
+        )?;
+        write_property(f, "map", &self.map.to_string())?;
+        if let Some(last_exit) = &self.last_exit {
+            write_property(
+                f,
+                "last_exit",
+                &format!("{} {}", last_exit.map, format_exit(&last_exit.exit)),
+            )?;
+        }
+
+        let player = &self.player;
+        writeln!(f, "\n[player]")?;
+        write_property(f, "position", &format_position(&player.position))?;
+        write_property(f, "health", &format_health(player.health))?;
+        write_property(f, "cooldown", &player.cooldown.0.to_string())?;
+        write_property(
+            f,
+            "search_streak",
+            &player.search_streak.0.to_string(),
+        )?;
+        write_property(f, "energy", &player.energy.0.to_string())?;
+        write_property(
+            f,
+            "inventory",
+            &format_list(&player.inventory.0, |item| {
+                name_of(&ITEM_KINDS, item).into()
+            }),
+        )?;
+
+        let overworld = &self.overworld;
+        writeln!(f, "\n[overworld]")?;
+        write_property(
+            f,
+            "size",
+            &format!("{}x{}", overworld.width, overworld.height),
+        )?;
+        write_property(f, "position", &format_position(&overworld.position))?;
+        write_property(
+            f,
+            "locations",
+            &format_list(&overworld.locations, |location| {
+                let kind = name_of(&LOCATION_KINDS, &location.kind);
+                location
+                    .map
+                    .map_or_else(|| kind.into(), |map| format!("{kind} {map}"))
+            }),
+        )?;
+
+        for (id, node) in self.world.nodes.iter().enumerate() {
+            writeln!(f, "\n[map {id}]")?;
+            write_property(f, "depth", &node.depth.to_string())?;
+            write_property(
+                f,
+                "location",
+                node.location
+                    .as_ref()
+                    .map_or("none", |kind| name_of(&LOCATION_KINDS, kind)),
+            )?;
+            write_property(
+                f,
+                "mobs",
+                &format_list(&node.mobs, |mob| {
+                    format!(
+                        "{} {} {} {} {}",
+                        name_of(&ACTOR_KINDS, &mob.actor.kind),
+                        format_position(&mob.position),
+                        format_health(mob.health),
+                        mob.cooldown.0,
+                        mob.energy.0
+                    )
+                }),
+            )?;
+            if let Some(map) = &node.map {
+                write_map(f, map)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a number.
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number '{value}'"))
+}
+
+/// Parses a `x,y` position.
+fn parse_position(value: &str) -> Result<MapPosition, String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("invalid position '{value}'"))?;
+    Ok(MapPosition::new(
+        parse_number(x.trim())?,
+        parse_number(y.trim())?,
+    ))
+}
+
+/// Checks that a position is on a map of a given size.
+fn check_position(
+    pos: MapPosition,
+    (width, height): (usize, usize),
+) -> Result<MapPosition, String> {
+    if pos.x >= width || pos.y >= height {
+        return Err(format!(
+            "position '{}' outside of the {width}x{height} map",
+            format_position(&pos)
+        ));
+    }
+    Ok(pos)
+}
+
+/// Parses a `x,y` position which must be on a map of a given size.
+fn parse_position_in(
+    value: &str,
+    size: (usize, usize),
+) -> Result<MapPosition, String> {
+    check_position(parse_position(value)?, size)
+}
+
+/// Parses the id of a map, which must be one of the `node_count` maps of the
+/// saved `WorldGraph`.
+fn parse_map_id(value: &str, node_count: usize) -> Result<usize, String> {
+    let id = parse_number(value)?;
+    if id >= node_count {
+        return Err(format!("unknown map '{value}'"));
+    }
+    Ok(id)
+}
+
+/// Parses `current/max` health points.
+fn parse_health(value: &str) -> Result<Health, String> {
+    let (current, max) = value
+        .split_once('/')
+        .ok_or_else(|| format!("invalid health '{value}'"))?;
+    Ok(Health {
+        current: parse_number(current)?,
+        max: parse_number(max)?,
+    })
+}
+
+/// Parses a `WxH` size.
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("invalid size '{value}'"))?;
+    Ok((parse_number(width)?, parse_number(height)?))
+}
+
+/// Parses a name from a table of names, e.g. `ITEM_KINDS`.
+fn parse_name<T: Copy>(
+    names: &[(T, &str)],
+    what: &str,
+    value: &str,
+) -> Result<T, String> {
+    names
+        .iter()
+        .find(|(_, name)| *name == value)
+        .map(|(kind, _)| *kind)
+        .ok_or_else(|| format!("unknown {what} '{value}'"))
+}
+
+/// Parses a value which can be `none`.
+fn parse_optional<T>(
+    value: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    if value == "none" {
+        Ok(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+/// Parses a list of values separated by `;`.
+fn parse_list<T>(
+    value: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(parse)
+        .collect()
+}
+
+/// Splits a value made of a given number of words separated by spaces.
+fn split_words<const N: usize>(value: &str) -> Result<[&str; N], String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    words
+        .try_into()
+        .map_err(|_| format!("expected {N} words in '{value}'"))
+}
+
+/// Parses an exit written as its position, its edge and its destination.
+fn parse_exit(value: &str, node_count: usize) -> Result<MapExit, String> {
+    let [position, edge, destination] = split_words(value)?;
+    Ok(MapExit {
+        position: parse_position(position)?,
+        edge: parse_name(&MAP_EDGES, "edge", edge)?,
+        destination: parse_optional(destination, |id| {
+            parse_map_id(id, node_count)
+        })?,
+    })
+}
+
+/// Parses the generators position written as `ai combat`.
+fn parse_rng_position(value: &str) -> Result<RunRngPosition, String> {
+    let [ai, combat] = split_words(value)?;
+    Ok(RunRngPosition {
+        ai: parse_number(ai)?,
+        combat: parse_number(combat)?,
+    })
+}
+
+/// Parses the `[run]` section of a run with `node_count` maps.
+fn parse_run(
+    section: &IniSection,
+    node_count: usize,
+) -> Result<(RunSeed, usize, RunRngPosition, usize, Option<LastMapExit>), String>
+{
+    let last_exit = if section.has("last_exit") {
+        Some(section.parse("last_exit", |value| {
+            let (map, exit) = value
+                .split_once(' ')
+                .ok_or_else(|| format!("invalid exit '{value}'"))?;
+            Ok(LastMapExit {
+                map: parse_map_id(map, node_count)?,
+                exit: parse_exit(exit.trim(), node_count)?,
+            })
+        })?)
+    } else {
+        None
+    };
+    Ok((
+        RunSeed(section.parse("seed", parse_number)?),
+        section.parse("turn", parse_number)?,
+        section.parse("rng", parse_rng_position)?,
+        section.parse("map", parse_number)?,
+        last_exit,
+    ))
+}
+
+/// Parses the `[player]` section, the player being on a map of a given size.
+fn parse_player(
+    section: &IniSection,
+    map_size: (usize, usize),
+) -> Result<SavedPlayer, String> {
+    Ok(SavedPlayer {
+        position: section
+            .parse("position", |value| parse_position_in(value, map_size))?,
+        health: section.parse("health", parse_health)?,
+        inventory: Inventory(section.parse("inventory", |value| {
+            parse_list(value, |item| parse_name(&ITEM_KINDS, "item", item))
+        })?),
+        cooldown: MovementCooldown(section.parse("cooldown", parse_number)?),
+        search_streak: SearchStreak(
+            section.parse("search_streak", parse_number)?,
+        ),
+        energy: Energy(section.parse("energy", parse_number)?),
+    })
+}
+
+/// Parses the `[overworld]` section of a run with `node_count` maps.
+fn parse_overworld(
+    section: &IniSection,
+    node_count: usize,
+) -> Result<Overworld, String> {
+    let (width, height) = section.parse("size", parse_size)?;
+    let locations = section.parse("locations", |value| {
+        let locations = parse_list(value, |location| {
+            let (kind, map) = location
+                .split_once(' ')
+                .map_or((location, None), |(kind, map)| (kind, Some(map)));
+            Ok(Location {
+                kind: parse_name(&LOCATION_KINDS, "location", kind)?,
+                map: map
+                    .map(|map| parse_map_id(map.trim(), node_count))
+                    .transpose()?,
+            })
+        })?;
+        if locations.len() != width * height {
+            return Err(format!(
+                "expected {} locations, found {}",
+                width * height,
+                locations.len()
+            ));
+        }
+        Ok(locations)
+    })?;
+    Ok(Overworld {
+        width,
+        height,
+        locations,
+        position: section.parse("position", |value| {
+            parse_position_in(value, (width, height))
+        })?,
+    })
+}
+
+/// Parses a `[map N]` section of a run with `node_count` maps. The mobs
+/// must be on the stored map, if any.
+fn parse_world_node(
+    section: &IniSection,
+    node_count: usize,
+) -> Result<WorldNode, String> {
+    let map = if section.has("size") {
+        Some(parse_map(section, node_count)?)
+    } else {
+        None
+    };
+    let mobs = section.parse("mobs", |value| {
+        parse_list(value, |mob| {
+            let [kind, position, health, cooldown, energy] = split_words(mob)?;
+            let position = parse_position(position)?;
+            Ok(StoredMob {
+                actor: Actor::new(parse_name(&ACTOR_KINDS, "actor", kind)?),
+                position: match &map {
+                    Some(map) => {
+                        check_position(position, (map.width, map.height))?
+                    }
+                    None => position,
+                },
+                health: parse_health(health)?,
+                cooldown: MovementCooldown(parse_number(cooldown)?),
+                energy: Energy(parse_number(energy)?),
+            })
+        })
+    })?;
+    Ok(WorldNode {
+        depth: section.parse("depth", parse_number)?,
+        location: section.parse("location", |value| {
+            parse_optional(value, |kind| {
+                parse_name(&LOCATION_KINDS, "location", kind)
+            })
+        })?,
+        map,
+        mobs,
+    })
+}
+
+/// Parses the map stored in a `[map N]` section of a run with `node_count`
+/// maps.
+fn parse_map(section: &IniSection, node_count: usize) -> Result<Map, String> {
+    let (width, height) = section.parse("size", parse_size)?;
+    let size = (width, height);
+    let tiles = section.parse("tiles", |value| {
+        let tiles = value
+            .split_whitespace()
+            .map(|id| Ok(Tile::from_kind(TileKind(parse_number(id)?))))
+            .collect::<Result<Vec<_>, String>>()?;
+        if tiles.len() != width * height {
+            return Err(format!(
+                "expected {} tiles, found {}",
+                width * height,
+                tiles.len()
+            ));
+        }
+        Ok(tiles)
+    })?;
+    let remembered = section.parse("remembered", |value| {
+        let remembered = value
+            .chars()
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(format!("invalid remembered tiles '{value}'")),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if remembered.len() != width * height {
+            return Err(format!(
+                "expected {} remembered tiles, found {}",
+                width * height,
+                remembered.len()
+            ));
+        }
+        Ok(remembered)
+    })?;
+    let positions =
+        |value| parse_list(value, |pos| parse_position_in(pos, size));
+
+    Ok(Map {
+        width,
+        height,
+        tiles,
+        entrance: section.parse("entrance", |value| {
+            parse_optional(value, |pos| parse_position_in(pos, size))
+        })?,
+        exits: section.parse("exits", |value| {
+            parse_list(value, |exit| {
+                let exit = parse_exit(exit, node_count)?;
+                check_position(exit.position, size)?;
+                Ok(exit)
+            })
+        })?,
+        mob_spawns: section.parse("mob_spawns", positions)?,
+        item_spawns: section.parse("item_spawns", positions)?,
+        items: section.parse("items", |value| {
+            parse_list(value, |item| {
+                let [kind, position] = split_words(item)?;
+                Ok(MapItem {
+                    kind: parse_name(&ITEM_KINDS, "item", kind)?,
+                    position: parse_position_in(position, size)?,
+                })
+            })
+        })?,
+        traps: section.parse("traps", |value| {
+            parse_list(value, |trap| {
+                let [kind, position, hidden] = split_words(trap)?;
+                Ok(MapTrap {
+                    kind: parse_name(&TRAP_KINDS, "trap", kind)?,
+                    position: parse_position_in(position, size)?,
+                    hidden: parse_name(
+                        &[(true, "hidden"), (false, "revealed")],
+                        "trap state",
+                        hidden,
+                    )?,
+                })
+            })
+        })?,
+        remembered,
+        ..Default::default()
+    })
+}
+
+impl FromStr for SavedRun {
+    type Err = String;
+
+    /// Parses and validates a run written as text.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sections = parse_sections(s)?;
+
+        let section = |name: &str| {
+            sections
+                .iter()
+                .find(|section| section.name == name)
+                .ok_or_else(|| format!("missing section '{name}'"))
+        };
+        let node_count = sections
+            .iter()
+            .filter(|section| section.name.starts_with("map "))
+            .count();
+        let (seed, turn, rng, map, last_exit) =
+            parse_run(section("run")?, node_count)?;
+        let overworld = parse_overworld(section("overworld")?, node_count)?;
+
+        let mut world = WorldGraph { nodes: vec![] };
+        for section in &sections {
+            let Some(id) = section.name.strip_prefix("map ") else {
+                continue;
+            };
+            if id.trim() != world.nodes.len().to_string() {
+                return Err(format!(
+                    "line {}: expected map {}",
+                    section.line_number,
+                    world.nodes.len()
+                ));
+            }
+            world.nodes.push(parse_world_node(section, node_count)?);
+        }
+        let Some(current) = world.nodes.get(map).and_then(|n| n.map.as_ref())
+        else {
+            return Err(format!("map {map} isn't saved"));
+        };
+        let player =
+            parse_player(section("player")?, (current.width, current.height))?;
+
+        Ok(Self {
+            seed,
+            turn,
+            rng,
+            map,
+            last_exit,
+            player,
+            overworld,
+            world,
+        })
+    }
+}
+
+impl SavedRun {
+    /// Returns whether a saved run can be continued.
+    pub fn exists() -> bool {
+        Path::new(SAVE_FILE).exists()
+    }
+
+    /// Loads and validates the saved run of a file, its tiles being checked
+    /// against `registry`.
+    pub fn load(path: &Path, registry: &TileRegistry) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| text.parse::<Self>())
+            .and_then(|run| run.check_tiles(registry).map(|()| run))
+            .map_err(|e| format!("invalid save {}: {e}", path.display()))
+    }
+
+    /// Checks that the tiles of the saved maps are all defined in `registry`.
+    fn check_tiles(&self, registry: &TileRegistry) -> Result<(), String> {
+        for (id, node) in self.world.nodes.iter().enumerate() {
+            let Some(map) = &node.map else {
+                continue;
+            };
+            if let Some(tile) = map
+                .tiles
+                .iter()
+                .find(|tile| registry.get(tile.kind).is_none())
+            {
+                return Err(format!(
+                    "map {id}: unknown tile kind '{}'",
+                    tile.kind.0
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the save file, so a run can't be continued twice from the same
+    /// save.
+    pub fn remove() {
+        if let Err(e) = fs::remove_file(SAVE_FILE) {
+            error!("failed to remove {SAVE_FILE}: {e}");
+        }
+    }
+
+    /// Writes the run in a file, replacing the previous save.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string())
+            .map_err(|e| format!("failed to save {}: {e}", path.display()))
+    }
+
+    /// Replaces the resources of the current run with the saved ones. The
+    /// player is spawned from the `SavedPlayer` when the current map is
+    /// initialized.
+    pub fn resume(self, commands: &mut Commands) {
+        commands.insert_resource(self.seed);
+        commands.insert_resource(RunRng::resumed(self.seed, self.rng));
+        commands.insert_resource(CurrentTurnNumber(self.turn));
+        commands.insert_resource(CurrentMapNumber(self.map));
+        commands.insert_resource(self.overworld);
+        commands.insert_resource(self.world);
+        commands.insert_resource(self.player);
+        match self.last_exit {
+            Some(last_exit) => commands.insert_resource(last_exit),
+            None => commands.remove_resource::<LastMapExit>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVE: &str = "\
+[run]
+seed = 42
+turn = 120
+rng = 35 12
+map = 1
+last_exit = 0 0,0 stairs 1
+
+[player]
+position = 2,0
+health = 12/20
+cooldown = 1
+search_streak = 2
+energy = 20
+inventory = key
+
+[overworld]
+size = 2x1
+position = 0,0
+locations = forest 1; cave
+
+[map 0]
+depth = 0
+location = none
+mobs =
+
+[map 1]
+depth = 0
+location = forest
+mobs = blob 1,0 5/8 0 50; rabbit 0,0 4/4 2 0
+size = 3x1
+tiles = 0 0 6
+remembered = 011
+entrance = 0,0
+exits = 2,0 right 0
+mob_spawns =
+item_spawns = 1,0
+items = key 1,0
+traps = spike 0,0 hidden; alarm 2,0 revealed
+";
+
+    #[test]
+    fn test_parse_saved_run() {
+        let run: SavedRun = SAVE.parse().unwrap();
+        assert_eq!(RunSeed(42), run.seed);
+        assert_eq!((120, 1), (run.turn, run.map));
+        assert_eq!(RunRngPosition { ai: 35, combat: 12 }, run.rng);
+
+        let last_exit = run.last_exit.unwrap();
+        assert_eq!(0, last_exit.map);
+        assert_eq!(None, last_exit.exit.edge);
+        assert_eq!(Some(1), last_exit.exit.destination);
+
+        assert_eq!(MapPosition::new(2, 0), run.player.position);
+        assert_eq!(
+            Health {
+                current: 12,
+                max: 20
+            },
+            run.player.health
+        );
+        assert_eq!(vec![ItemKind::Key], run.player.inventory.0);
+        assert_eq!(1, run.player.cooldown.0);
+        assert_eq!(2, run.player.search_streak.0);
+        assert_eq!(Energy(20), run.player.energy);
+
+        assert_eq!(2, run.overworld.locations.len());
+        assert_eq!(Some(1), run.overworld.locations[0].map);
+        assert_eq!(LocationKind::Cave, run.overworld.locations[1].kind);
+
+        assert_eq!(2, run.world.nodes.len());
+        assert!(run.world.nodes[0].map.is_none());
+        let node = &run.world.nodes[1];
+        assert_eq!(Some(LocationKind::Forest), node.location);
+        assert_eq!(2, node.mobs.len());
+        assert_eq!(MapPosition::new(1, 0), node.mobs[0].position);
+        assert_eq!(Health { current: 5, max: 8 }, node.mobs[0].health);
+        assert_eq!(2, node.mobs[1].cooldown.0);
+        assert_eq!(Energy(50), node.mobs[0].energy);
+
+        let map = node.map.as_ref().unwrap();
+        assert_eq!(TileKind::FLOOR, map.tiles[2].kind);
+        assert_eq!(vec![false, true, true], map.remembered);
+        assert_eq!(Some(MapEdge::Right), map.exits[0].edge);
+        assert_eq!(vec![MapPosition::new(1, 0)], map.item_spawns);
+        assert!(map.mob_spawns.is_empty());
+        assert_eq!(ItemKind::Key, map.items[0].kind);
+        assert!(map.traps[0].hidden);
+        assert_eq!(TrapKind::Alarm, map.traps[1].kind);
+        assert!(!map.traps[1].hidden);
+    }
+
+    #[test]
+    fn test_write_saved_run() {
+        let run: SavedRun = SAVE.parse().unwrap();
+        assert_eq!(SAVE, run.to_string());
+    }
+
+    #[test]
+    fn test_parse_saved_run_errors() {
+        let cases = [
+            ("seed = 42", "seed = 4x2", "line 2: invalid number '4x2'"),
+            (
+                "position = 2,0",
+                "position = 2",
+                "line 9: invalid position '2'",
+            ),
+            (
+                "inventory = key",
+                "inventory = gem",
+                "line 14: unknown item 'gem'",
+            ),
+            (
+                "tiles = 0 0 6",
+                "tiles = 0 0",
+                "line 31: expected 3 tiles, found 2",
+            ),
+            ("[map 1]", "[map 2]", "line 26: expected map 1"),
+            ("map = 1", "map = 0", "map 0 isn't saved"),
+            (
+                "cooldown = 1\n",
+                "",
+                "line 8: section 'player' has no property 'cooldown'",
+            ),
+            ("[overworld]", "[world]", "missing section 'overworld'"),
+            (
+                "position = 2,0",
+                "position = 3,0",
+                "line 9: position '3,0' outside of the 3x1 map",
+            ),
+            (
+                "blob 1,0",
+                "blob 4,0",
+                "line 29: position '4,0' outside of the 3x1 map",
+            ),
+            (
+                "remembered = 011",
+                "remembered = 01",
+                "line 32: expected 3 remembered tiles, found 2",
+            ),
+            (
+                "exits = 2,0 right 0",
+                "exits = 2,1 right 0",
+                "line 34: position '2,1' outside of the 3x1 map",
+            ),
+            (
+                "exits = 2,0 right 0",
+                "exits = 2,0 right 2",
+                "line 34: unknown map '2'",
+            ),
+            (
+                "items = key 1,0",
+                "items = key 1,2",
+                "line 37: position '1,2' outside of the 3x1 map",
+            ),
+            (
+                "spike 0,0",
+                "spike 0,3",
+                "line 38: position '0,3' outside of the 3x1 map",
+            ),
+            (
+                "last_exit = 0 0,0 stairs 1",
+                "last_exit = 2 0,0 stairs 1",
+                "line 6: unknown map '2'",
+            ),
+            (
+                "locations = forest 1",
+                "locations = forest 5",
+                "line 19: unknown map '5'",
+            ),
+        ];
+        for (from, to, error) in cases {
+            let save = SAVE.replacen(from, to, 1);
+            assert_eq!(Err(error.into()), save.parse::<SavedRun>().map(|_| ()));
+        }
+    }
+}
@@ -4,16 +4,29 @@ use crate::prelude::*;
 /// loading, game switching, etc.
 ///
 /// The lifecycle of the game is:
-/// 1. `LoadingAssets` -> `InGame`
-/// 2. `InGame` -> `Finished`
+/// 1. `LoadingAssets` -> `MainMenu`
+/// 2.
+///   1. `MainMenu` -> `InGame`
+///   2. `MainMenu` -> `Finished`
+/// 3.
+///   1. `InGame` -> `GameOver`
+///   2. `InGame` -> `Finished`
+/// 4.
+///   1. `GameOver` -> `InGame` (back to step 3, with a new run)
+///   2. `GameOver` -> `Finished`
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum AppState {
     /// First state for the whole application, consisting of loading the
     /// assets and creating the game resources.
     #[default]
     LoadingAssets,
+    /// Corresponds to the title screen, where a run is started or continued.
+    MainMenu,
     /// Corresponds to the main game state.
     InGame,
+    /// Corresponds to the screen displayed once the player died, from which a
+    /// new run can be started.
+    GameOver,
     /// This state is used to exit the application cleanly, performing
     /// potential resources cleanup.
     Finished,
@@ -34,6 +47,9 @@ pub enum AppState {
 ///   1. `EnemyTurn` -> `PlayerTurn` (back to step 4.1)
 ///   2. `CleanupActors` -> `CleanupMap`
 /// 6. `CleanupMap` -> `InitializingMap` (back to step 2)
+///
+/// When the player dies, the game state goes back to `Uninitialized` until a
+/// new run is started.
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     /// Corresponds to the default state, before the game is running.
@@ -45,7 +61,9 @@ pub enum GameState {
     InitializingActors,
     /// Corresponds to the turn when the player can do a move or an action.
     PlayerTurn,
-    /// Corresponds to the turn when the enemies can do a move or an action.
+    /// Corresponds to the turn when the enemies can do a move or an action,
+    /// as many times as their speed allows until the player can act again
+    /// (see `schedule_turns`).
     EnemyTurn,
     /// Corresponds to the map cleanup (spawned entities removal).
     CleanupMap,
@@ -75,3 +93,16 @@ impl ExecutionMode {
         }
     }
 }
+
+/// States used for suspending the game while the pause menu is displayed.
+/// The game state is left untouched, so the game resumes exactly where it was
+/// paused.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum PauseState {
+    /// Corresponds to the game being played.
+    #[default]
+    Running,
+    /// Corresponds to the pause menu being displayed, while the player's
+    /// input is ignored.
+    Paused,
+}
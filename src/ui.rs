@@ -16,6 +16,11 @@ impl Plugin for UiPlugin {
     }
 }
 
+/// Marker component for the ui elements displayed during the game, removed
+/// when a new run is started.
+#[derive(Component)]
+pub struct UiHud;
+
 /// Marker component to represent the ui element to display the current turn
 /// number.
 #[derive(Component)]
@@ -26,19 +31,25 @@ pub struct UiCurrentTurnText;
 #[derive(Component)]
 pub struct UiCurrentMapText;
 
+/// Marker component to represent the ui element to display the run seed.
+#[derive(Component)]
+pub struct UiRunSeedText;
+
 /// Creates components for the ui elements.
 pub fn setup_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     current_turn_number: Res<CurrentTurnNumber>,
     current_map_number: Res<CurrentMapNumber>,
+    run_seed: Res<RunSeed>,
 ) {
     commands.spawn((
+        UiHud,
         UiCurrentTurnText,
         TextBundle::from_section(
             format!("Turn {}", current_turn_number.0),
             TextStyle {
-                font: asset_server.load("fonts/GABOED.ttf"),
+                font: asset_server.load(UI_FONT),
                 font_size: UI_TEXT_TURN_SIZE,
                 color: UI_TEXT_TURN_COLOR,
             },
@@ -46,11 +57,12 @@ pub fn setup_ui(
     ));
 
     commands.spawn((
+        UiHud,
         UiCurrentMapText,
         TextBundle::from_section(
             format!("Map {}", current_map_number.0),
             TextStyle {
-                font: asset_server.load("fonts/GABOED.ttf"),
+                font: asset_server.load(UI_FONT),
                 font_size: UI_TEXT_TURN_SIZE,
                 color: UI_TEXT_TURN_COLOR,
             },
@@ -62,6 +74,25 @@ pub fn setup_ui(
             ..default()
         }),
     ));
+
+    commands.spawn((
+        UiHud,
+        UiRunSeedText,
+        TextBundle::from_section(
+            format!("Seed {}", run_seed.0),
+            TextStyle {
+                font: asset_server.load(UI_FONT),
+                font_size: UI_TEXT_TURN_SIZE,
+                color: UI_TEXT_TURN_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(0.0),
+            left: Val::Px(0.0),
+            ..default()
+        }),
+    ));
 }
 
 /// Updates the ui element which represents the current turn.